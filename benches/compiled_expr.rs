@@ -0,0 +1,57 @@
+//! Compares evaluating the same expression repeatedly from its string form
+//! with evaluating a [CompiledExpr] built once.
+//!
+//! Run with `cargo bench --bench compiled_expr`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::Instant;
+
+use ad::expression::ast::Expr;
+use ad::expression::compiled::CompiledExpr;
+use ad::expression::functions::Functions;
+use ad::expression::shunting_yard::shunting_yard;
+use ad::expression::value::Value;
+
+const ITERATIONS: u32 = 200_000;
+
+fn measure(name: &str, mut body: impl FnMut(u32)) {
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        body(i);
+    }
+    let elapsed = start.elapsed();
+    let per_iteration = elapsed / ITERATIONS;
+    println!("{:<40} {:>10.2?} total {:>10?} per evaluation", name, elapsed, per_iteration);
+}
+
+fn main() {
+    let functions = Functions::with_builtins();
+
+    let constant = "max(3, 9, 4) * (2 + 4) ^ 2 - gcd(84, 231)";
+    measure("shunting_yard (constant)", |_| {
+        black_box(shunting_yard(black_box(constant)));
+    });
+    let mut compiled = CompiledExpr::compile(constant, &functions).unwrap();
+    measure("CompiledExpr::evaluate (constant)", |_| {
+        black_box(compiled.evaluate(black_box(&[])).unwrap());
+    });
+
+    let formula = "(a > 3 && b <= 10) || c == 0 ? a * b + max(a, b, c) : a - b";
+    let mut variables = HashMap::new();
+    measure("Expr::parse + evaluate (variables)", |i| {
+        variables.insert("a".to_string(), Value::Int((i % 7) as i32));
+        variables.insert("b".to_string(), Value::Int((i % 13) as i32));
+        variables.insert("c".to_string(), Value::Int((i % 3) as i32));
+        black_box(Expr::parse(black_box(formula)).unwrap().evaluate(&functions, &variables).unwrap());
+    });
+    let mut compiled = CompiledExpr::compile(formula, &functions).unwrap();
+    let (a, b, c) = (compiled.slot("a").unwrap(), compiled.slot("b").unwrap(), compiled.slot("c").unwrap());
+    let mut values = vec![Value::Int(0); compiled.variables().len()];
+    measure("CompiledExpr::evaluate (variables)", |i| {
+        values[a] = Value::Int((i % 7) as i32);
+        values[b] = Value::Int((i % 13) as i32);
+        values[c] = Value::Int((i % 3) as i32);
+        black_box(compiled.evaluate(black_box(&values)).unwrap());
+    });
+}
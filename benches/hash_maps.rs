@@ -0,0 +1,77 @@
+//! Compares the maps of the hash module on the same workload through the [Map] trait,
+//! and the hash functions of the module by how evenly they spread keys over buckets.
+//!
+//! Run with `cargo bench --bench hash_maps`.
+
+use std::hash::BuildHasher;
+use std::hint::black_box;
+use std::time::Instant;
+
+use ad::hash::cuckoo::CuckooHashMap;
+use ad::hash::fnv::FnvHashBuilder;
+use ad::hash::hashmap::HashMap;
+use ad::hash::map::Map;
+use ad::hash::mod_hash::ModHashBuilder;
+use ad::hash::murmur3::{Murmur3HashBuilder128, Murmur3HashBuilder32};
+use ad::hash::open_addressing::{DoubleHashingMap, LinearProbingMap, QuadraticProbingMap};
+use ad::hash::robin_hood::RobinHoodMap;
+use ad::hash::siphash::SipHashBuilder;
+use ad::hash::static_hashmap::StaticHashMap;
+use ad::hash::xxhash::XxHashBuilder;
+
+const KEYS: u32 = 100_000;
+const BUCKETS: usize = 1024;
+
+/// inserts all keys, looks each of them up, removes half of them and looks all of them up again
+fn measure<M: Map<u32, u32>>(name: &str, mut map: M) {
+    let start = Instant::now();
+    for key in 0..KEYS {
+        map.put(key.wrapping_mul(2_654_435_761), key);
+    }
+    for key in 0..KEYS {
+        black_box(map.get(&key.wrapping_mul(2_654_435_761)));
+    }
+    for key in (0..KEYS).step_by(2) {
+        map.remove(&key.wrapping_mul(2_654_435_761));
+    }
+    for key in 0..KEYS {
+        black_box(map.get(&key.wrapping_mul(2_654_435_761)));
+    }
+    println!("{:<30} {:>10.2?}", name, start.elapsed());
+}
+
+/// hashes sequential keys into a power of two number of buckets and prints the chi-squared statistic
+/// of the bucket sizes, which stays close to the number of buckets for a uniform hash
+fn distribution<S: BuildHasher>(name: &str, hash_builder: S) {
+    let mut buckets = vec![0usize; BUCKETS];
+    for key in 0..KEYS {
+        buckets[hash_builder.hash_one(key) as usize % BUCKETS] += 1;
+    }
+    let expected = KEYS as f64 / BUCKETS as f64;
+    let chi_squared: f64 = buckets.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum();
+    let longest = buckets.iter().max().unwrap_or(&0);
+    println!("{:<30} chi^2 {:>12.1}   longest bucket {:>6}", name, chi_squared, longest);
+}
+
+fn main() {
+    measure("StaticHashMap (4096 buckets)", StaticHashMap::new(4096));
+    measure("HashMap", HashMap::new());
+    measure("LinearProbingMap", LinearProbingMap::new());
+    measure("QuadraticProbingMap", QuadraticProbingMap::new());
+    measure("DoubleHashingMap", DoubleHashingMap::new());
+    measure("RobinHoodMap", RobinHoodMap::new());
+    measure("CuckooHashMap", CuckooHashMap::new());
+
+    measure("HashMap (FNV-1a)", HashMap::with_hasher(FnvHashBuilder));
+    measure("HashMap (MurmurHash3 x64)", HashMap::with_hasher(Murmur3HashBuilder128::default()));
+    measure("HashMap (xxHash64)", HashMap::with_hasher(XxHashBuilder::default()));
+    measure("HashMap (SipHash-2-4)", HashMap::with_hasher(SipHashBuilder::default()));
+
+    println!();
+    distribution("mod_hash", ModHashBuilder::default());
+    distribution("FNV-1a", FnvHashBuilder);
+    distribution("MurmurHash3 x86 32", Murmur3HashBuilder32::default());
+    distribution("MurmurHash3 x64 128", Murmur3HashBuilder128::default());
+    distribution("xxHash64", XxHashBuilder::default());
+    distribution("SipHash-2-4", SipHashBuilder::default());
+}
@@ -0,0 +1,531 @@
+//! An interactive calculator on top of the expression module
+//!
+//! Reads one expression per line from stdin and prints its value. Besides expressions it understands
+//! `let name = expression` to define variables, `ans` for the previous result and the commands listed by `:help`.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use ad::expression::ast::Expr;
+use ad::expression::error::ExpressionError;
+use ad::expression::functions::{Arity, Functions};
+use ad::expression::lexer::Span;
+use ad::expression::shunting_yard::Operator;
+use ad::expression::value::Value;
+use ad::numbers::big_integer::BigInteger;
+use ad::numbers::rational::Rational;
+
+const HELP: &str = "\
+expressions   1 + 2 * 3, 2 ^ 10, max(a, 3), x > 1 && x < 5 ? 1 : 0
+let x = expr  stores the value of expr in the variable x
+ans           the value of the previous expression
+:mode [mode]  shows or sets the number mode: float, bigint or rational
+:vars         lists the variables
+:help         shows this help
+:quit         exits the calculator";
+
+/// The kind of numbers the calculator computes with
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Float,
+    BigInt,
+    Rational,
+}
+
+impl Mode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "float" => Some(Mode::Float),
+            "bigint" => Some(Mode::BigInt),
+            "rational" => Some(Mode::Rational),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Float => "float",
+            Mode::BigInt => "bigint",
+            Mode::Rational => "rational",
+        }
+    }
+}
+
+/// A value computed by the calculator
+/// Numbers are converted to the current mode when they are used, so variables survive a change of mode
+#[derive(Debug, Clone)]
+enum Scalar {
+    Float(f64),
+    BigInt(BigInteger),
+    Rational(Rational),
+    Bool(bool),
+}
+
+impl Scalar {
+    /// converts a number to the representation of the given mode, failing if it cannot be represented exactly
+    fn to_mode(&self, mode: Mode) -> Result<Self, ExpressionError> {
+        let not_representable = || ExpressionError::TypeMismatch(format!("{} cannot be used in {} mode", self, mode.name()));
+        Ok(match (self, mode) {
+            (Scalar::Bool(_), _)
+            | (Scalar::Float(_), Mode::Float)
+            | (Scalar::BigInt(_), Mode::BigInt)
+            | (Scalar::Rational(_), Mode::Rational) => self.clone(),
+            (Scalar::Float(value), Mode::BigInt) if value.is_finite() && value.fract() == 0.0 => {
+                Scalar::BigInt(BigInteger::from_str(&format!("{:.0}", value)).map_err(|_| not_representable())?)
+            }
+            // the shortest representation of a float is what a user would have typed, so 0.1 becomes 1/10
+            (Scalar::Float(value), Mode::Rational) if value.is_finite() => {
+                Scalar::Rational(Rational::parse(&value.to_string()).map_err(|_| not_representable())?)
+            }
+            (Scalar::Float(_), _) => return Err(not_representable()),
+            (Scalar::BigInt(value), Mode::Float) => Scalar::Float(value.to_f64()),
+            (Scalar::BigInt(value), Mode::Rational) => Scalar::Rational(Rational::from_integer(value.clone())),
+            (Scalar::Rational(value), Mode::Float) => Scalar::Float(value.to_f64()),
+            (Scalar::Rational(value), Mode::BigInt) if value.is_integer() => Scalar::BigInt(value.numerator().clone()),
+            (Scalar::Rational(_), Mode::BigInt) => return Err(not_representable()),
+        })
+    }
+
+    fn as_bool(&self, operation: &str) -> Result<bool, ExpressionError> {
+        match self {
+            Scalar::Bool(value) => Ok(*value),
+            _ => Err(ExpressionError::TypeMismatch(format!("{} expects a boolean but got {}", operation, self))),
+        }
+    }
+}
+
+impl Display for Scalar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scalar::Float(value) => write!(f, "{}", value),
+            Scalar::BigInt(value) => write!(f, "{}", value),
+            Scalar::Rational(value) => write!(f, "{}", value),
+            Scalar::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// What the calculator wants the caller to do after a line was executed
+#[derive(Debug, PartialEq)]
+enum Response {
+    Print(String),
+    Nothing,
+    Quit,
+}
+
+/// An error together with the offset of the evaluated expression in the line, so carets can be placed under it
+#[derive(Debug)]
+struct Failure {
+    error: ExpressionError,
+    offset: usize,
+}
+
+impl Failure {
+    /// renders the error, pointing at the offending part of the line if the error has a span
+    fn report(&self, line: &str) -> String {
+        match &self.error {
+            ExpressionError::Syntax { span, .. } => {
+                let start = (self.offset + span.start).min(line.len());
+                let end = (self.offset + span.end).clamp(start, line.len());
+                let column = line[..start].chars().count();
+                let width = line[start..end].chars().count().max(1);
+                format!("{}\n{}\n{}{}", self.error, line, " ".repeat(column), "^".repeat(width))
+            }
+            error => format!("error: {}", error),
+        }
+    }
+}
+
+impl From<ExpressionError> for Failure {
+    fn from(error: ExpressionError) -> Self {
+        Self { error, offset: 0 }
+    }
+}
+
+struct Calculator {
+    mode: Mode,
+    variables: HashMap<String, Scalar>,
+    functions: Functions,
+}
+
+impl Calculator {
+    fn new() -> Self {
+        Self {
+            mode: Mode::Float,
+            variables: HashMap::new(),
+            functions: Functions::with_builtins(),
+        }
+    }
+
+    /// executes a single line of input
+    fn execute(&mut self, line: &str) -> Result<Response, Failure> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Ok(Response::Nothing);
+        }
+        if let Some(command) = trimmed.strip_prefix(':') {
+            return self.command(command);
+        }
+
+        let offset = line.len() - line.trim_start().len();
+        if let Some(rest) = trimmed.strip_prefix("let").filter(|rest| rest.starts_with(char::is_whitespace)) {
+            return self.assign(offset + 3, rest);
+        }
+
+        let value = self.evaluate_input(trimmed).map_err(|error| Failure { error, offset })?;
+        self.variables.insert("ans".to_string(), value.clone());
+        Ok(Response::Print(value.to_string()))
+    }
+
+    fn command(&mut self, command: &str) -> Result<Response, Failure> {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("q" | "quit" | "exit"), None, _) => Ok(Response::Quit),
+            (Some("help"), None, _) => Ok(Response::Print(HELP.to_string())),
+            (Some("mode"), None, _) => Ok(Response::Print(format!("mode: {}", self.mode.name()))),
+            (Some("mode"), Some(name), None) => {
+                self.mode = Mode::from_name(name).ok_or_else(|| {
+                    ExpressionError::InvalidArgument(format!("unknown mode '{}', expected float, bigint or rational", name))
+                })?;
+                Ok(Response::Print(format!("mode: {}", self.mode.name())))
+            }
+            (Some("vars"), None, _) => {
+                let mut names: Vec<&String> = self.variables.keys().collect();
+                names.sort();
+                let lines: Vec<String> = names.into_iter().map(|name| format!("{} = {}", name, self.variables[name])).collect();
+                Ok(Response::Print(lines.join("\n")))
+            }
+            _ => Err(ExpressionError::InvalidArgument(format!("unknown command ':{}', type :help for a list", command)).into()),
+        }
+    }
+
+    /// handles `let name = expression`, where rest is everything after `let` and starts at offset in the line
+    fn assign(&mut self, offset: usize, rest: &str) -> Result<Response, Failure> {
+        let syntax_error = |message: &str, start: usize, end: usize| Failure {
+            error: ExpressionError::Syntax {
+                message: message.to_string(),
+                span: Span::new(start, end),
+            },
+            offset,
+        };
+
+        let name_start = rest.len() - rest.trim_start().len();
+        let name_length = rest[name_start..].find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len() - name_start);
+        let name = &rest[name_start..name_start + name_length];
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(syntax_error("expected a variable name after let", name_start, name_start + name_length));
+        }
+        if name == "ans" {
+            return Err(syntax_error("ans is set automatically and cannot be assigned", name_start, name_start + name_length));
+        }
+
+        let after_name = &rest[name_start + name_length..];
+        let equals = name_start + name_length + after_name.len() - after_name.trim_start().len();
+        let expression = match rest[equals..].strip_prefix('=') {
+            Some(expression) if !expression.starts_with('=') => expression,
+            _ => return Err(syntax_error("expected '=' after the variable name", equals, (equals + 1).min(rest.len()))),
+        };
+
+        let value = self.evaluate_input(expression).map_err(|error| Failure {
+            error,
+            offset: offset + equals + 1,
+        })?;
+        self.variables.insert(name.to_string(), value.clone());
+        self.variables.insert("ans".to_string(), value.clone());
+        Ok(Response::Print(format!("{} = {}", name, value)))
+    }
+
+    fn evaluate_input(&self, input: &str) -> Result<Scalar, ExpressionError> {
+        self.evaluate(&Expr::parse(input)?)
+    }
+
+    fn evaluate(&self, expr: &Expr) -> Result<Scalar, ExpressionError> {
+        match expr {
+            Expr::Literal(value) => self.literal(*value),
+            Expr::Variable(name) => self.variables.get(name)
+                .ok_or_else(|| ExpressionError::UnknownVariable(name.clone()))?
+                .to_mode(self.mode),
+            Expr::Binary(Operator::And, left, right) => {
+                if !self.evaluate(left)?.as_bool("&&")? {
+                    return Ok(Scalar::Bool(false));
+                }
+                Ok(Scalar::Bool(self.evaluate(right)?.as_bool("&&")?))
+            }
+            Expr::Binary(Operator::Or, left, right) => {
+                if self.evaluate(left)?.as_bool("||")? {
+                    return Ok(Scalar::Bool(true));
+                }
+                Ok(Scalar::Bool(self.evaluate(right)?.as_bool("||")?))
+            }
+            Expr::Binary(operator, left, right) => apply(*operator, self.evaluate(left)?, self.evaluate(right)?),
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.evaluate(condition)?.as_bool("?:")? {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(otherwise)
+                }
+            }
+            Expr::Call(name, arguments) => {
+                let arguments = arguments.iter().map(|argument| self.evaluate(argument)).collect::<Result<Vec<_>, _>>()?;
+                self.call(name, arguments)
+            }
+        }
+    }
+
+    fn literal(&self, value: Value) -> Result<Scalar, ExpressionError> {
+        match value {
+            Value::Int(value) => Scalar::BigInt(BigInteger::from_i32(value)).to_mode(self.mode),
+            Value::Float(value) => Scalar::Float(value).to_mode(self.mode),
+            Value::Bool(value) => Ok(Scalar::Bool(value)),
+        }
+    }
+
+    fn call(&self, name: &str, arguments: Vec<Scalar>) -> Result<Scalar, ExpressionError> {
+        if self.mode == Mode::Float {
+            // the builtins are called directly, only gcd needs integers, so it gets integral floats as Int
+            let is_int = |value: f64| name == "gcd" && value.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(&value);
+            let arguments = arguments.iter().map(|argument| match argument {
+                Scalar::Float(value) if is_int(*value) => Value::Int(*value as i32),
+                Scalar::Float(value) => Value::Float(*value),
+                Scalar::Bool(value) => Value::Bool(*value),
+                _ => unreachable!("numbers to be floats in float mode"),
+            }).collect::<Vec<_>>();
+            let function = self.functions.get(name).ok_or_else(|| ExpressionError::UnknownFunction(name.to_string()))?;
+            return Ok(match function.call(name, &arguments)? {
+                Value::Int(value) => Scalar::Float(value as f64),
+                Value::Float(value) => Scalar::Float(value),
+                Value::Bool(value) => Scalar::Bool(value),
+            });
+        }
+
+        let arity = match (name, self.mode) {
+            ("abs", _) => Arity::Fixed(1),
+            ("max" | "min", _) => Arity::Variadic(1),
+            ("gcd", Mode::BigInt) => Arity::Variadic(2),
+            _ if self.functions.get(name).is_some() => {
+                return Err(ExpressionError::InvalidArgument(format!("{} is not available in {} mode", name, self.mode.name())));
+            }
+            _ => return Err(ExpressionError::UnknownFunction(name.to_string())),
+        };
+        if !arity.accepts(arguments.len()) {
+            return Err(ExpressionError::WrongArgumentCount { name: name.to_string(), arity, found: arguments.len() });
+        }
+
+        let mut arguments = arguments.into_iter();
+        let first = arguments.next().expect("at least one argument");
+        arguments.try_fold(absolute(name, first)?, |result, argument| {
+            Ok(match name {
+                "max" if compare("max", &argument, &result)?.is_gt() => argument,
+                "min" if compare("min", &argument, &result)?.is_lt() => argument,
+                "gcd" => match (result, absolute(name, argument)?) {
+                    (Scalar::BigInt(result), Scalar::BigInt(argument)) => Scalar::BigInt(result.gcd(&argument)),
+                    (result, argument) => return Err(mismatch(name, &result, &argument)),
+                },
+                _ => result,
+            })
+        })
+    }
+}
+
+/// returns the absolute value of a number for abs and gcd, other functions get their argument back
+fn absolute(name: &str, value: Scalar) -> Result<Scalar, ExpressionError> {
+    if name != "abs" && name != "gcd" {
+        return Ok(value);
+    }
+    Ok(match value {
+        Scalar::Float(value) => Scalar::Float(value.abs()),
+        Scalar::BigInt(value) => Scalar::BigInt(value.as_positive()),
+        Scalar::Rational(value) if value.is_negative() => Scalar::Rational(value.negate()),
+        Scalar::Rational(value) => Scalar::Rational(value),
+        Scalar::Bool(_) => return Err(ExpressionError::TypeMismatch(format!("{} expects a number but got {}", name, value))),
+    })
+}
+
+fn mismatch(operation: &str, left: &Scalar, right: &Scalar) -> ExpressionError {
+    ExpressionError::TypeMismatch(format!("{} cannot be applied to {} and {}", operation, left, right))
+}
+
+/// compares two numbers of the same mode
+fn compare(operation: &str, left: &Scalar, right: &Scalar) -> Result<Ordering, ExpressionError> {
+    let ordering = |greater: bool, less: bool| if greater { Ordering::Greater } else if less { Ordering::Less } else { Ordering::Equal };
+    match (left, right) {
+        (Scalar::Float(left), Scalar::Float(right)) => Ok(left.partial_cmp(right).unwrap_or(Ordering::Equal)),
+        (Scalar::BigInt(left), Scalar::BigInt(right)) => Ok(ordering(left.greater_than(right), right.greater_than(left))),
+        (Scalar::Rational(left), Scalar::Rational(right)) => Ok(ordering(left.greater_than(right), right.greater_than(left))),
+        _ => Err(mismatch(operation, left, right)),
+    }
+}
+
+/// Applies an operator that needs both of its operands to two values of the current mode
+fn apply(operator: Operator, left: Scalar, right: Scalar) -> Result<Scalar, ExpressionError> {
+    let symbol = operator.symbol();
+    let division_by_zero = || ExpressionError::InvalidArgument("division by zero".to_string());
+    Ok(match operator {
+        Operator::Eq | Operator::Ne => {
+            let equal = match (&left, &right) {
+                (Scalar::Bool(left), Scalar::Bool(right)) => left == right,
+                (Scalar::Float(left), Scalar::Float(right)) => left == right,
+                _ => compare(symbol, &left, &right)?.is_eq(),
+            };
+            Scalar::Bool(equal == (operator == Operator::Eq))
+        }
+        Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => {
+            if let (Scalar::Float(left), Scalar::Float(right)) = (&left, &right) {
+                if left.is_nan() || right.is_nan() {
+                    return Ok(Scalar::Bool(false));
+                }
+            }
+            let ordering = compare(symbol, &left, &right)?;
+            Scalar::Bool(match operator {
+                Operator::Lt => ordering.is_lt(),
+                Operator::Le => ordering.is_le(),
+                Operator::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        Operator::And | Operator::Or => unreachable!("Calculator::evaluate short-circuits && and ||"),
+        _ => match (left, right) {
+            (Scalar::Float(left), Scalar::Float(right)) => Scalar::Float(match operator {
+                Operator::Sub => left - right,
+                Operator::Add => left + right,
+                Operator::Mul => left * right,
+                Operator::Div => left / right,
+                _ => left.powf(right),
+            }),
+            (Scalar::BigInt(left), Scalar::BigInt(right)) => Scalar::BigInt(match operator {
+                Operator::Sub => left.sub(&right),
+                Operator::Add => left.add(&right),
+                Operator::Mul => left.mul(&right),
+                Operator::Div => left.div_rem(&right).map_err(|_| division_by_zero())?.0,
+                _ => {
+                    let exponent = right.to_i64().and_then(|exponent| u32::try_from(exponent).ok()).ok_or_else(|| {
+                        ExpressionError::InvalidArgument(format!("^ expects a non-negative exponent that fits into 32 bits but got {}", right))
+                    })?;
+                    left.pow(exponent)
+                }
+            }),
+            (Scalar::Rational(left), Scalar::Rational(right)) => Scalar::Rational(match operator {
+                Operator::Sub => left.sub(&right),
+                Operator::Add => left.add(&right),
+                Operator::Mul => left.mul(&right),
+                Operator::Div => left.div(&right).map_err(|_| division_by_zero())?,
+                _ => {
+                    let exponent = Some(&right)
+                        .filter(|right| right.is_integer())
+                        .and_then(|right| right.numerator().to_i64())
+                        .and_then(|exponent| i32::try_from(exponent).ok())
+                        .ok_or_else(|| ExpressionError::InvalidArgument(format!("^ expects an integer exponent but got {}", right)))?;
+                    left.pow(exponent).map_err(|_| division_by_zero())?
+                }
+            }),
+            (left, right) => return Err(mismatch(symbol, &left, &right)),
+        },
+    })
+}
+
+fn main() {
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!("ad-calc, type :help for help");
+    }
+
+    let mut calculator = Calculator::new();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush().expect("stdout to be writable");
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(error)) => {
+                eprintln!("error: {}", error);
+                break;
+            }
+            None => break,
+        };
+        match calculator.execute(&line) {
+            Ok(Response::Print(output)) => println!("{}", output),
+            Ok(Response::Nothing) => {}
+            Ok(Response::Quit) => break,
+            Err(failure) => eprintln!("{}", failure.report(&line)),
+        }
+    }
+}
+
+#[test]
+pub fn evaluate_test() {
+    let mut calculator = Calculator::new();
+    let mut execute = |line: &str| match calculator.execute(line) {
+        Ok(Response::Print(output)) => output,
+        Ok(response) => panic!("expected output for {} but got {:?}", line, response),
+        Err(failure) => failure.report(line),
+    };
+
+    assert_eq!(execute("1 + 2 * 3"), "7");
+    assert_eq!(execute("7 / 2"), "3.5");
+    assert_eq!(execute("ans * 2"), "7");
+    assert_eq!(execute("let x = sqrt(16)"), "x = 4");
+    assert_eq!(execute("x > 3 && ans == 4 ? max(x, 10) : 0"), "10");
+    assert_eq!(execute("gcd(12, 18)"), "6");
+    assert_eq!(execute("gcd(x * 3, 2.5)"), "error: gcd expects an integer but got 2.5");
+
+    assert_eq!(execute(":mode bigint"), "mode: bigint");
+    assert_eq!(execute("2 ^ 100"), "1267650600228229401496703205376");
+    assert_eq!(execute("ans / 3 ^ 60"), "29");
+    assert_eq!(execute("7 / 2"), "3");
+    assert_eq!(execute("gcd(x * 6, 0x24)"), "12");
+    assert_eq!(execute("1.5 + 1"), "error: 1.5 cannot be used in bigint mode");
+    assert_eq!(execute("sqrt(4)"), "error: sqrt is not available in bigint mode");
+
+    assert_eq!(execute(":mode rational"), "mode: rational");
+    assert_eq!(execute("1 / 3 + 0.5"), "5/6");
+    assert_eq!(execute("let y = ans ^ 2"), "y = 25/36");
+    assert_eq!(execute("min(y, 1 / 2, abs(0 - 3))"), "1/2");
+    assert_eq!(execute("y ^ (0 - 1) > 1"), "true");
+    assert_eq!(execute(":mode bigint"), "mode: bigint");
+    assert_eq!(execute("y"), "error: 25/36 cannot be used in bigint mode");
+    assert_eq!(execute(":mode float"), "mode: float");
+    assert_eq!(execute("y"), "0.6944444444444444");
+}
+
+#[test]
+pub fn modes_test() {
+    let mut calculator = Calculator::new();
+    let mut execute = |line: &str| match calculator.execute(line) {
+        Ok(Response::Print(output)) => output,
+        Ok(response) => panic!("expected output for {} but got {:?}", line, response),
+        Err(failure) => failure.report(line),
+    };
+
+    // operators of equal precedence group from the left, a leading '-' negates
+    for (mode, quotient) in [("float", "1.5"), ("bigint", "1"), ("rational", "3/2")] {
+        assert_eq!(execute(&format!(":mode {}", mode)), format!("mode: {}", mode));
+        assert_eq!(execute("10 - 2 + 3"), "11", "{}", mode);
+        assert_eq!(execute("2*3/4"), quotient, "{}", mode);
+        assert_eq!(execute("-5"), "-5", "{}", mode);
+        assert_eq!(execute("2 * -ans"), "10", "{}", mode);
+        assert_eq!(execute("-2 ^ 2"), "-4", "{}", mode);
+    }
+}
+
+#[test]
+pub fn errors_test() {
+    let mut calculator = Calculator::new();
+    let mut execute = |line: &str| match calculator.execute(line) {
+        Ok(response) => panic!("expected an error for {} but got {:?}", line, response),
+        Err(failure) => failure.report(line),
+    };
+
+    assert_eq!(execute("1 + * 2"), "syntax error: expected an operand\n1 + * 2\n    ^");
+    assert_eq!(execute("  let x = (1 + 2"), "syntax error: unclosed '('\n  let x = (1 + 2\n          ^");
+    assert_eq!(execute("let 1x = 2"), "syntax error: expected a variable name after let\nlet 1x = 2\n    ^^");
+    assert_eq!(execute("let x 2"), "syntax error: expected '=' after the variable name\nlet x 2\n      ^");
+    assert_eq!(execute("let ans = 2"), "syntax error: ans is set automatically and cannot be assigned\nlet ans = 2\n    ^^^");
+    assert_eq!(execute("ans"), "error: unknown variable 'ans'");
+    assert_eq!(execute("abs(1, 2)"), "error: abs expects 1 arguments but got 2");
+    assert_eq!(execute(":mode complex"), "error: unknown mode 'complex', expected float, bigint or rational");
+    assert_eq!(execute("1 +"), "syntax error: unexpected end of input\n1 +\n   ^");
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use crate::expression::error::ExpressionError;
+use crate::expression::functions::Functions;
+use crate::expression::lexer::Span;
+use crate::expression::shunting_yard::{convert_to_rpn, syntax_error, Operator, Token};
+use crate::expression::value::Value;
+
+/// An expression in tree form, built from the output of the shunting yard algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    /// condition ? then : otherwise
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression in infix notation
+    pub fn parse(input: &str) -> Result<Self, ExpressionError> {
+        Self::from_rpn(convert_to_rpn(input)?)
+    }
+
+    /// Builds the tree for a sequence of tokens in reverse polish notation, like the output of [convert_to_rpn]
+    /// The spans of syntax errors refer to the indices of the tokens
+    pub fn from_rpn(tokens: Vec<Token>) -> Result<Self, ExpressionError> {
+        let length = tokens.len();
+        let tokens = tokens.into_iter().enumerate().map(|(index, token)| (token, Span::new(index, index + 1)));
+        build_from_rpn(tokens, Span::new(length, length))
+    }
+
+    /// Evaluates the expression, looking up variables and functions in the given maps
+    /// The operands of && and || as well as the branches of a conditional are only evaluated when needed
+    pub fn evaluate(&self, functions: &Functions, variables: &HashMap<String, Value>) -> Result<Value, ExpressionError> {
+        match self {
+            Expr::Literal(value) => Ok(*value),
+            Expr::Variable(name) => variables.get(name).copied().ok_or_else(|| ExpressionError::UnknownVariable(name.clone())),
+            Expr::Binary(Operator::And, left, right) => {
+                if !left.evaluate(functions, variables)?.as_bool("&&")? {
+                    return Ok(Value::Bool(false));
+                }
+                Ok(Value::Bool(right.evaluate(functions, variables)?.as_bool("&&")?))
+            }
+            Expr::Binary(Operator::Or, left, right) => {
+                if left.evaluate(functions, variables)?.as_bool("||")? {
+                    return Ok(Value::Bool(true));
+                }
+                Ok(Value::Bool(right.evaluate(functions, variables)?.as_bool("||")?))
+            }
+            Expr::Binary(operator, left, right) => {
+                let left = left.evaluate(functions, variables)?;
+                let right = right.evaluate(functions, variables)?;
+                apply(*operator, left, right)
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if condition.evaluate(functions, variables)?.as_bool("?:")? {
+                    then.evaluate(functions, variables)
+                } else {
+                    otherwise.evaluate(functions, variables)
+                }
+            }
+            Expr::Call(name, arguments) => {
+                let function = functions.get(name).ok_or_else(|| ExpressionError::UnknownFunction(name.clone()))?;
+                let arguments = arguments.iter()
+                    .map(|argument| argument.evaluate(functions, variables))
+                    .collect::<Result<Vec<_>, _>>()?;
+                function.call(name, &arguments)
+            }
+        }
+    }
+}
+
+/// Builds the tree for tokens in reverse polish notation, reporting errors at the span of the offending token
+/// end is the span reported when the tokens do not form exactly one expression
+pub(crate) fn build_from_rpn(tokens: impl IntoIterator<Item = (Token, Span)>, end: Span) -> Result<Expr, ExpressionError> {
+    let mut stack = Vec::new();
+
+    for (token, span) in tokens {
+        let operand_count = match token {
+            Token::Operator(_) => 2,
+            Token::Conditional => 3,
+            Token::Function(_, argument_count) => argument_count,
+            _ => 0,
+        };
+        if stack.len() < operand_count {
+            return Err(syntax_error(&format!("expected {} operands but found {}", operand_count, stack.len()), span));
+        }
+        let mut operands = stack.split_off(stack.len() - operand_count).into_iter();
+        let mut operand = || Box::new(operands.next().expect("operands to be split off"));
+        let expr = match token {
+            Token::Number(num) => Expr::Literal(Value::Int(num)),
+            Token::Float(num) => Expr::Literal(Value::Float(num)),
+            Token::Variable(name) => Expr::Variable(name),
+            Token::Operator(operator) => Expr::Binary(operator, operand(), operand()),
+            Token::Conditional => Expr::Conditional(operand(), operand(), operand()),
+            Token::Function(name, _) => Expr::Call(name, operands.collect()),
+        };
+        stack.push(expr);
+    }
+
+    if stack.len() != 1 {
+        return Err(syntax_error(&format!("expected a single expression but found {}", stack.len()), end));
+    }
+    Ok(stack.pop().expect("a single expression on the stack"))
+}
+
+/// Applies an operator that needs both of its operands
+/// Arithmetic on two integers stays integral, as soon as a float is involved the result is a float
+/// Integer division by zero, negative integer exponents and overflow are reported as [ExpressionError::InvalidArgument]
+pub(crate) fn apply(operator: Operator, left: Value, right: Value) -> Result<Value, ExpressionError> {
+    let symbol = operator.symbol();
+    Ok(match operator {
+        Operator::Eq | Operator::Ne => {
+            let equal = match (left, right) {
+                (Value::Bool(left), Value::Bool(right)) => left == right,
+                (Value::Int(left), Value::Int(right)) => left == right,
+                (Value::Bool(_), _) | (_, Value::Bool(_)) => {
+                    return Err(ExpressionError::TypeMismatch(format!("cannot compare {} with {}", left, right)));
+                }
+                _ => left.as_float(symbol)? == right.as_float(symbol)?,
+            };
+            Value::Bool(equal == (operator == Operator::Eq))
+        }
+        Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => {
+            let ordering = match (left, right) {
+                (Value::Int(left), Value::Int(right)) => Some(left.cmp(&right)),
+                _ => left.as_float(symbol)?.partial_cmp(&right.as_float(symbol)?),
+            };
+            Value::Bool(ordering.is_some_and(|ordering| match operator {
+                Operator::Lt => ordering.is_lt(),
+                Operator::Le => ordering.is_le(),
+                Operator::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        Operator::And | Operator::Or => Value::Bool(left.as_bool(symbol)? && right.as_bool(symbol)?),
+        _ => match (left, right) {
+            (Value::Int(left_operand), Value::Int(right_operand)) => {
+                let result = match operator {
+                    Operator::Sub => left_operand.checked_sub(right_operand),
+                    Operator::Add => left_operand.checked_add(right_operand),
+                    Operator::Mul => left_operand.checked_mul(right_operand),
+                    Operator::Div if right_operand == 0 => {
+                        return Err(ExpressionError::InvalidArgument(format!("{} / 0 divides by zero", left_operand)));
+                    }
+                    Operator::Div => left_operand.checked_div(right_operand),
+                    Operator::Pow if right_operand < 0 => {
+                        return Err(ExpressionError::InvalidArgument(format!("{} ^ {} has a negative integer exponent", left_operand, right_operand)));
+                    }
+                    Operator::Pow => left_operand.checked_pow(right_operand as u32),
+                    _ => unreachable!(),
+                };
+                Value::Int(result.ok_or_else(|| {
+                    ExpressionError::InvalidArgument(format!("{} {} {} overflows a 32 bit integer", left_operand, symbol, right_operand))
+                })?)
+            }
+            _ => {
+                let left_operand = left.as_float(symbol)?;
+                let right_operand = right.as_float(symbol)?;
+                Value::Float(match operator {
+                    Operator::Sub => left_operand - right_operand,
+                    Operator::Add => left_operand + right_operand,
+                    Operator::Mul => left_operand * right_operand,
+                    Operator::Div => left_operand / right_operand,
+                    Operator::Pow => left_operand.powf(right_operand),
+                    _ => unreachable!(),
+                })
+            }
+        },
+    })
+}
+
+#[test]
+pub fn parse_test() {
+    assert_eq!(Expr::parse("max(1, x) > 2 ? 3 : 4"), Ok(Expr::Conditional(
+        Box::new(Expr::Binary(
+            Operator::Gt,
+            Box::new(Expr::Call("max".to_string(), vec![
+                Expr::Literal(Value::Int(1)),
+                Expr::Variable("x".to_string()),
+            ])),
+            Box::new(Expr::Literal(Value::Int(2))),
+        )),
+        Box::new(Expr::Literal(Value::Int(3))),
+        Box::new(Expr::Literal(Value::Int(4))),
+    )));
+}
+
+#[test]
+pub fn evaluate_test() {
+    let functions = Functions::with_builtins();
+    let mut variables = HashMap::new();
+    variables.insert("a".to_string(), Value::Int(5));
+    variables.insert("b".to_string(), Value::Int(12));
+    variables.insert("c".to_string(), Value::Int(0));
+    let evaluate = |input: &str| Expr::parse(input)?.evaluate(&functions, &variables);
+
+    assert_eq!(evaluate("(a > 3 && b <= 10) || c == 0"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("a > 3 && b <= 10"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate("a != 5 || b >= 12"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("a < b == (c < a)"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("a * 2 == 10 ? b : c"), Ok(Value::Int(12)));
+    assert_eq!(evaluate("c > 0 ? 1 : c < 0 ? 2 : 3"), Ok(Value::Int(3)));
+    assert_eq!(evaluate("max(a, b) + 1"), Ok(Value::Int(13)));
+
+    // the right hand side and the branch not taken are never evaluated
+    assert_eq!(evaluate("c == 1 && missing > 0"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate("c == 0 || missing > 0"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("c == 0 ? 1 : missing"), Ok(Value::Int(1)));
+
+    assert_eq!(evaluate("missing"), Err(ExpressionError::UnknownVariable("missing".to_string())));
+    assert!(matches!(evaluate("a && b"), Err(ExpressionError::TypeMismatch(_))));
+    assert!(matches!(evaluate("(a > 1) + 1"), Err(ExpressionError::TypeMismatch(_))));
+    assert!(matches!(evaluate("a == (b > 1)"), Err(ExpressionError::TypeMismatch(_))));
+}
+
+#[test]
+pub fn evaluate_arithmetic_errors_test() {
+    let functions = Functions::with_builtins();
+    let evaluate = |input: &str| Expr::parse(input)?.evaluate(&functions, &HashMap::new());
+    for input in ["1/0", "2^(0-1)", "2147483647+1", "0-2147483647-2", "65536*65536", "2^31", "(0-2147483647-1)/(0-1)"] {
+        assert!(matches!(evaluate(input), Err(ExpressionError::InvalidArgument(_))), "{}", input);
+    }
+    assert_eq!(evaluate("2^30"), Ok(Value::Int(1 << 30)));
+    assert_eq!(evaluate("2.0^(0-1)"), Ok(Value::Float(0.5)));
+    assert_eq!(evaluate("1/0.0"), Ok(Value::Float(f64::INFINITY)));
+}
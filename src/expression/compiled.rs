@@ -0,0 +1,310 @@
+use std::rc::Rc;
+
+use crate::expression::ast::{apply, Expr};
+use crate::expression::error::ExpressionError;
+use crate::expression::functions::{Function, Functions};
+use crate::expression::shunting_yard::Operator;
+use crate::expression::value::Value;
+use crate::linear_data_structures::array_stack::ArrayStack;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Instruction {
+    /// pushes the constant with the given index
+    Constant(usize),
+    /// pushes the value of the variable in the given slot
+    Load(usize),
+    /// pops two operands and pushes the result of the operator
+    Binary(Operator),
+    /// pops the given number of arguments and pushes the result of the function with the given index
+    Call(usize, usize),
+    /// pops the left operand of && or ||, if it decides the result it is pushed back and execution jumps to the target
+    ShortCircuit(Operator, usize),
+    /// checks that the right operand of && or || on top of the stack is a boolean
+    CheckBool(Operator),
+    /// pops the condition of a conditional and jumps to the target if it is false
+    JumpUnless(usize),
+    Jump(usize),
+}
+
+/// An expression compiled to a program for a stack machine, so that it can be evaluated repeatedly
+/// without parsing it again
+///
+/// Variables are resolved to slots at compile time, the values for them are passed to [CompiledExpr::evaluate]
+/// in the order given by [CompiledExpr::variables]. Functions are looked up once when compiling.
+pub struct CompiledExpr {
+    instructions: Vec<Instruction>,
+    constants: Vec<Value>,
+    variables: Vec<String>,
+    functions: Vec<(String, Rc<Function>)>,
+    stack: ArrayStack<Value>,
+    arguments: Vec<Value>,
+}
+
+impl CompiledExpr {
+    /// Parses and compiles an expression, resolving the functions it calls in functions
+    pub fn compile(input: &str, functions: &Functions) -> Result<Self, ExpressionError> {
+        Self::compile_expr(&Expr::parse(input)?, functions)
+    }
+
+    /// Compiles an already parsed expression
+    pub fn compile_expr(expr: &Expr, functions: &Functions) -> Result<Self, ExpressionError> {
+        let mut compiler = Compiler {
+            functions,
+            program: CompiledExpr {
+                instructions: Vec::new(),
+                constants: Vec::new(),
+                variables: Vec::new(),
+                functions: Vec::new(),
+                stack: ArrayStack::new(),
+                arguments: Vec::new(),
+            },
+            depth: 0,
+            max_depth: 0,
+        };
+        compiler.compile(expr)?;
+        let mut program = compiler.program;
+        program.stack = ArrayStack::with_capacity(compiler.max_depth);
+        Ok(program)
+    }
+
+    /// returns the names of the variables used by the expression, indexed by their slot
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// returns the slot of the variable with the given name, if the expression uses it
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.variables.iter().position(|variable| variable == name)
+    }
+
+    /// Evaluates the expression, taking the value of the variable in slot i from values[i]
+    /// A slot without a value is reported as unknown variable when it is loaded, like [Expr::evaluate] does
+    pub fn evaluate(&mut self, values: &[Value]) -> Result<Value, ExpressionError> {
+        self.stack.clear();
+
+        let mut pc = 0;
+        while pc < self.instructions.len() {
+            let instruction = self.instructions[pc];
+            pc += 1;
+            match instruction {
+                Instruction::Constant(index) => self.stack.push(self.constants[index]),
+                Instruction::Load(slot) => match values.get(slot) {
+                    Some(value) => self.stack.push(*value),
+                    None => return Err(ExpressionError::UnknownVariable(self.variables[slot].clone())),
+                },
+                Instruction::Binary(operator) => {
+                    let right = self.stack.pop().expect("right operand to be on stack");
+                    let left = self.stack.pop().expect("left operand to be on stack");
+                    self.stack.push(apply(operator, left, right)?);
+                }
+                Instruction::Call(index, argument_count) => {
+                    self.arguments.clear();
+                    for _ in 0..argument_count {
+                        self.arguments.push(self.stack.pop().expect("argument to be on stack"));
+                    }
+                    self.arguments.reverse();
+                    let (name, function) = &self.functions[index];
+                    self.stack.push(function.call(name, &self.arguments)?);
+                }
+                Instruction::ShortCircuit(operator, target) => {
+                    let left = self.stack.pop().expect("left operand to be on stack").as_bool(operator.symbol())?;
+                    if left == (operator == Operator::Or) {
+                        self.stack.push(Value::Bool(left));
+                        pc = target;
+                    }
+                }
+                Instruction::CheckBool(operator) => {
+                    let right = self.stack.pop().expect("right operand to be on stack").as_bool(operator.symbol())?;
+                    self.stack.push(Value::Bool(right));
+                }
+                Instruction::JumpUnless(target) => {
+                    if !self.stack.pop().expect("condition to be on stack").as_bool("?:")? {
+                        pc = target;
+                    }
+                }
+                Instruction::Jump(target) => pc = target,
+            }
+        }
+
+        Ok(self.stack.pop().expect("a single value on the stack"))
+    }
+}
+
+struct Compiler<'a> {
+    functions: &'a Functions,
+    program: CompiledExpr,
+    /// the number of values on the stack at the current instruction
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Compiler<'_> {
+    /// adds an instruction that changes the stack depth by the given amount, returning its index
+    fn emit(&mut self, instruction: Instruction, depth_change: isize) -> usize {
+        self.program.instructions.push(instruction);
+        self.depth = self.depth.checked_add_signed(depth_change).expect("stack depth to stay positive");
+        self.max_depth = self.max_depth.max(self.depth);
+        self.program.instructions.len() - 1
+    }
+
+    /// makes the jump at the given index point to the next instruction
+    fn patch(&mut self, index: usize) {
+        let target = self.program.instructions.len();
+        match &mut self.program.instructions[index] {
+            Instruction::ShortCircuit(_, jump_target) | Instruction::JumpUnless(jump_target) | Instruction::Jump(jump_target) => {
+                *jump_target = target
+            }
+            _ => panic!("only jumps can be patched"),
+        }
+    }
+
+    fn compile(&mut self, expr: &Expr) -> Result<(), ExpressionError> {
+        match expr {
+            Expr::Literal(value) => {
+                let index = match self.program.constants.iter().position(|constant| constant == value) {
+                    Some(index) => index,
+                    None => {
+                        self.program.constants.push(*value);
+                        self.program.constants.len() - 1
+                    }
+                };
+                self.emit(Instruction::Constant(index), 1);
+            }
+            Expr::Variable(name) => {
+                let slot = match self.program.slot(name) {
+                    Some(slot) => slot,
+                    None => {
+                        self.program.variables.push(name.clone());
+                        self.program.variables.len() - 1
+                    }
+                };
+                self.emit(Instruction::Load(slot), 1);
+            }
+            Expr::Binary(operator @ (Operator::And | Operator::Or), left, right) => {
+                self.compile(left)?;
+                let jump = self.emit(Instruction::ShortCircuit(*operator, 0), -1);
+                self.compile(right)?;
+                self.emit(Instruction::CheckBool(*operator), 0);
+                self.patch(jump);
+            }
+            Expr::Binary(operator, left, right) => {
+                self.compile(left)?;
+                self.compile(right)?;
+                self.emit(Instruction::Binary(*operator), -1);
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                self.compile(condition)?;
+                let jump_to_otherwise = self.emit(Instruction::JumpUnless(0), -1);
+                self.compile(then)?;
+                let jump_to_end = self.emit(Instruction::Jump(0), -1);
+                self.patch(jump_to_otherwise);
+                self.compile(otherwise)?;
+                self.patch(jump_to_end);
+            }
+            Expr::Call(name, arguments) => {
+                let function = self.functions.get_shared(name).ok_or_else(|| ExpressionError::UnknownFunction(name.clone()))?;
+                if !function.arity().accepts(arguments.len()) {
+                    return Err(ExpressionError::WrongArgumentCount {
+                        name: name.clone(),
+                        arity: function.arity(),
+                        found: arguments.len(),
+                    });
+                }
+                for argument in arguments {
+                    self.compile(argument)?;
+                }
+                let index = match self.program.functions.iter().position(|(function_name, _)| function_name == name) {
+                    Some(index) => index,
+                    None => {
+                        self.program.functions.push((name.clone(), function));
+                        self.program.functions.len() - 1
+                    }
+                };
+                self.emit(Instruction::Call(index, arguments.len()), 1 - arguments.len() as isize);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+pub fn compile_test() {
+    let functions = Functions::with_builtins();
+    let compiled = CompiledExpr::compile("x * x + max(x, y, 2) + 2", &functions).unwrap();
+    assert_eq!(compiled.variables(), &["x".to_string(), "y".to_string()]);
+    assert_eq!(compiled.slot("y"), Some(1));
+    assert_eq!(compiled.slot("z"), None);
+    assert_eq!(compiled.constants, vec![Value::Int(2)]);
+    assert_eq!(compiled.instructions, vec![
+        Instruction::Load(0),
+        Instruction::Load(0),
+        Instruction::Binary(Operator::Mul),
+        Instruction::Load(0),
+        Instruction::Load(1),
+        Instruction::Constant(0),
+        Instruction::Call(0, 3),
+        Instruction::Binary(Operator::Add),
+        Instruction::Constant(0),
+        Instruction::Binary(Operator::Add),
+    ]);
+
+    assert!(matches!(CompiledExpr::compile("nope(1)", &functions), Err(ExpressionError::UnknownFunction(_))));
+    assert!(matches!(CompiledExpr::compile("abs(1, 2)", &functions), Err(ExpressionError::WrongArgumentCount { .. })));
+}
+
+#[test]
+pub fn compiled_evaluate_test() {
+    use std::collections::HashMap;
+
+    let functions = Functions::with_builtins();
+    let inputs = [
+        "(a > 3 && b <= 10) || c == 0",
+        "a > 3 && b <= 10",
+        "c == 1 && b > 0",
+        "c == 0 || b > 0",
+        "a * 2 == 10 ? b : c",
+        "c > 0 ? 1 : c < 0 ? 2 : 3",
+        "max(a, b) + gcd(a * 3, 12) - 2 ^ c",
+        "a / 2.0 + sqrt(b)",
+    ];
+    for (a, b, c) in [(5, 12, 0), (4, 3, 1), (0, 10, 2)] {
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), Value::Int(a));
+        variables.insert("b".to_string(), Value::Int(b));
+        variables.insert("c".to_string(), Value::Int(c));
+        for input in inputs {
+            let mut compiled = CompiledExpr::compile(input, &functions).unwrap();
+            let values: Vec<Value> = compiled.variables().iter().map(|name| variables[name]).collect();
+            // evaluating twice checks that no state leaks from one evaluation into the next
+            for _ in 0..2 {
+                assert_eq!(compiled.evaluate(&values), Expr::parse(input).unwrap().evaluate(&functions, &variables), "{}", input);
+            }
+        }
+    }
+
+    // like the tree, the compiled expression only needs the variables it actually reads
+    let mut compiled = CompiledExpr::compile("c == 0 || missing", &functions).unwrap();
+    let mut variables = HashMap::new();
+    variables.insert("c".to_string(), Value::Int(0));
+    let tree = Expr::parse("c == 0 || missing").unwrap();
+    assert_eq!(compiled.evaluate(&[Value::Int(0)]), Ok(Value::Bool(true)));
+    assert_eq!(compiled.evaluate(&[Value::Int(0)]), tree.evaluate(&functions, &variables));
+    variables.insert("c".to_string(), Value::Int(1));
+    assert_eq!(compiled.evaluate(&[Value::Int(1)]), Err(ExpressionError::UnknownVariable("missing".to_string())));
+    assert_eq!(compiled.evaluate(&[Value::Int(1)]), tree.evaluate(&functions, &variables));
+    assert_eq!(compiled.evaluate(&[]), Err(ExpressionError::UnknownVariable("c".to_string())));
+    assert_eq!(compiled.evaluate(&[Value::Int(0), Value::Int(1)]), Ok(Value::Bool(true)));
+    assert!(matches!(compiled.evaluate(&[Value::Int(1), Value::Int(1)]), Err(ExpressionError::TypeMismatch(_))));
+    assert_eq!(compiled.evaluate(&[Value::Int(1), Value::Bool(false)]), Ok(Value::Bool(false)));
+}
+
+#[test]
+pub fn compiled_arithmetic_errors_test() {
+    let functions = Functions::with_builtins();
+    let mut compiled = CompiledExpr::compile("a / b + 2 ^ b", &functions).unwrap();
+    assert!(matches!(compiled.evaluate(&[Value::Int(1), Value::Int(0)]), Err(ExpressionError::InvalidArgument(_))));
+    assert!(matches!(compiled.evaluate(&[Value::Int(1), Value::Int(-1)]), Err(ExpressionError::InvalidArgument(_))));
+    assert!(matches!(compiled.evaluate(&[Value::Int(i32::MAX), Value::Int(1)]), Err(ExpressionError::InvalidArgument(_))));
+    // a failed evaluation leaves nothing behind for the next one
+    assert_eq!(compiled.evaluate(&[Value::Int(6), Value::Int(2)]), Ok(Value::Int(7)));
+}
@@ -0,0 +1,50 @@
+use std::fmt::{Display, Formatter};
+
+use crate::expression::functions::Arity;
+use crate::expression::lexer::Span;
+
+/// The ways in which evaluating an expression can fail
+#[derive(Debug, PartialEq)]
+pub enum ExpressionError {
+    /// The input is not a well-formed expression
+    Syntax {
+        message: String,
+        span: Span,
+    },
+    /// A function was called that is not registered
+    UnknownFunction(String),
+    /// A function was called with a number of arguments its arity does not accept
+    WrongArgumentCount {
+        name: String,
+        arity: Arity,
+        found: usize,
+    },
+    /// A variable was used that has no value
+    UnknownVariable(String),
+    /// A function was called with an argument outside of its domain
+    InvalidArgument(String),
+    /// An operator or function was applied to a value of the wrong type
+    TypeMismatch(String),
+    /// A derivative was requested of an expression containing an operator or function that has none
+    NotDifferentiable(String),
+}
+
+impl Display for ExpressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpressionError::Syntax { message, .. } => write!(f, "syntax error: {}", message),
+            ExpressionError::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            ExpressionError::WrongArgumentCount { name, arity, found } => {
+                let expected = match arity {
+                    Arity::Fixed(count) => format!("{}", count),
+                    Arity::Variadic(min) => format!("at least {}", min),
+                };
+                write!(f, "{} expects {} arguments but got {}", name, expected, found)
+            }
+            ExpressionError::UnknownVariable(name) => write!(f, "unknown variable '{}'", name),
+            ExpressionError::InvalidArgument(message)
+            | ExpressionError::TypeMismatch(message)
+            | ExpressionError::NotDifferentiable(message) => write!(f, "{}", message),
+        }
+    }
+}
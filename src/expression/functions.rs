@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::expression::error::ExpressionError;
+use crate::expression::value::Value;
+use crate::numbers::gcd::gcd_euclid;
+
+/// The number of arguments a function accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    /// The function takes exactly this many arguments
+    Fixed(usize),
+    /// The function takes at least this many arguments
+    Variadic(usize),
+}
+
+impl Arity {
+    /// returns true iff a function with this arity can be called with n arguments
+    pub fn accepts(&self, n: usize) -> bool {
+        match *self {
+            Arity::Fixed(count) => n == count,
+            Arity::Variadic(min) => n >= min,
+        }
+    }
+}
+
+type NativeFunction = Box<dyn Fn(&[Value]) -> Result<Value, ExpressionError>>;
+
+/// A function that can be called from within an expression
+pub struct Function {
+    arity: Arity,
+    body: NativeFunction,
+}
+
+impl Function {
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    /// Calls the function after checking that the number of arguments matches its arity
+    pub fn call(&self, name: &str, arguments: &[Value]) -> Result<Value, ExpressionError> {
+        if !self.arity.accepts(arguments.len()) {
+            return Err(ExpressionError::WrongArgumentCount {
+                name: name.to_string(),
+                arity: self.arity,
+                found: arguments.len(),
+            });
+        }
+        (self.body)(arguments)
+    }
+}
+
+/// A registry of the functions that are available to an expression
+pub struct Functions {
+    functions: HashMap<String, Rc<Function>>,
+}
+
+impl Functions {
+    /// Creates a registry without any functions
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Creates a registry containing the built-in functions
+    /// max, min, abs, gcd, sqrt, exp, ln and if
+    ///
+    /// sqrt of an Int returns an Int and fails for numbers that are not perfect squares, so a result is never
+    /// silently rounded. `sqrt(2.0)` gives the square root as a Float.
+    pub fn with_builtins() -> Self {
+        let mut functions = Self::new();
+        functions.register("max", Arity::Variadic(1), |args| extremum("max", args, i32::max, f64::max));
+        functions.register("min", Arity::Variadic(1), |args| extremum("min", args, i32::min, f64::min));
+        functions.register("abs", Arity::Fixed(1), |args| match args[0] {
+            Value::Int(value) => Ok(Value::Int(value.abs())),
+            value => Ok(Value::Float(value.as_float("abs")?.abs())),
+        });
+        functions.register("gcd", Arity::Variadic(2), |args| {
+            Ok(Value::Int(ints("gcd", args)?.into_iter().fold(0, gcd)))
+        });
+        functions.register("sqrt", Arity::Fixed(1), |args| {
+            if args[0].as_float("sqrt")? < 0.0 {
+                return Err(ExpressionError::InvalidArgument(format!("sqrt of negative number {}", args[0])));
+            }
+            match args[0] {
+                Value::Int(value) if value.isqrt() * value.isqrt() == value => Ok(Value::Int(value.isqrt())),
+                Value::Int(value) => Err(ExpressionError::InvalidArgument(format!(
+                    "sqrt of {} is not an integer, use sqrt({}.0) for a float",
+                    value, value
+                ))),
+                value => Ok(Value::Float(value.as_float("sqrt")?.sqrt())),
+            }
+        });
+        functions.register("exp", Arity::Fixed(1), |args| Ok(Value::Float(args[0].as_float("exp")?.exp())));
+        functions.register("ln", Arity::Fixed(1), |args| {
+            let value = args[0].as_float("ln")?;
+            if value <= 0.0 {
+                return Err(ExpressionError::InvalidArgument(format!("ln of non-positive number {}", args[0])));
+            }
+            Ok(Value::Float(value.ln()))
+        });
+        functions.register("if", Arity::Fixed(3), |args| {
+            // numbers are accepted as conditions as well, with 0 being false
+            let condition = match args[0] {
+                Value::Int(value) => value != 0,
+                Value::Float(value) => value != 0.0,
+                Value::Bool(value) => value,
+            };
+            Ok(if condition { args[1] } else { args[2] })
+        });
+        functions
+    }
+
+    /// Registers a function under the given name, replacing any function previously registered under it
+    pub fn register<F>(&mut self, name: &str, arity: Arity, body: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, ExpressionError> + 'static,
+    {
+        self.functions.insert(name.to_string(), Rc::new(Function {
+            arity,
+            body: Box::new(body),
+        }));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name).map(|function| function.as_ref())
+    }
+
+    /// returns a handle to the function that stays valid independently of the registry
+    pub(crate) fn get_shared(&self, name: &str) -> Option<Rc<Function>> {
+        self.functions.get(name).cloned()
+    }
+}
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn ints(name: &str, args: &[Value]) -> Result<Vec<i32>, ExpressionError> {
+    args.iter().map(|arg| arg.as_int(name)).collect()
+}
+
+/// Folds the arguments with the given functions, keeping the result integral if all arguments are integers
+fn extremum(name: &str, args: &[Value], int_fold: fn(i32, i32) -> i32, float_fold: fn(f64, f64) -> f64) -> Result<Value, ExpressionError> {
+    if args.iter().all(|arg| matches!(arg, Value::Int(_))) {
+        return Ok(Value::Int(ints(name, args)?.into_iter().reduce(int_fold).expect("at least one argument")));
+    }
+    let floats = args.iter().map(|arg| arg.as_float(name)).collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::Float(floats.into_iter().reduce(float_fold).expect("at least one argument")))
+}
+
+fn gcd(u: i32, v: i32) -> i32 {
+    let u = u.unsigned_abs() as usize;
+    let v = v.unsigned_abs() as usize;
+    // gcd_euclid divides by its second argument, so it must not be the smaller one
+    gcd_euclid(u.min(v), u.max(v))
+}
+
+#[test]
+pub fn builtins_test() {
+    let functions = Functions::with_builtins();
+    let call = |name: &str, args: &[i32]| {
+        let args: Vec<Value> = args.iter().map(|&arg| Value::Int(arg)).collect();
+        functions.get(name).unwrap().call(name, &args)
+    };
+    assert_eq!(call("max", &[3, 9, 4]), Ok(Value::Int(9)));
+    assert_eq!(call("min", &[3, 9, 4]), Ok(Value::Int(3)));
+    assert_eq!(call("abs", &[-7]), Ok(Value::Int(7)));
+    assert_eq!(call("gcd", &[84, 231]), Ok(Value::Int(21)));
+    assert_eq!(call("gcd", &[12, -18, 8]), Ok(Value::Int(2)));
+    assert_eq!(call("gcd", &[0, 5]), Ok(Value::Int(5)));
+    assert_eq!(call("sqrt", &[16]), Ok(Value::Int(4)));
+    assert_eq!(call("sqrt", &[0]), Ok(Value::Int(0)));
+    assert_eq!(call("sqrt", &[i32::MAX]), Err(ExpressionError::InvalidArgument(
+        "sqrt of 2147483647 is not an integer, use sqrt(2147483647.0) for a float".to_string(),
+    )));
+    assert!(matches!(call("sqrt", &[2]), Err(ExpressionError::InvalidArgument(_))));
+    assert_eq!(call("if", &[0, 1, 2]), Ok(Value::Int(2)));
+    let max = functions.get("max").unwrap();
+    assert_eq!(max.call("max", &[Value::Int(3), Value::Float(2.5)]), Ok(Value::Float(3.0)));
+    let sqrt = functions.get("sqrt").unwrap();
+    assert_eq!(sqrt.call("sqrt", &[Value::Float(2.25)]), Ok(Value::Float(1.5)));
+    assert!(matches!(sqrt.call("sqrt", &[Value::Bool(true)]), Err(ExpressionError::TypeMismatch(_))));
+    assert_eq!(call("exp", &[0]), Ok(Value::Float(1.0)));
+    assert_eq!(call("ln", &[1]), Ok(Value::Float(0.0)));
+    assert!(matches!(call("ln", &[0]), Err(ExpressionError::InvalidArgument(_))));
+    assert!(matches!(call("sqrt", &[-1]), Err(ExpressionError::InvalidArgument(_))));
+    assert_eq!(call("abs", &[1, 2]), Err(ExpressionError::WrongArgumentCount {
+        name: "abs".to_string(),
+        arity: Arity::Fixed(1),
+        found: 2,
+    }));
+}
@@ -0,0 +1,306 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::expression::error::ExpressionError;
+use crate::expression::shunting_yard::Operator;
+
+/// A region of the input, given as byte offsets with an exclusive end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Int(i32),
+    Float(f64),
+    Identifier(String),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+    Comma,
+    Question,
+    Colon,
+}
+
+/// A token of the expression language together with the part of the input it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Splits an expression into tokens
+///
+/// Numbers can be written as decimal integers, floats with an optional exponent (`3.14`, `.5`, `1e-3`)
+/// or integers with a `0x`, `0o` or `0b` prefix. All of them may contain `_` as a digit separator.
+pub struct Lexer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    /// returns the byte offset of the next character
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.input.len(), |&(index, _)| index)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    /// consumes the next character if it satisfies the predicate
+    fn next_if(&mut self, predicate: impl Fn(char) -> bool) -> Option<char> {
+        self.chars.next_if(|&(_, c)| predicate(c)).map(|(_, c)| c)
+    }
+
+    /// consumes digits of the given radix and separators, returning the digits without the separators
+    fn read_digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.next_if(|c| c.is_digit(radix) || c == '_') {
+            if c != '_' {
+                digits.push(c);
+            }
+        }
+        digits
+    }
+
+    fn read_number(&mut self, start: usize) -> Result<TokenKind, ExpressionError> {
+        let radix = if self.peek() == Some('0') {
+            let mut lookahead = self.chars.clone();
+            lookahead.next();
+            match lookahead.peek().map(|&(_, c)| c.to_ascii_lowercase()) {
+                Some('x') => 16,
+                Some('o') => 8,
+                Some('b') => 2,
+                _ => 10,
+            }
+        } else {
+            10
+        };
+
+        let kind = if radix == 10 {
+            let mut literal = self.read_digits(10);
+            let mut is_float = false;
+            if self.next_if(|c| c == '.').is_some() {
+                is_float = true;
+                literal.push('.');
+                literal.push_str(&self.read_digits(10));
+            }
+            if let Some(e) = self.next_if(|c| c == 'e' || c == 'E') {
+                is_float = true;
+                literal.push(e);
+                if let Some(sign) = self.next_if(|c| c == '+' || c == '-') {
+                    literal.push(sign);
+                }
+                let exponent = self.read_digits(10);
+                if exponent.is_empty() {
+                    return Err(self.error("expected digits in the exponent", start));
+                }
+                literal.push_str(&exponent);
+            }
+            if is_float {
+                TokenKind::Float(literal.parse().map_err(|_| self.error("invalid number", start))?)
+            } else {
+                TokenKind::Int(literal.parse().map_err(|_| self.error("integer literal out of range", start))?)
+            }
+        } else {
+            self.chars.next();
+            self.chars.next();
+            let digits = self.read_digits(radix);
+            if digits.is_empty() {
+                return Err(self.error("expected digits after the radix prefix", start));
+            }
+            TokenKind::Int(i32::from_str_radix(&digits, radix).map_err(|_| self.error("integer literal out of range", start))?)
+        };
+
+        // catches things like 0b12, 0xFG or 12abc
+        if self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            let invalid = self.offset();
+            self.chars.next();
+            return Err(ExpressionError::Syntax {
+                message: "invalid character in number".to_string(),
+                span: Span::new(invalid, self.offset()),
+            });
+        }
+        Ok(kind)
+    }
+
+    fn read_identifier(&mut self) -> TokenKind {
+        let mut name = String::new();
+        while let Some(c) = self.next_if(|c| c.is_alphanumeric() || c == '_') {
+            name.push(c);
+        }
+        TokenKind::Identifier(name)
+    }
+
+    fn read_operator(&mut self, start: usize) -> Result<TokenKind, ExpressionError> {
+        let (_, first) = self.chars.next().expect("a character to be left");
+        let second = self.peek();
+        let (operator, length) = match (first, second) {
+            ('=', Some('=')) => (Operator::Eq, 2),
+            ('!', Some('=')) => (Operator::Ne, 2),
+            ('<', Some('=')) => (Operator::Le, 2),
+            ('>', Some('=')) => (Operator::Ge, 2),
+            ('&', Some('&')) => (Operator::And, 2),
+            ('|', Some('|')) => (Operator::Or, 2),
+            ('<', _) => (Operator::Lt, 1),
+            ('>', _) => (Operator::Gt, 1),
+            ('+', _) => (Operator::Add, 1),
+            ('-', _) => (Operator::Sub, 1),
+            ('*', _) => (Operator::Mul, 1),
+            ('/', _) => (Operator::Div, 1),
+            ('^', _) => (Operator::Pow, 1),
+            _ => return Err(self.error(&format!("unknown operator '{}'", first), start)),
+        };
+        if length == 2 {
+            self.chars.next();
+        }
+        Ok(TokenKind::Operator(operator))
+    }
+
+    /// creates a syntax error covering everything from start to the current position
+    fn error(&mut self, message: &str, start: usize) -> ExpressionError {
+        ExpressionError::Syntax {
+            message: message.to_string(),
+            span: Span::new(start, self.offset()),
+        }
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, ExpressionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next_if(char::is_whitespace).is_some() {}
+
+        let start = self.offset();
+        let c = self.peek()?;
+        let kind = if c.is_ascii_digit() {
+            self.read_number(start)
+        } else if c == '.' {
+            let mut lookahead = self.chars.clone();
+            lookahead.next();
+            if lookahead.peek().is_some_and(|&(_, c)| c.is_ascii_digit()) {
+                self.read_number(start)
+            } else {
+                self.chars.next();
+                Err(self.error("unexpected '.'", start))
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            Ok(self.read_identifier())
+        } else {
+            let punctuation = match c {
+                '(' => Some(TokenKind::OpenParen),
+                ')' => Some(TokenKind::CloseParen),
+                ',' => Some(TokenKind::Comma),
+                '?' => Some(TokenKind::Question),
+                ':' => Some(TokenKind::Colon),
+                _ => None,
+            };
+            match punctuation {
+                Some(kind) => {
+                    self.chars.next();
+                    Ok(kind)
+                }
+                None => self.read_operator(start),
+            }
+        };
+
+        Some(kind.map(|kind| Token {
+            kind,
+            span: Span::new(start, self.offset()),
+        }))
+    }
+}
+
+/// Splits the whole input into tokens, stopping at the first error
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    Lexer::new(input).collect()
+}
+
+#[test]
+pub fn tokenize_test() {
+    let kinds = |input: &str| tokenize(input).unwrap().into_iter().map(|token| token.kind).collect::<Vec<_>>();
+    assert_eq!(kinds("12 + x_1"), vec![
+        TokenKind::Int(12),
+        TokenKind::Operator(Operator::Add),
+        TokenKind::Identifier("x_1".to_string()),
+    ]);
+    assert_eq!(kinds("a<=b&&c!=d||e?f:g"), vec![
+        TokenKind::Identifier("a".to_string()),
+        TokenKind::Operator(Operator::Le),
+        TokenKind::Identifier("b".to_string()),
+        TokenKind::Operator(Operator::And),
+        TokenKind::Identifier("c".to_string()),
+        TokenKind::Operator(Operator::Ne),
+        TokenKind::Identifier("d".to_string()),
+        TokenKind::Operator(Operator::Or),
+        TokenKind::Identifier("e".to_string()),
+        TokenKind::Question,
+        TokenKind::Identifier("f".to_string()),
+        TokenKind::Colon,
+        TokenKind::Identifier("g".to_string()),
+    ]);
+    assert_eq!(kinds("f(1, 2)"), vec![
+        TokenKind::Identifier("f".to_string()),
+        TokenKind::OpenParen,
+        TokenKind::Int(1),
+        TokenKind::Comma,
+        TokenKind::Int(2),
+        TokenKind::CloseParen,
+    ]);
+
+    let spans = tokenize(" 3.5 *foo").unwrap().into_iter().map(|token| token.span).collect::<Vec<_>>();
+    assert_eq!(spans, vec![Span::new(1, 4), Span::new(5, 6), Span::new(6, 9)]);
+}
+
+#[test]
+pub fn tokenize_numbers_test() {
+    let number = |input: &str| tokenize(input).unwrap().remove(0).kind;
+    assert_eq!(number("1_000_000"), TokenKind::Int(1_000_000));
+    assert_eq!(number("1.25"), TokenKind::Float(1.25));
+    assert_eq!(number(".5"), TokenKind::Float(0.5));
+    assert_eq!(number("2."), TokenKind::Float(2.0));
+    assert_eq!(number("1e-3"), TokenKind::Float(1e-3));
+    assert_eq!(number("6.02E+23"), TokenKind::Float(6.02e23));
+    assert_eq!(number("1_0.2_5e1_0"), TokenKind::Float(10.25e10));
+    assert_eq!(number("0xFF"), TokenKind::Int(255));
+    assert_eq!(number("0Xbe_ef"), TokenKind::Int(0xbeef));
+    assert_eq!(number("0o17"), TokenKind::Int(15));
+    assert_eq!(number("0b1010_1010"), TokenKind::Int(170));
+    assert_eq!(number("0"), TokenKind::Int(0));
+}
+
+#[test]
+pub fn tokenize_errors_test() {
+    let error = |input: &str| match tokenize(input) {
+        Err(ExpressionError::Syntax { span, .. }) => span,
+        other => panic!("expected a syntax error but got {:?}", other),
+    };
+    assert_eq!(error("1 + 0b12"), Span::new(7, 8));
+    assert_eq!(error("0xFG"), Span::new(3, 4));
+    assert_eq!(error("1.2.3"), Span::new(3, 4));
+    assert_eq!(error("12abc"), Span::new(2, 3));
+    assert_eq!(error("0x"), Span::new(0, 2));
+    assert_eq!(error("1e+"), Span::new(0, 3));
+    assert_eq!(error("99999999999"), Span::new(0, 11));
+    assert_eq!(error("0x1_0000_0000"), Span::new(0, 13));
+    assert_eq!(error("a = b"), Span::new(2, 3));
+    assert_eq!(error("a & b"), Span::new(2, 3));
+    assert_eq!(error("a # b"), Span::new(2, 3));
+}
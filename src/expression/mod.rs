@@ -1 +1,9 @@
-pub mod shunting_yard;
\ No newline at end of file
+pub mod shunting_yard;
+pub mod functions;
+pub mod error;
+pub mod value;
+pub mod ast;
+pub mod lexer;
+pub mod compiled;
+pub mod symbolic;
+pub mod notation;
@@ -0,0 +1,406 @@
+use std::fmt::{Display, Formatter};
+
+use crate::expression::ast::{build_from_rpn, Expr};
+use crate::expression::error::ExpressionError;
+use crate::expression::lexer::{tokenize, Span, TokenKind};
+use crate::expression::shunting_yard::{syntax_error, Operator, Token, NEGATION_PRECEDENCE};
+use crate::expression::value::Value;
+
+/// The word used for the conditional operator in postfix, prefix and S-expression notation
+const CONDITIONAL: &str = "?:";
+
+/// The precedence of the conditional, which binds weaker than every binary operator
+const CONDITIONAL_PRECEDENCE: i32 = 0;
+
+/// The precedence of expressions that never need parentheses
+const ATOM_PRECEDENCE: i32 = i32::MAX;
+
+impl Expr {
+    fn precedence(&self) -> i32 {
+        match self {
+            Expr::Binary(operator, _, _) => operator.precedence(),
+            Expr::Conditional(_, _, _) => CONDITIONAL_PRECEDENCE,
+            // literals without a literal syntax are written as the operation computing them, see to_infix
+            Expr::Literal(Value::Int(i32::MIN)) => Operator::Sub.precedence(),
+            Expr::Literal(Value::Float(value)) if !value.is_finite() => Operator::Div.precedence(),
+            // negative numbers are written with a unary minus
+            Expr::Literal(Value::Int(value)) if *value < 0 => NEGATION_PRECEDENCE,
+            Expr::Literal(Value::Float(value)) if value.is_sign_negative() => NEGATION_PRECEDENCE,
+            _ => ATOM_PRECEDENCE,
+        }
+    }
+
+    /// Returns the expression in infix notation with as few parentheses as possible
+    ///
+    /// Parsing the result with [Expr::parse] gives back the same expression. The only exceptions are the smallest
+    /// integer and non-finite floats, which have no literal syntax and are written as the operations computing them,
+    /// like `1.0 / 0.0`, so they come back once the parsed expression is simplified.
+    pub fn to_infix(&self) -> String {
+        match self {
+            Expr::Literal(Value::Int(i32::MIN)) => format!("{} - 1", i32::MIN + 1),
+            Expr::Literal(Value::Float(value)) if value.is_nan() => "0.0 / 0.0".to_string(),
+            Expr::Literal(Value::Float(value)) if value.is_infinite() => format!("{:?} / 0.0", value.signum()),
+            Expr::Literal(value) => value.to_string(),
+            Expr::Variable(name) => name.clone(),
+            Expr::Binary(operator, left, right) => {
+                let left = parenthesize(left.to_infix(), needs_parentheses(*operator, left, false));
+                let right = parenthesize(right.to_infix(), needs_parentheses(*operator, right, true));
+                format!("{} {} {}", left, operator.symbol(), right)
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                // the conditional is right associative, so only a conditional as condition needs parentheses
+                let condition = parenthesize(condition.to_infix(), condition.precedence() == CONDITIONAL_PRECEDENCE);
+                format!("{} ? {} : {}", condition, then.to_infix(), otherwise.to_infix())
+            }
+            Expr::Call(name, arguments) => {
+                format!("{}({})", name, arguments.iter().map(Expr::to_infix).collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+
+    /// Returns the expression in reverse polish (postfix) notation, like `1 2 + x max/2`
+    ///
+    /// Function calls are written as the name followed by a slash and the number of arguments,
+    /// the conditional as `?:` taking the condition and both branches.
+    pub fn to_rpn(&self) -> String {
+        let mut words = Vec::new();
+        self.postfix_words(&mut words);
+        words.join(" ")
+    }
+
+    fn postfix_words(&self, words: &mut Vec<String>) {
+        match self {
+            Expr::Literal(_) | Expr::Variable(_) => words.push(self.atom()),
+            Expr::Binary(operator, left, right) => {
+                left.postfix_words(words);
+                right.postfix_words(words);
+                words.push(operator.symbol().to_string());
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                condition.postfix_words(words);
+                then.postfix_words(words);
+                otherwise.postfix_words(words);
+                words.push(CONDITIONAL.to_string());
+            }
+            Expr::Call(name, arguments) => {
+                arguments.iter().for_each(|argument| argument.postfix_words(words));
+                words.push(format!("{}/{}", name, arguments.len()));
+            }
+        }
+    }
+
+    /// Returns the expression in polish (prefix) notation, like `max/2 + 1 2 x`
+    /// Function calls and conditionals are written like in [Expr::to_rpn]
+    pub fn to_prefix(&self) -> String {
+        let mut words = Vec::new();
+        self.prefix_words(&mut words);
+        words.join(" ")
+    }
+
+    fn prefix_words(&self, words: &mut Vec<String>) {
+        match self {
+            Expr::Literal(_) | Expr::Variable(_) => words.push(self.atom()),
+            Expr::Binary(operator, left, right) => {
+                words.push(operator.symbol().to_string());
+                left.prefix_words(words);
+                right.prefix_words(words);
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                words.push(CONDITIONAL.to_string());
+                condition.prefix_words(words);
+                then.prefix_words(words);
+                otherwise.prefix_words(words);
+            }
+            Expr::Call(name, arguments) => {
+                words.push(format!("{}/{}", name, arguments.len()));
+                arguments.iter().for_each(|argument| argument.prefix_words(words));
+            }
+        }
+    }
+
+    /// Returns the expression as an S-expression, like `(max (+ 1 2) x)`
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            Expr::Literal(_) | Expr::Variable(_) => self.atom(),
+            Expr::Binary(operator, left, right) => format!("({} {} {})", operator.symbol(), left.to_sexpr(), right.to_sexpr()),
+            Expr::Conditional(condition, then, otherwise) => {
+                format!("({} {} {} {})", CONDITIONAL, condition.to_sexpr(), then.to_sexpr(), otherwise.to_sexpr())
+            }
+            Expr::Call(name, arguments) => {
+                let mut words = vec![name.clone()];
+                words.extend(arguments.iter().map(Expr::to_sexpr));
+                format!("({})", words.join(" "))
+            }
+        }
+    }
+
+    /// Returns LaTeX math code typesetting the expression
+    pub fn to_latex(&self) -> String {
+        match self {
+            Expr::Literal(Value::Bool(value)) => format!("\\text{{{}}}", value),
+            Expr::Literal(value) => value.to_string(),
+            Expr::Variable(name) if name.chars().count() == 1 => name.clone(),
+            Expr::Variable(name) => format!("\\mathit{{{}}}", name.replace('_', "\\_")),
+            Expr::Binary(Operator::Div, left, right) => format!("\\frac{{{}}}{{{}}}", left.to_latex(), right.to_latex()),
+            Expr::Binary(Operator::Pow, base, exponent) => {
+                let base = latex_parenthesize(base.to_latex(), base.precedence() <= Operator::Pow.precedence());
+                format!("{{{}}}^{{{}}}", base, exponent.to_latex())
+            }
+            Expr::Binary(operator, left, right) => {
+                let symbol = match operator {
+                    Operator::Mul => "\\cdot",
+                    Operator::Eq => "=",
+                    Operator::Ne => "\\neq",
+                    Operator::Le => "\\leq",
+                    Operator::Ge => "\\geq",
+                    Operator::And => "\\land",
+                    Operator::Or => "\\lor",
+                    operator => operator.symbol(),
+                };
+                let left = latex_parenthesize(left.to_latex(), needs_parentheses(*operator, left, false));
+                let right = latex_parenthesize(right.to_latex(), needs_parentheses(*operator, right, true));
+                format!("{} {} {}", left, symbol, right)
+            }
+            Expr::Conditional(condition, then, otherwise) => format!(
+                "\\begin{{cases}} {} & \\text{{if }} {} \\\\ {} & \\text{{otherwise}} \\end{{cases}}",
+                then.to_latex(),
+                condition.to_latex(),
+                otherwise.to_latex()
+            ),
+            Expr::Call(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(Expr::to_latex).collect();
+                match (name.as_str(), arguments.as_slice()) {
+                    ("sqrt", [argument]) => format!("\\sqrt{{{}}}", argument),
+                    ("abs", [argument]) => format!("\\left| {} \\right|", argument),
+                    ("exp", [argument]) => format!("e^{{{}}}", argument),
+                    ("ln" | "max" | "min" | "gcd", _) => format!("\\{}\\left({}\\right)", name, arguments.join(", ")),
+                    _ => format!("\\operatorname{{{}}}\\left({}\\right)", name.replace('_', "\\_"), arguments.join(", ")),
+                }
+            }
+        }
+    }
+
+    /// returns the single word for a literal or variable in postfix, prefix and S-expression notation
+    fn atom(&self) -> String {
+        match self {
+            Expr::Literal(value) => value.to_string(),
+            Expr::Variable(name) => name.clone(),
+            _ => panic!("only literals and variables are atoms"),
+        }
+    }
+
+    /// Parses an expression in reverse polish (postfix) notation as written by [Expr::to_rpn]
+    pub fn parse_rpn(input: &str) -> Result<Expr, ExpressionError> {
+        let tokens = words(input).map(|(word, span)| Ok((read_word(word, span)?, span))).collect::<Result<Vec<_>, ExpressionError>>()?;
+        build_from_rpn(tokens, Span::new(input.len(), input.len()))
+    }
+
+    /// Parses an expression in polish (prefix) notation as written by [Expr::to_prefix]
+    pub fn parse_prefix(input: &str) -> Result<Expr, ExpressionError> {
+        let mut tokens = words(input).map(|(word, span)| Ok((read_word(word, span)?, span)));
+        let expr = parse_prefix_expr(&mut tokens, input.len())?;
+        match tokens.next() {
+            Some(token) => Err(syntax_error("expected the end of the input", token?.1)),
+            None => Ok(expr),
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_infix())
+    }
+}
+
+fn parenthesize(text: String, needed: bool) -> String {
+    if needed { format!("({})", text) } else { text }
+}
+
+/// returns whether an operand of the operator has to be put in parentheses to keep the tree when parsing again
+///
+/// At equal precedence the operand on the side the operator associates away from needs them, like the right
+/// operand in `a - (b + c)` or the left one in `(a ^ b) ^ c`.
+fn needs_parentheses(operator: Operator, operand: &Expr, is_right: bool) -> bool {
+    let (precedence, operand_precedence) = (operator.precedence(), operand.precedence());
+    operand_precedence < precedence || (operand_precedence == precedence && is_right != operator.is_right_associative())
+}
+
+fn latex_parenthesize(text: String, needed: bool) -> String {
+    if needed { format!("\\left({}\\right)", text) } else { text }
+}
+
+/// Splits the input at whitespace, returning every word with its span
+fn words(input: &str) -> impl Iterator<Item = (&str, Span)> {
+    let mut offset = 0;
+    input.split(char::is_whitespace).filter_map(move |word| {
+        let start = offset;
+        offset += word.len() + 1;
+        (!word.is_empty()).then(|| (word, Span::new(start, start + word.len())))
+    })
+}
+
+/// Reads a single word of postfix or prefix notation
+fn read_word(word: &str, span: Span) -> Result<Token, ExpressionError> {
+    if word == CONDITIONAL {
+        return Ok(Token::Conditional);
+    }
+    if let Some(operator) = Operator::from_symbol(word) {
+        return Ok(Token::Operator(operator));
+    }
+    if let Some((name, argument_count)) = word.rsplit_once('/') {
+        let is_name = matches!(tokenize(name).as_deref(), Ok([token]) if matches!(token.kind, TokenKind::Identifier(_)));
+        return match argument_count.parse() {
+            Ok(argument_count) if is_name => Ok(Token::Function(name.to_string(), argument_count)),
+            _ => Err(syntax_error("expected a function name followed by '/' and the number of arguments", span)),
+        };
+    }
+
+    let (negative, literal) = match word.strip_prefix('-') {
+        Some(literal) => (true, literal),
+        None => (false, word),
+    };
+    let tokens = tokenize(literal).map_err(|error| match error {
+        ExpressionError::Syntax { message, span: inner } => {
+            let offset = span.end - literal.len();
+            ExpressionError::Syntax {
+                message,
+                span: Span::new(inner.start + offset, inner.end + offset),
+            }
+        }
+        error => error,
+    })?;
+    match (negative, tokens.as_slice().first().map(|token| &token.kind)) {
+        (false, Some(TokenKind::Identifier(name))) if tokens.len() == 1 => Ok(Token::Variable(name.clone())),
+        (_, Some(TokenKind::Int(value))) if tokens.len() == 1 => Ok(Token::Number(if negative { -value } else { *value })),
+        (_, Some(TokenKind::Float(value))) if tokens.len() == 1 => Ok(Token::Float(if negative { -value } else { *value })),
+        _ => Err(syntax_error("expected a number, variable, operator or function", span)),
+    }
+}
+
+fn parse_prefix_expr(tokens: &mut impl Iterator<Item = Result<(Token, Span), ExpressionError>>, end: usize) -> Result<Expr, ExpressionError> {
+    let (token, _) = match tokens.next() {
+        Some(token) => token?,
+        None => return Err(syntax_error("unexpected end of input", Span::new(end, end))),
+    };
+    let mut operand = || parse_prefix_expr(tokens, end).map(Box::new);
+    Ok(match token {
+        Token::Number(num) => Expr::Literal(Value::Int(num)),
+        Token::Float(num) => Expr::Literal(Value::Float(num)),
+        Token::Variable(name) => Expr::Variable(name),
+        Token::Operator(operator) => Expr::Binary(operator, operand()?, operand()?),
+        Token::Conditional => Expr::Conditional(operand()?, operand()?, operand()?),
+        Token::Function(name, argument_count) => {
+            let arguments = (0..argument_count).map(|_| operand().map(|argument| *argument)).collect::<Result<_, _>>()?;
+            Expr::Call(name, arguments)
+        }
+    })
+}
+
+#[test]
+pub fn to_infix_test() {
+    let infix = |input: &str| Expr::parse(input).unwrap().to_infix();
+    assert_eq!(infix("((1 + 2)) * x"), "(1 + 2) * x");
+    assert_eq!(infix("(a * b) + (c / d)"), "a * b + c / d");
+    assert_eq!(infix("(a - b) - c"), "a - b - c");
+    assert_eq!(infix("a - (b - c)"), "a - (b - c)");
+    assert_eq!(infix("a - (b + c)"), "a - (b + c)");
+    assert_eq!(infix("a / (b * c)"), "a / (b * c)");
+    assert_eq!(infix("(a ^ b) ^ c"), "(a ^ b) ^ c");
+    assert_eq!(infix("2 ^ (3 ^ 2)"), "2 ^ 3 ^ 2");
+    assert_eq!(infix("(a > 1 && b) || (c ? d : e)"), "a > 1 && b || (c ? d : e)");
+    assert_eq!(infix("(a ? b : c) ? d : (e ? f : g)"), "(a ? b : c) ? d : e ? f : g");
+    assert_eq!(infix("max(1 + 2, (f()))"), "max(1 + 2, f())");
+    assert_eq!(infix("1.5e-7 + 0x10"), "1.5e-7 + 16");
+    assert_eq!(Expr::parse("x * 2").unwrap().differentiate("x").unwrap().to_string(), "2");
+    assert_eq!(Expr::Binary(
+        Operator::Mul,
+        Box::new(Expr::Literal(Value::Int(-3))),
+        Box::new(Expr::Variable("x".to_string())),
+    ).to_infix(), "-3 * x");
+
+    // printing and parsing again has to give back the same tree
+    let inputs = [
+        "a - b + c",
+        "(a - b) + c",
+        "a / (b / c) * d ^ e ^ f",
+        "(a ^ b) ^ c - (d + e) / (f * g)",
+        "a == (b != c) || x == y",
+        "a ? b ? c : d : (e ? f : g) + 1",
+        "gcd(a, max(b, c * (d + e)), 0b101)",
+        "(x + 1) ^ 2 - 2.5 * x / (y - 1)",
+    ];
+    for input in inputs {
+        let expr = Expr::parse(input).unwrap();
+        assert_eq!(Expr::parse(&expr.to_infix()), Ok(expr.clone()), "{}", input);
+    }
+
+    // negative literals are printed with a unary minus and read back as literals
+    let literal = |value: Value| Box::new(Expr::Literal(value));
+    let negative = [
+        Expr::Literal(Value::Int(-3)),
+        Expr::Literal(Value::Float(-0.5)),
+        Expr::Binary(Operator::Pow, literal(Value::Int(-2)), literal(Value::Int(2))),
+        Expr::Binary(Operator::Pow, literal(Value::Int(2)), literal(Value::Float(-1.5))),
+        Expr::Binary(Operator::Sub, literal(Value::Int(1)), literal(Value::Int(-1))),
+        Expr::Binary(Operator::Mul, literal(Value::Int(-1)), Box::new(Expr::parse("x - y").unwrap())),
+    ];
+    for expr in negative {
+        assert_eq!(Expr::parse(&expr.to_infix()), Ok(expr.clone()), "{}", expr);
+    }
+    assert_eq!(Expr::Binary(Operator::Pow, literal(Value::Int(-2)), literal(Value::Int(-2))).to_infix(), "(-2) ^ (-2)");
+
+    // folded literals without a literal syntax come back after simplifying
+    for input in ["1 / 0.0", "-1 / 0.0", "0 - 2147483647 - 1", "(0 - 2147483647 - 1) * (1.0 / 0.0)"] {
+        let expr = Expr::parse(input).unwrap().simplify();
+        assert_eq!(Expr::parse(&expr.to_infix()).map(|parsed| parsed.simplify()), Ok(expr.clone()), "{}", input);
+    }
+    assert_eq!(Expr::parse("0.0 / 0.0").unwrap().simplify().to_infix(), "0.0 / 0.0");
+}
+
+#[test]
+pub fn rpn_and_prefix_test() {
+    let expr = Expr::parse("max(1 + 2, x) * (a > 0 ? b : 3.5)").unwrap();
+    assert_eq!(expr.to_rpn(), "1 2 + x max/2 a 0 > b 3.5 ?: *");
+    assert_eq!(expr.to_prefix(), "* max/2 + 1 2 x ?: > a 0 b 3.5");
+    assert_eq!(expr.to_sexpr(), "(* (max (+ 1 2) x) (?: (> a 0) b 3.5))");
+    assert_eq!(Expr::parse_rpn(&expr.to_rpn()), Ok(expr.clone()));
+    assert_eq!(Expr::parse_prefix(&expr.to_prefix()), Ok(expr.clone()));
+
+    assert_eq!(Expr::parse_rpn(" 3  -4 - f/0 + "), Ok(Expr::Binary(
+        Operator::Add,
+        Box::new(Expr::Binary(Operator::Sub, Box::new(Expr::Literal(Value::Int(3))), Box::new(Expr::Literal(Value::Int(-4))))),
+        Box::new(Expr::Call("f".to_string(), vec![])),
+    )));
+    assert_eq!(Expr::parse_prefix("- 0x10 -0.5"), Ok(Expr::Binary(
+        Operator::Sub,
+        Box::new(Expr::Literal(Value::Int(16))),
+        Box::new(Expr::Literal(Value::Float(-0.5))),
+    )));
+
+    let error = |result: Result<Expr, ExpressionError>| match result {
+        Err(ExpressionError::Syntax { span, .. }) => (span.start, span.end),
+        other => panic!("expected a syntax error but got {:?}", other),
+    };
+    assert_eq!(error(Expr::parse_rpn("1 +")), (2, 3));
+    assert_eq!(error(Expr::parse_rpn("1 2")), (3, 3));
+    assert_eq!(error(Expr::parse_rpn("1 0xZ +")), (2, 4));
+    assert_eq!(error(Expr::parse_rpn("1 max/x")), (2, 7));
+    assert_eq!(error(Expr::parse_rpn("1 (")), (2, 3));
+    assert_eq!(error(Expr::parse_prefix("+ 1")), (3, 3));
+    assert_eq!(error(Expr::parse_prefix("+ 1 2 3")), (6, 7));
+}
+
+#[test]
+pub fn to_latex_test() {
+    let latex = |input: &str| Expr::parse(input).unwrap().to_latex();
+    assert_eq!(latex("(a + 1) / sqrt(b_2)"), "\\frac{a + 1}{\\sqrt{\\mathit{b\\_2}}}");
+    assert_eq!(latex("(x + 1) ^ 2 * y"), "{\\left(x + 1\\right)}^{2} \\cdot y");
+    assert_eq!(latex("a - (b + c)"), "a - \\left(b + c\\right)");
+    assert_eq!(latex("a / (b * c)"), "\\frac{a}{b \\cdot c}");
+    assert_eq!(latex("(a ^ b) ^ c"), "{\\left({a}^{b}\\right)}^{c}");
+    assert_eq!(latex("a * (b * c)"), "a \\cdot \\left(b \\cdot c\\right)");
+    assert_eq!(latex("exp(x) - abs(y)"), "e^{x} - \\left| y \\right|");
+    assert_eq!(latex("a <= b && c != 1"), "a \\leq b \\land c \\neq 1");
+    assert_eq!(latex("max(a, ln(b)) + price(c)"), "\\max\\left(a, \\ln\\left(b\\right)\\right) + \\operatorname{price}\\left(c\\right)");
+    assert_eq!(latex("x > 0 ? x : 0"), "\\begin{cases} x & \\text{if } x > 0 \\\\ 0 & \\text{otherwise} \\end{cases}");
+}
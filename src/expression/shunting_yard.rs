@@ -1,121 +1,312 @@
-const RADIX: u32 = 10;
+use crate::expression::ast::Expr;
+use crate::expression::error::ExpressionError;
+use crate::expression::functions::Functions;
+use crate::expression::lexer::{tokenize, Span, TokenKind};
+use std::collections::HashMap;
 
-#[derive(Debug, PartialEq)]
-enum Token {
+/// A binary operator of the expression language
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl Operator {
+    /// returns the operator written as symbol, if there is one
+    pub fn from_symbol(symbol: &str) -> Option<Operator> {
+        [
+            Operator::Add, Operator::Sub, Operator::Mul, Operator::Div, Operator::Pow,
+            Operator::Eq, Operator::Ne, Operator::Lt, Operator::Le, Operator::Gt, Operator::Ge,
+            Operator::And, Operator::Or,
+        ].into_iter().find(|operator| operator.symbol() == symbol)
+    }
+
+    /// Operators with a higher precedence bind more tightly, see [Operator::is_right_associative] for equal precedence
+    pub fn precedence(&self) -> i32 {
+        match self {
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Eq | Operator::Ne => 3,
+            Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => 4,
+            Operator::Add | Operator::Sub => 5,
+            Operator::Mul | Operator::Div => 6,
+            Operator::Pow => 8,
+        }
+    }
+
+    /// returns true iff a chain of this operator groups from the right, like `2^3^2 = 2^(3^2)`
+    /// All other operators group from the left, like `10 - 2 + 3 = (10 - 2) + 3`
+    pub fn is_right_associative(&self) -> bool {
+        *self == Operator::Pow
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Pow => "^",
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+        }
+    }
+}
+
+/// A token of an expression in reverse polish notation as produced by [convert_to_rpn]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
     Number(i32),
-    Operator(char),
+    Float(f64),
+    Operator(Operator),
+    /// A call of the named function with the given number of arguments
+    Function(String, usize),
+    Variable(String),
+    /// The conditional operator, taking a condition and two branches
+    Conditional,
+}
+
+/// An entry of the holding stack of [convert_to_rpn], which waits there until its operands are in the output
+enum Held {
+    Operator(Operator),
+    /// A function whose arguments are being read
+    Function(String),
     OpenParen,
+    /// The '?' of a conditional whose ':' has not been read yet
+    Question,
+    /// A conditional whose ':' was read
+    Conditional,
+    /// A unary minus, written to the output as a subtraction from 0
+    Negation,
 }
 
-fn get_precedence(operator: char) -> i32 {
-    match operator {
-        '-' => 1,
-        '+' => 2,
-        '*' => 3,
-        '/' => 4,
-        '^' => 5,
-        _ => { panic!("Unknown Operator {}", operator) }
+/// A unary minus binds tighter than `*` and `/` but not as tight as `^`, so `-2^2 = -(2^2)`
+pub(crate) const NEGATION_PRECEDENCE: i32 = 7;
+
+/// moves an operator from the top of the holding stack to the output, a negation becomes a subtraction from 0
+fn pop_to_output(holding_stack: &mut Vec<Held>, output_stack: &mut Vec<Token>) {
+    match holding_stack.pop() {
+        Some(Held::Operator(operator)) => output_stack.push(Token::Operator(operator)),
+        Some(Held::Negation) => output_stack.push(Token::Operator(Operator::Sub)),
+        Some(Held::Conditional) => output_stack.push(Token::Conditional),
+        _ => unreachable!("only operators are popped to the output"),
     }
 }
 
-fn convert_to_rpn(input: &str) -> Vec<Token> {
+/// An open parenthesis that has not been closed yet
+struct Paren {
+    span: Span,
+    /// true iff the parenthesis encloses the arguments of a function call
+    is_call: bool,
+    /// the number of arguments seen so far, if the parenthesis belongs to a function call
+    argument_count: usize,
+}
+
+pub(crate) fn syntax_error(message: &str, span: Span) -> ExpressionError {
+    ExpressionError::Syntax {
+        message: message.to_string(),
+        span,
+    }
+}
+
+/// Converts an expression in infix notation to reverse polish notation using the shunting yard algorithm
+pub fn convert_to_rpn(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let tokens = tokenize(input)?;
     let mut holding_stack = Vec::new();
     let mut output_stack = Vec::new();
+    let mut parens: Vec<Paren> = Vec::new();
+    // the positions of the '?' on the holding stack
+    let mut questions = Vec::new();
+    // whether the next token has to start an operand, which is the case at the beginning and after operators
+    let mut expect_operand = true;
+    // whether the next number is read as a negative literal because a unary minus came right before it
+    let mut negate_number = false;
 
-    let mut number = 0;
-    let mut is_number = false;
-
-    for char in input.chars() {
-        if char.is_digit(RADIX) {
-            number = number * RADIX + char.to_digit(RADIX).expect("char to be valid digit");
-            is_number = true;
-            continue;
-        } else if is_number {
-            output_stack.push(Token::Number(number as i32));
-            number = 0;
-            is_number = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let span = token.span;
+        let starts_operand = matches!(token.kind, TokenKind::Int(_) | TokenKind::Float(_) | TokenKind::Identifier(_) | TokenKind::OpenParen);
+        // a '-' where an operand has to start negates that operand
+        let is_negation = expect_operand && token.kind == TokenKind::Operator(Operator::Sub);
+        if starts_operand != expect_operand && !is_negation {
+            // a call without arguments is the only place where an operand may be left out
+            let empty_call = token.kind == TokenKind::CloseParen
+                && parens.last().is_some_and(|paren| paren.is_call && paren.argument_count == 0);
+            if !empty_call {
+                return Err(syntax_error(if expect_operand { "expected an operand" } else { "expected an operator" }, span));
+            }
         }
-
-        if char.is_whitespace() {
-            continue;
+        if starts_operand || is_negation {
+            if let Some(paren) = parens.last_mut() {
+                if paren.is_call && paren.argument_count == 0 {
+                    paren.argument_count = 1;
+                }
+            }
         }
-        if char == '(' {
-            holding_stack.push(Token::OpenParen);
-        } else if char == ')' {
-            while !holding_stack.is_empty() && !matches!(holding_stack.last(), Some(Token::OpenParen)) {
-                output_stack.push(holding_stack.pop().unwrap())
+
+        match &token.kind {
+            TokenKind::Int(num) => {
+                output_stack.push(Token::Number(if negate_number { -num } else { *num }));
+                negate_number = false;
+                expect_operand = false;
             }
-            holding_stack.pop();
-        } else {
-            'inner: while !holding_stack.is_empty() && matches!(holding_stack.last(), Some(Token::Operator(_))) {
-                if let Some(Token::Operator(c)) = holding_stack.iter().last() {
-                    if get_precedence(char) > get_precedence(*c) {
-                        break 'inner;
+            TokenKind::Float(num) => {
+                output_stack.push(Token::Float(if negate_number { -num } else { *num }));
+                negate_number = false;
+                expect_operand = false;
+            }
+            TokenKind::Identifier(name) => {
+                if tokens.get(index + 1).is_some_and(|next| next.kind == TokenKind::OpenParen) {
+                    holding_stack.push(Held::Function(name.clone()));
+                } else {
+                    output_stack.push(Token::Variable(name.clone()));
+                    expect_operand = false;
+                }
+            }
+            TokenKind::OpenParen => {
+                let is_call = matches!(holding_stack.last(), Some(Held::Function(_)));
+                holding_stack.push(Held::OpenParen);
+                parens.push(Paren {
+                    span,
+                    is_call,
+                    argument_count: 0,
+                });
+            }
+            TokenKind::CloseParen | TokenKind::Comma => {
+                while matches!(holding_stack.last(), Some(Held::Operator(_) | Held::Conditional | Held::Negation)) {
+                    pop_to_output(&mut holding_stack, &mut output_stack);
+                }
+                match holding_stack.last() {
+                    Some(Held::Question) => return Err(syntax_error("expected ':'", span)),
+                    Some(Held::OpenParen) => {}
+                    _ => return Err(syntax_error(if token.kind == TokenKind::Comma { "',' outside of a function call" } else { "unmatched ')'" }, span)),
+                }
+                let paren = parens.last_mut().expect("a paren for every OpenParen");
+                if token.kind == TokenKind::Comma {
+                    if !paren.is_call {
+                        return Err(syntax_error("',' outside of a function call", span));
                     }
-                    output_stack.push(holding_stack.pop().unwrap());
+                    paren.argument_count += 1;
+                    expect_operand = true;
+                    continue;
+                }
+                holding_stack.pop();
+                let argument_count = parens.pop().unwrap().argument_count;
+                if matches!(holding_stack.last(), Some(Held::Function(_))) {
+                    if let Some(Held::Function(name)) = holding_stack.pop() {
+                        output_stack.push(Token::Function(name, argument_count));
+                    }
+                }
+                expect_operand = false;
+            }
+            TokenKind::Question => {
+                // the conditional binds weakest and is right associative, so enclosing conditionals stay on the stack
+                while matches!(holding_stack.last(), Some(Held::Operator(_) | Held::Negation)) {
+                    pop_to_output(&mut holding_stack, &mut output_stack);
+                }
+                holding_stack.push(Held::Question);
+                questions.push(span);
+                expect_operand = true;
+            }
+            TokenKind::Colon => {
+                while matches!(holding_stack.last(), Some(Held::Operator(_) | Held::Conditional | Held::Negation)) {
+                    pop_to_output(&mut holding_stack, &mut output_stack);
+                }
+                match holding_stack.pop() {
+                    Some(Held::Question) => holding_stack.push(Held::Conditional),
+                    _ => return Err(syntax_error("':' without matching '?'", span)),
+                }
+                questions.pop();
+                expect_operand = true;
+            }
+            TokenKind::Operator(_) if is_negation => {
+                // a negated number is a negative literal, unless a following ^ binds tighter than the minus
+                let number_follows = tokens.get(index + 1).is_some_and(|next| matches!(next.kind, TokenKind::Int(_) | TokenKind::Float(_)));
+                let power_follows = tokens.get(index + 2).is_some_and(|next| next.kind == TokenKind::Operator(Operator::Pow));
+                if number_follows && !power_follows {
+                    negate_number = true;
                 } else {
-                    panic!("unreachable");
+                    output_stack.push(Token::Number(0));
+                    holding_stack.push(Held::Negation);
+                }
+            }
+            TokenKind::Operator(operator) => {
+                'inner: while !holding_stack.is_empty() && matches!(holding_stack.last(), Some(Held::Operator(_) | Held::Negation)) {
+                    let top_precedence = match holding_stack.iter().last() {
+                        Some(Held::Operator(top)) => top.precedence(),
+                        Some(Held::Negation) => NEGATION_PRECEDENCE,
+                        _ => panic!("unreachable"),
+                    };
+                    let binds_tighter = operator.precedence() > top_precedence
+                        || (operator.precedence() == top_precedence && operator.is_right_associative());
+                    if binds_tighter {
+                        break 'inner;
+                    }
+                    pop_to_output(&mut holding_stack, &mut output_stack);
                 }
+                holding_stack.push(Held::Operator(*operator));
+                expect_operand = true;
             }
-            holding_stack.push(Token::Operator(char));
         }
     }
 
-    if is_number {
-        output_stack.push(Token::Number(number as i32))
+    if expect_operand {
+        return Err(syntax_error("unexpected end of input", Span::new(input.len(), input.len())));
     }
-    while !holding_stack.is_empty() {
-        output_stack.push(holding_stack.pop().unwrap())
+    while let Some(held) = holding_stack.last() {
+        match held {
+            Held::OpenParen => return Err(syntax_error("unclosed '('", parens.pop().unwrap().span)),
+            Held::Question => return Err(syntax_error("'?' without matching ':'", questions.pop().unwrap())),
+            _ => pop_to_output(&mut holding_stack, &mut output_stack),
+        }
     }
 
-    output_stack
+    Ok(output_stack)
 }
 
 /// Evaluates an arbitrary expression in string form with respect to PEMDAS
-/// Supports the +,-,*,/,^ operators and parentheses
+/// Supports the +,-,*,/,^ operators, parentheses and the built-in functions of [Functions::with_builtins]
 pub fn shunting_yard(input: &str) -> i32 {
-    let tokens = convert_to_rpn(input);
-    let mut stack = Vec::new();
-
-    for token in tokens {
-        match token {
-            Token::Number(num) => {
-                stack.push(num);
-            }
-            Token::Operator(operator) => {
-                let right_operand = stack.pop().expect("right operand to be on stack");
-                let left_operand = stack.pop().expect("left operand to be on stack");
-                stack.push(match operator {
-                    '-' => left_operand - right_operand,
-                    '+' => left_operand + right_operand,
-                    '*' => left_operand * right_operand,
-                    '/' => left_operand / right_operand,
-                    '^' => left_operand.pow(right_operand as u32),
-                    _ => panic!("Unknown Operator {}", operator)
-                });
-            }
-            _ => panic!("Unexpected Token")
-        }
-    }
+    evaluate(input, &Functions::with_builtins()).expect("expression to be valid")
+}
 
-    stack.pop().expect("a single value on the stack")
+/// Evaluates an expression like [shunting_yard], calling the functions registered in functions
+/// The expression has to evaluate to a number
+pub fn evaluate(input: &str, functions: &Functions) -> Result<i32, ExpressionError> {
+    Expr::parse(input)?.evaluate(functions, &HashMap::new())?.as_int("expression")
 }
 
 #[test]
 pub fn convert_to_rpn_test() {
-    assert_eq!(convert_to_rpn("1+1"), vec![Token::Number(1), Token::Number(1), Token::Operator('+')]);
-    assert_eq!(convert_to_rpn("(1+1)*(3+6)*0"), vec![
+    assert_eq!(convert_to_rpn("1+1"), Ok(vec![Token::Number(1), Token::Number(1), Token::Operator(Operator::Add)]));
+    assert_eq!(convert_to_rpn("(1+1)*(3+6)*0"), Ok(vec![
         Token::Number(1),
         Token::Number(1),
-        Token::Operator('+'),
+        Token::Operator(Operator::Add),
         Token::Number(3),
         Token::Number(6),
-        Token::Operator('+'),
-        Token::Operator('*'),
+        Token::Operator(Operator::Add),
+        Token::Operator(Operator::Mul),
         Token::Number(0),
-        Token::Operator('*'),
-    ]);
-    assert_eq!(convert_to_rpn("1234+5678"), vec![Token::Number(1234), Token::Number(5678), Token::Operator('+')])
+        Token::Operator(Operator::Mul),
+    ]));
+    assert_eq!(convert_to_rpn("1234+5678"), Ok(vec![Token::Number(1234), Token::Number(5678), Token::Operator(Operator::Add)]))
 }
 
 #[test]
@@ -125,3 +316,153 @@ pub fn shunting_yard_test() {
     assert_eq!(shunting_yard("(2 + 4) * 3"), 18);
     assert_eq!(shunting_yard("1+2^5"), 33);
 }
+
+#[test]
+pub fn precedence_and_associativity_test() {
+    use crate::expression::value::Value;
+
+    let functions = Functions::with_builtins();
+    let evaluate = |input: &str| Expr::parse(input)?.evaluate(&functions, &HashMap::new());
+    assert_eq!(evaluate("10 - 2 + 3"), Ok(Value::Int(11)));
+    assert_eq!(evaluate("2 * 3 / 4"), Ok(Value::Int(1)));
+    assert_eq!(evaluate("2^3^2"), Ok(Value::Int(512)));
+}
+
+#[test]
+pub fn negation_test() {
+    use crate::expression::value::Value;
+
+    assert_eq!(convert_to_rpn("-x"), Ok(vec![Token::Number(0), Token::Variable("x".to_string()), Token::Operator(Operator::Sub)]));
+    assert_eq!(convert_to_rpn("-3 * -1.5"), Ok(vec![Token::Number(-3), Token::Float(-1.5), Token::Operator(Operator::Mul)]));
+    assert_eq!(convert_to_rpn("-2^2"), Ok(vec![
+        Token::Number(0),
+        Token::Number(2),
+        Token::Number(2),
+        Token::Operator(Operator::Pow),
+        Token::Operator(Operator::Sub),
+    ]));
+    let functions = Functions::with_builtins();
+    let evaluate = |input: &str| Expr::parse(input)?.evaluate(&functions, &HashMap::new());
+    assert_eq!(evaluate("-5"), Ok(Value::Int(-5)));
+    assert_eq!(evaluate("-2^2"), Ok(Value::Int(-4)));
+    assert_eq!(evaluate("2^-1.0"), Ok(Value::Float(0.5)));
+    assert_eq!(evaluate("-3 * 2 - -4"), Ok(Value::Int(-2)));
+    assert_eq!(evaluate("--2 + max(-1, -(2 - 5))"), Ok(Value::Int(5)));
+    assert_eq!(evaluate("1 > -1 ? -1.5 : 0"), Ok(Value::Float(-1.5)));
+}
+
+#[test]
+pub fn convert_functions_to_rpn_test() {
+    assert_eq!(convert_to_rpn("max(1, 2+3, 4)"), Ok(vec![
+        Token::Number(1),
+        Token::Number(2),
+        Token::Number(3),
+        Token::Operator(Operator::Add),
+        Token::Number(4),
+        Token::Function("max".to_string(), 3),
+    ]));
+    assert_eq!(convert_to_rpn("abs((1))*f()"), Ok(vec![
+        Token::Number(1),
+        Token::Function("abs".to_string(), 1),
+        Token::Function("f".to_string(), 0),
+        Token::Operator(Operator::Mul),
+    ]));
+}
+
+#[test]
+pub fn evaluate_functions_test() {
+    use crate::expression::functions::Arity;
+    use crate::expression::value::Value;
+
+    assert_eq!(shunting_yard("max(3, 9, 4) + 1"), 10);
+    assert_eq!(shunting_yard("gcd(84, 231) * abs(min(2, 0) - 2)"), 42);
+    assert_eq!(shunting_yard("sqrt(max(16, 2^3)) + if(0, 100, 1)"), 5);
+
+    let mut functions = Functions::with_builtins();
+    functions.register("sum", Arity::Variadic(0), |args| {
+        Ok(Value::Int(args.iter().map(|arg| arg.as_int("sum")).sum::<Result<i32, _>>()?))
+    });
+    functions.register("answer", Arity::Fixed(0), |_| Ok(Value::Int(42)));
+    assert_eq!(evaluate("sum(1, 2, sum(3, 4)) + sum()", &functions), Ok(10));
+    assert_eq!(evaluate("answer() / 2", &functions), Ok(21));
+    assert_eq!(evaluate("nope(1)", &functions), Err(ExpressionError::UnknownFunction("nope".to_string())));
+    assert_eq!(evaluate("abs(1, 2)", &functions), Err(ExpressionError::WrongArgumentCount {
+        name: "abs".to_string(),
+        arity: Arity::Fixed(1),
+        found: 2,
+    }));
+}
+
+#[test]
+pub fn convert_logic_to_rpn_test() {
+    assert_eq!(convert_to_rpn("a > 3 && b <= 10 || c == 0"), Ok(vec![
+        Token::Variable("a".to_string()),
+        Token::Number(3),
+        Token::Operator(Operator::Gt),
+        Token::Variable("b".to_string()),
+        Token::Number(10),
+        Token::Operator(Operator::Le),
+        Token::Operator(Operator::And),
+        Token::Variable("c".to_string()),
+        Token::Number(0),
+        Token::Operator(Operator::Eq),
+        Token::Operator(Operator::Or),
+    ]));
+    assert_eq!(convert_to_rpn("a ? 1 : b ? 2 : 3"), Ok(vec![
+        Token::Variable("a".to_string()),
+        Token::Number(1),
+        Token::Variable("b".to_string()),
+        Token::Number(2),
+        Token::Number(3),
+        Token::Conditional,
+        Token::Conditional,
+    ]));
+    assert_eq!(convert_to_rpn("a ? b ? 1 : 2 : 3"), Ok(vec![
+        Token::Variable("a".to_string()),
+        Token::Variable("b".to_string()),
+        Token::Number(1),
+        Token::Number(2),
+        Token::Conditional,
+        Token::Number(3),
+        Token::Conditional,
+    ]));
+}
+
+#[test]
+pub fn convert_syntax_errors_test() {
+    let error = |input: &str| match convert_to_rpn(input) {
+        Err(ExpressionError::Syntax { message, span }) => (message, span.start, span.end),
+        other => panic!("expected a syntax error but got {:?}", other),
+    };
+    assert_eq!(error("1 +"), ("unexpected end of input".to_string(), 3, 3));
+    assert_eq!(error("1 2"), ("expected an operator".to_string(), 2, 3));
+    assert_eq!(error("* 2"), ("expected an operand".to_string(), 0, 1));
+    assert_eq!(error("1 - -"), ("unexpected end of input".to_string(), 5, 5));
+    assert_eq!(error("f(-)"), ("expected an operand".to_string(), 3, 4));
+    assert_eq!(error("(1 + 2"), ("unclosed '('".to_string(), 0, 1));
+    assert_eq!(error("1 + 2)"), ("unmatched ')'".to_string(), 5, 6));
+    assert_eq!(error("()"), ("expected an operand".to_string(), 1, 2));
+    assert_eq!(error("(1, 2)"), ("',' outside of a function call".to_string(), 2, 3));
+    assert_eq!(error("f(1,)"), ("expected an operand".to_string(), 4, 5));
+    assert_eq!(error("a ? 1"), ("'?' without matching ':'".to_string(), 2, 3));
+    assert_eq!(error("a : 1"), ("':' without matching '?'".to_string(), 2, 3));
+    assert_eq!(error("f(a ? 1, 2)"), ("expected ':'".to_string(), 7, 8));
+    assert_eq!(error("1 + 0x"), ("expected digits after the radix prefix".to_string(), 4, 6));
+}
+
+#[test]
+pub fn evaluate_literals_test() {
+    use crate::expression::value::Value;
+
+    let functions = Functions::with_builtins();
+    let evaluate = |input: &str| Expr::parse(input)?.evaluate(&functions, &HashMap::new());
+    assert_eq!(evaluate("0xFF + 0b1 + 0o10 + 1_000"), Ok(Value::Int(1264)));
+    assert_eq!(evaluate("1.5 * 2"), Ok(Value::Float(3.0)));
+    assert_eq!(evaluate(".5 + 1e-1"), Ok(Value::Float(0.6)));
+    assert_eq!(evaluate("7 / 2"), Ok(Value::Int(3)));
+    assert_eq!(evaluate("7 / 2.0"), Ok(Value::Float(3.5)));
+    assert_eq!(evaluate("2 ^ 0.5 > 1.41"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("1 == 1.0"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("max(1, 2.5) + sqrt(6.25)"), Ok(Value::Float(5.0)));
+    assert_eq!(shunting_yard("0x10 * 2"), 32);
+}
@@ -0,0 +1,405 @@
+use crate::expression::ast::{apply, Expr};
+use crate::expression::error::ExpressionError;
+use crate::expression::shunting_yard::Operator;
+use crate::expression::value::Value;
+
+/// The largest number of terms [Expr::expand] multiplies a product or power of sums out into,
+/// larger ones are left as they are since `(a + b + c + d)^16` alone would have 4^16 terms
+const MAX_EXPANDED_TERMS: usize = 1024;
+
+fn int(value: i32) -> Expr {
+    Expr::Literal(Value::Int(value))
+}
+
+fn binary(operator: Operator, left: Expr, right: Expr) -> Expr {
+    Expr::Binary(operator, Box::new(left), Box::new(right))
+}
+
+fn call(name: &str, arguments: Vec<Expr>) -> Expr {
+    Expr::Call(name.to_string(), arguments)
+}
+
+/// Computes the value of an operator applied to two constants
+/// returns None if evaluating it would fail, so that the error is left for evaluation to report
+fn fold(operator: Operator, left: Value, right: Value) -> Option<Value> {
+    apply(operator, left, right).ok()
+}
+
+/// returns true iff the expression evaluates to a number or fails, assuming its variables are bound to numbers
+/// Only such operands may be kept alone by an identity, otherwise `0 + (a > b)` would lose its type error
+fn is_numeric(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(value) => !matches!(value, Value::Bool(_)),
+        Expr::Variable(_) => true,
+        Expr::Binary(operator, _, _) => matches!(operator, Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Pow),
+        Expr::Conditional(_, then, otherwise) => is_numeric(then) && is_numeric(otherwise),
+        Expr::Call(_, _) => false,
+    }
+}
+
+/// Adds or subtracts two derivatives, leaving out terms that are exactly 0
+fn sum(operator: Operator, left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (left, right) if right == int(0) => left,
+        (left, right) if left == int(0) && operator == Operator::Add => right,
+        (left, right) => binary(operator, left, right),
+    }
+}
+
+/// Multiplies a derivative with a factor, a factor of 0 makes the product 0 and factors of 1 are left out
+///
+/// Unlike [Expr::simplify] this drops operands, which is what the derivative means mathematically.
+fn product(left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (left, right) if left == int(0) || right == int(0) => int(0),
+        (left, right) if left == int(1) => right,
+        (left, right) if right == int(1) => left,
+        (left, right) => binary(Operator::Mul, left, right),
+    }
+}
+
+impl Expr {
+    /// returns true iff the variable occurs somewhere in the expression
+    pub fn depends_on(&self, variable: &str) -> bool {
+        match self {
+            Expr::Literal(_) => false,
+            Expr::Variable(name) => name == variable,
+            Expr::Binary(_, left, right) => left.depends_on(variable) || right.depends_on(variable),
+            Expr::Conditional(condition, then, otherwise) => {
+                condition.depends_on(variable) || then.depends_on(variable) || otherwise.depends_on(variable)
+            }
+            Expr::Call(_, arguments) => arguments.iter().any(|argument| argument.depends_on(variable)),
+        }
+    }
+
+    /// Returns a simplified copy of the expression
+    ///
+    /// Operators applied to constants are folded, identities like `x*1`, `x+0` and `x^1` are removed
+    /// and conditionals with a constant condition are replaced by their branch. Identities are only applied with
+    /// integer constants to operands that are numbers, since removing a float constant could turn a float result
+    /// into an integer and removing one from `0 + (a > b)` would hide its error. Identities like `x-x = 0` or
+    /// `0*x = 0` are not applied, the type of the result depends on x and for `x = inf` the result is NaN.
+    pub fn simplify(&self) -> Expr {
+        match self {
+            Expr::Literal(_) | Expr::Variable(_) => self.clone(),
+            Expr::Binary(operator, left, right) => simplify_binary(*operator, left.simplify(), right.simplify()),
+            Expr::Conditional(condition, then, otherwise) => match condition.simplify() {
+                Expr::Literal(Value::Bool(true)) => then.simplify(),
+                Expr::Literal(Value::Bool(false)) => otherwise.simplify(),
+                condition => Expr::Conditional(Box::new(condition), Box::new(then.simplify()), Box::new(otherwise.simplify())),
+            },
+            Expr::Call(name, arguments) => Expr::Call(name.clone(), arguments.iter().map(Expr::simplify).collect()),
+        }
+    }
+
+    /// Returns a copy of the expression in which products of sums are multiplied out,
+    /// so `(a + b) * c` becomes `a*c + b*c` and `(a + b)^2` becomes `a*a + a*b + b*a + b*b`
+    ///
+    /// Products and powers that would have more than 1024 terms are left as they are.
+    pub fn expand(&self) -> Expr {
+        let expanded = match self {
+            Expr::Literal(_) | Expr::Variable(_) => self.clone(),
+            Expr::Binary(Operator::Mul, left, right) => match (left.expand(), right.expand()) {
+                (left, right) if term_count(&left).saturating_mul(term_count(&right)) <= MAX_EXPANDED_TERMS => distribute(left, right),
+                (left, right) => binary(Operator::Mul, left, right),
+            },
+            Expr::Binary(Operator::Pow, base, exponent) => match (base.expand(), exponent.simplify()) {
+                (base @ Expr::Binary(Operator::Add | Operator::Sub, _, _), Expr::Literal(Value::Int(n)))
+                    if n >= 2 && term_count(&base).checked_pow(n as u32).is_some_and(|terms| terms <= MAX_EXPANDED_TERMS) =>
+                {
+                    (1..n).fold(base.clone(), |product, _| distribute(product, base.clone()))
+                }
+                (base, exponent) => binary(Operator::Pow, base, exponent),
+            },
+            Expr::Binary(operator, left, right) => binary(*operator, left.expand(), right.expand()),
+            Expr::Conditional(condition, then, otherwise) => {
+                Expr::Conditional(Box::new(condition.expand()), Box::new(then.expand()), Box::new(otherwise.expand()))
+            }
+            Expr::Call(name, arguments) => Expr::Call(name.clone(), arguments.iter().map(Expr::expand).collect()),
+        };
+        expanded.simplify()
+    }
+
+    /// Returns the simplified derivative of the expression with respect to the variable
+    ///
+    /// Besides the arithmetic operators, conditionals and the functions sqrt, exp, ln, abs, if and
+    /// max and min with two arguments can be differentiated. The derivative treats every value as real,
+    /// so it should be evaluated with float arguments.
+    pub fn differentiate(&self, variable: &str) -> Result<Expr, ExpressionError> {
+        Ok(self.derivative(variable)?.simplify())
+    }
+
+    fn derivative(&self, variable: &str) -> Result<Expr, ExpressionError> {
+        if !self.depends_on(variable) {
+            return Ok(int(0));
+        }
+        let d = |expr: &Expr| expr.derivative(variable);
+        Ok(match self {
+            Expr::Literal(_) => int(0),
+            Expr::Variable(_) => int(1),
+            Expr::Binary(operator @ (Operator::Add | Operator::Sub), left, right) => sum(*operator, d(left)?, d(right)?),
+            Expr::Binary(Operator::Mul, left, right) => sum(
+                Operator::Add,
+                product(d(left)?, *right.clone()),
+                product(*left.clone(), d(right)?),
+            ),
+            Expr::Binary(Operator::Div, left, right) => binary(
+                Operator::Div,
+                sum(
+                    Operator::Sub,
+                    product(d(left)?, *right.clone()),
+                    product(*left.clone(), d(right)?),
+                ),
+                binary(Operator::Pow, *right.clone(), int(2)),
+            ),
+            Expr::Binary(Operator::Pow, base, exponent) => {
+                let base = *base.clone();
+                let exponent = *exponent.clone();
+                let power = binary(Operator::Pow, base.clone(), exponent.clone());
+                if !exponent.depends_on(variable) {
+                    // d(u^n) = n * u^(n-1) * u'
+                    let reduced = binary(Operator::Pow, base.clone(), binary(Operator::Sub, exponent.clone(), int(1)));
+                    product(binary(Operator::Mul, exponent, reduced), d(&base)?)
+                } else if !base.depends_on(variable) {
+                    // d(a^v) = a^v * ln(a) * v'
+                    product(binary(Operator::Mul, power, call("ln", vec![base])), d(&exponent)?)
+                } else {
+                    // d(u^v) = u^v * (v' * ln(u) + v * u' / u)
+                    let inner = sum(
+                        Operator::Add,
+                        product(d(&exponent)?, call("ln", vec![base.clone()])),
+                        binary(Operator::Div, product(exponent, d(&base)?), base),
+                    );
+                    binary(Operator::Mul, power, inner)
+                }
+            }
+            Expr::Binary(operator, _, _) => {
+                return Err(ExpressionError::NotDifferentiable(format!("operator {} does not have a derivative", operator.symbol())));
+            }
+            // a conditional is differentiated piecewise, ignoring the points where the condition changes
+            Expr::Conditional(condition, then, otherwise) => {
+                Expr::Conditional(condition.clone(), Box::new(d(then)?), Box::new(d(otherwise)?))
+            }
+            Expr::Call(name, arguments) => match (name.as_str(), arguments.as_slice()) {
+                ("sqrt", [argument]) => binary(Operator::Div, d(argument)?, binary(Operator::Mul, int(2), self.clone())),
+                ("exp", [argument]) => product(self.clone(), d(argument)?),
+                ("ln", [argument]) => binary(Operator::Div, d(argument)?, argument.clone()),
+                ("abs", [argument]) => binary(Operator::Div, product(argument.clone(), d(argument)?), self.clone()),
+                ("if", [condition, then, otherwise]) => call("if", vec![condition.clone(), d(then)?, d(otherwise)?]),
+                ("max" | "min", [left, right]) => {
+                    let comparison = if name == "max" { Operator::Ge } else { Operator::Le };
+                    Expr::Conditional(
+                        Box::new(binary(comparison, left.clone(), right.clone())),
+                        Box::new(d(left)?),
+                        Box::new(d(right)?),
+                    )
+                }
+                _ => return Err(ExpressionError::NotDifferentiable(format!("function {} with {} arguments does not have a derivative", name, arguments.len()))),
+            },
+        })
+    }
+}
+
+fn simplify_binary(operator: Operator, left: Expr, right: Expr) -> Expr {
+    if let (Expr::Literal(left), Expr::Literal(right)) = (&left, &right) {
+        if let Some(value) = fold(operator, *left, *right) {
+            return Expr::Literal(value);
+        }
+    }
+
+    let zero = int(0);
+    let one = int(1);
+    match operator {
+        Operator::Add if left == zero && is_numeric(&right) => right,
+        Operator::Add | Operator::Sub if right == zero && is_numeric(&left) => left,
+        Operator::Mul if left == one && is_numeric(&right) => right,
+        Operator::Mul | Operator::Div | Operator::Pow if right == one && is_numeric(&left) => left,
+        Operator::And if left == Expr::Literal(Value::Bool(true)) => right,
+        Operator::And if left == Expr::Literal(Value::Bool(false)) => left,
+        Operator::Or if left == Expr::Literal(Value::Bool(false)) => right,
+        Operator::Or if left == Expr::Literal(Value::Bool(true)) => left,
+        // constants are moved to the front of products and the end of sums, so that they can be combined
+        Operator::Mul if matches!(right, Expr::Literal(_)) && !matches!(left, Expr::Literal(_)) => simplify_binary(operator, right, left),
+        Operator::Add if matches!(left, Expr::Literal(_)) && !matches!(right, Expr::Literal(_)) => simplify_binary(operator, right, left),
+        Operator::Mul | Operator::Add => match regroup(operator, &left, &right) {
+            Some((constant, rest)) if operator == Operator::Mul => simplify_binary(operator, Expr::Literal(constant), rest),
+            Some((constant, rest)) => simplify_binary(operator, rest, Expr::Literal(constant)),
+            None => binary(operator, left, right),
+        },
+        _ => binary(operator, left, right),
+    }
+}
+
+/// Finds `c2 * (c1 * e)` or `(e + c1) + c2` and returns the combined constant and e, if applying the
+/// combined constant at once gives the same result as applying c1 and then c2
+fn regroup(operator: Operator, left: &Expr, right: &Expr) -> Option<(Value, Expr)> {
+    let (inner, second) = match (operator, left, right) {
+        (Operator::Mul, Expr::Literal(second), inner) | (Operator::Add, inner, Expr::Literal(second)) => (inner, *second),
+        _ => return None,
+    };
+    let (first, rest) = match (operator, inner) {
+        (Operator::Mul, Expr::Binary(Operator::Mul, first, rest)) | (Operator::Add, Expr::Binary(Operator::Add, rest, first)) => (first, rest),
+        _ => return None,
+    };
+    let Expr::Literal(first) = **first else {
+        return None;
+    };
+    let combinable = match (operator, first, second) {
+        // the partial sum lies between e and the final sum, so it only overflows if the final sum does
+        (Operator::Add, Value::Int(first), Value::Int(second)) => (first >= 0) == (second >= 0),
+        // a second factor of 1 changes nothing and one of magnitude 2 or more keeps the partial product below
+        // half the final product, so again it only overflows if the final product does
+        (Operator::Mul, Value::Int(_), Value::Int(second)) => second == 1 || second.unsigned_abs() >= 2,
+        // floats are rounded after every operation, so regrouping them changes the result
+        _ => false,
+    };
+    if !combinable {
+        return None;
+    }
+    Some((fold(operator, first, second)?, (**rest).clone()))
+}
+
+/// returns the number of terms of a sum or difference, counting nested ones
+fn term_count(expr: &Expr) -> usize {
+    match expr {
+        Expr::Binary(Operator::Add | Operator::Sub, left, right) => term_count(left) + term_count(right),
+        _ => 1,
+    }
+}
+
+/// Multiplies two expressions, distributing the product over sums and differences in either of them
+fn distribute(left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (Expr::Binary(operator @ (Operator::Add | Operator::Sub), a, b), right) => {
+            binary(operator, distribute(*a, right.clone()), distribute(*b, right))
+        }
+        (left, Expr::Binary(operator @ (Operator::Add | Operator::Sub), a, b)) => {
+            binary(operator, distribute(left.clone(), *a), distribute(left, *b))
+        }
+        (left, right) => binary(Operator::Mul, left, right).simplify(),
+    }
+}
+
+#[test]
+pub fn simplify_test() {
+    let simplify = |input: &str| Expr::parse(input).unwrap().simplify();
+    let parse = |input: &str| Expr::parse(input).unwrap();
+    assert_eq!(simplify("2 * 3 + 4"), parse("10"));
+    assert_eq!(simplify("x * 1 + 0"), parse("x"));
+    assert_eq!(simplify("1 * (x + 0) ^ 1"), parse("x"));
+    assert_eq!(simplify("0 + x * 1 + y / 1"), parse("x + y"));
+    assert_eq!(simplify("2 * (3 * x)"), parse("6 * x"));
+    assert_eq!(simplify("(x * 2) * 3"), parse("6 * x"));
+    assert_eq!(simplify("(x + 2) + 3"), parse("x + 5"));
+    assert_eq!(simplify("2 > 1 ? x : y"), parse("x"));
+    assert_eq!(simplify("1 < 0 && x"), Expr::Literal(Value::Bool(false)));
+    assert_eq!(simplify("max(1 + 1, x * 1)"), parse("max(2, x)"));
+    // removing these would turn a float result into an integer or hide an error
+    assert_eq!(simplify("x * 1.0"), parse("1.0 * x"));
+    assert_eq!(simplify("1 / 0"), parse("1 / 0"));
+    assert_eq!(simplify("0 * (1 / 0)"), parse("0 * (1 / 0)"));
+    assert_eq!(simplify("0 * x"), parse("0 * x"));
+    assert_eq!(simplify("x - x"), parse("x - x"));
+    assert_eq!(simplify("x ^ 0"), parse("x ^ 0"));
+    assert_eq!(simplify("0 + (a > b)"), parse("(a > b) + 0"));
+    assert_eq!(simplify("f(x) * 1"), parse("1 * f(x)"));
+    assert_eq!(simplify("2 ^ 40"), parse("2 ^ 40"));
+    // combining the constants would overflow later or not at all, or round differently
+    assert_eq!(simplify("(x + 2147483647) + (0 - 1)"), parse("x + 2147483647 + -1"));
+    assert_eq!(simplify("0 - 1 + (x + 2147483647)"), parse("x + 2147483647 + -1"));
+    assert_eq!(simplify("(x * 65536) * 0"), parse("0 * (65536 * x)"));
+    assert_eq!(simplify("(x * 65536) * (0 - 1)"), parse("-1 * (65536 * x)"));
+    assert_eq!(simplify("(x + 0.1) + 0.2"), parse("x + 0.1 + 0.2"));
+    assert_eq!(simplify("(x - 1) + 3"), parse("x - 1 + 3"));
+    assert_eq!(simplify("(x + 3) + 4 + (0 - 2) + (0 - 5)"), parse("x + 7 + -7"));
+    assert_eq!(simplify("-2 * (x * -3)"), parse("6 * x"));
+
+    use crate::expression::functions::Functions;
+    use std::collections::HashMap;
+    let functions = Functions::with_builtins();
+    let mut variables = HashMap::new();
+    variables.insert("x".to_string(), Value::Float(f64::INFINITY));
+    variables.insert("y".to_string(), Value::Float(2.5));
+    variables.insert("z".to_string(), Value::Int(2));
+    for input in ["x - x", "0 * y + y ^ 1", "(y + 0) * 1 / 1", "y ^ 0", "x * 0 + 1", "(z + 2147483646) + 1 + -1", "(z * 1073741824) * -2"] {
+        let expected = parse(input).evaluate(&functions, &variables);
+        let actual = simplify(input).evaluate(&functions, &variables);
+        // NaN is not equal to itself, so values are compared as text, and an overflow may name other operands
+        match (actual, expected) {
+            (Err(ExpressionError::InvalidArgument(_)), Err(ExpressionError::InvalidArgument(_))) => {}
+            (actual, expected) => assert_eq!(format!("{:?}", actual), format!("{:?}", expected), "{}", input),
+        }
+    }
+}
+
+#[test]
+pub fn expand_test() {
+    let expand = |input: &str| Expr::parse(input).unwrap().expand();
+    let parse = |input: &str| Expr::parse(input).unwrap();
+    assert_eq!(expand("(a + b) * c"), parse("a * c + b * c"));
+    assert_eq!(expand("2 * (a - 1)"), parse("2 * a - 2"));
+    assert_eq!(expand("(a + b) ^ 2"), parse("(a * a + a * b) + (b * a + b * b)"));
+    assert_eq!(expand("f((a + 1) * b)"), parse("f(a * b + b)"));
+    assert_eq!(term_count(&expand("(a + b) ^ 10")), 1024);
+    // these would have 4^16 and 2^11 terms
+    assert_eq!(expand("(a + b + c + d) ^ 16"), parse("(a + b + c + d) ^ 16"));
+    assert_eq!(expand("(a + b) ^ 10 * (c - d)"), binary(Operator::Mul, expand("(a + b) ^ 10"), parse("c - d")));
+
+    use crate::expression::functions::Functions;
+    use std::collections::HashMap;
+    let functions = Functions::with_builtins();
+    let mut variables = HashMap::new();
+    variables.insert("a".to_string(), Value::Int(3));
+    variables.insert("b".to_string(), Value::Int(-4));
+    variables.insert("c".to_string(), Value::Int(7));
+    for input in ["(a + b) * (a - c) * (b + 2)", "(a - b + c) ^ 3", "a * (b + c * (a + 1))"] {
+        let expected = parse(input).evaluate(&functions, &variables);
+        assert_eq!(expand(input).evaluate(&functions, &variables), expected, "{}", input);
+    }
+}
+
+#[test]
+pub fn differentiate_test() {
+    let differentiate = |input: &str| Expr::parse(input).unwrap().differentiate("x").unwrap();
+    let parse = |input: &str| Expr::parse(input).unwrap();
+    assert_eq!(differentiate("y * 3"), parse("0"));
+    assert_eq!(differentiate("x"), parse("1"));
+    assert_eq!(differentiate("x ^ 2"), parse("2 * x"));
+    assert_eq!(differentiate("3 * x + y"), parse("3"));
+    assert_eq!(differentiate("x * y"), parse("y"));
+    assert_eq!(differentiate("exp(2 * x)"), parse("2 * exp(2 * x)"));
+    assert_eq!(differentiate("ln(x)"), parse("1 / x"));
+    assert!(matches!(Expr::parse("x > 1").unwrap().differentiate("x"), Err(ExpressionError::NotDifferentiable(_))));
+    assert!(matches!(Expr::parse("gcd(x, 2)").unwrap().differentiate("x"), Err(ExpressionError::NotDifferentiable(_))));
+
+    // compares the derivatives against central differences
+    use crate::expression::functions::Functions;
+    use std::collections::HashMap;
+    let functions = Functions::with_builtins();
+    let at = |x: f64| {
+        let mut variables = HashMap::new();
+        variables.insert("x".to_string(), Value::Float(x));
+        variables.insert("y".to_string(), Value::Float(0.75));
+        variables
+    };
+    let value = |expr: &Expr, x: f64| expr.evaluate(&functions, &at(x)).unwrap().as_float("test").unwrap();
+    let inputs = [
+        "x ^ 3 - 2 * x * y + 1",
+        "(x + 1) / (x * x + y)",
+        "sqrt(x * x + 1) * exp(0 - x * y)",
+        "ln(x) * x ^ y + 2 ^ x",
+        "x ^ x",
+        "max(x * x, y) + abs(x - 2)",
+        "x > 1 ? x * x : 2 * x",
+    ];
+    for input in inputs {
+        let expr = parse(input);
+        let derivative = expr.differentiate("x").unwrap();
+        for x in [0.5, 1.25, 3.0] {
+            let h = 1e-6;
+            let expected = (value(&expr, x + h) - value(&expr, x - h)) / (2.0 * h);
+            let actual = value(&derivative, x);
+            assert!((expected - actual).abs() < 1e-4 * expected.abs().max(1.0), "d/dx {} at {}: {} != {}", input, x, actual, expected);
+        }
+    }
+}
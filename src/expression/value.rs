@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+
+use crate::expression::error::ExpressionError;
+
+/// The result of evaluating an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Value {
+    /// returns the integer held by this value or an error naming the operation that required it
+    pub fn as_int(&self, operation: &str) -> Result<i32, ExpressionError> {
+        match *self {
+            Value::Int(value) => Ok(value),
+            _ => Err(ExpressionError::TypeMismatch(format!("{} expects an integer but got {}", operation, self))),
+        }
+    }
+
+    /// returns the number held by this value as a float or an error naming the operation that required it
+    pub fn as_float(&self, operation: &str) -> Result<f64, ExpressionError> {
+        match *self {
+            Value::Int(value) => Ok(value as f64),
+            Value::Float(value) => Ok(value),
+            Value::Bool(_) => Err(ExpressionError::TypeMismatch(format!("{} expects a number but got {}", operation, self))),
+        }
+    }
+
+    /// returns the boolean held by this value or an error naming the operation that required it
+    pub fn as_bool(&self, operation: &str) -> Result<bool, ExpressionError> {
+        match *self {
+            Value::Bool(value) => Ok(value),
+            _ => Err(ExpressionError::TypeMismatch(format!("{} expects a boolean but got {}", operation, self))),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            // debug formatting keeps the decimal point, so floats can be told apart from integers
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
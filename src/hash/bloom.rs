@@ -0,0 +1,401 @@
+use std::fmt::{Display, Formatter};
+
+use crate::hash::murmur3::murmur3_128;
+use crate::hash::to_bytes::ToBytes;
+
+/// the first bytes of a serialized [BloomFilter]
+const BLOOM_MAGIC: &[u8; 4] = b"BLM1";
+/// the first bytes of a serialized [CountingBloomFilter]
+const COUNTING_MAGIC: &[u8; 4] = b"CBF1";
+/// the length of the magic bytes, the number of bits as u64 and the number of hash functions as u32
+const HEADER_LENGTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum BloomError {
+    /// two filters with a different number of bits or hash functions cannot be combined
+    IncompatibleFilters,
+    /// the bytes passed to from_bytes do not describe a filter
+    InvalidData(String),
+}
+
+impl Display for BloomError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BloomError::IncompatibleFilters => write!(f, "the filters differ in their number of bits or hash functions"),
+            BloomError::InvalidData(message) => write!(f, "invalid filter data: {}", message),
+        }
+    }
+}
+
+/// returns the number of bits and hash functions that keep the false positive rate of a filter
+/// holding expected_items keys at false_positive_rate
+fn optimal_parameters(expected_items: usize, false_positive_rate: f64) -> (usize, u32) {
+    assert!(false_positive_rate > 0.0 && false_positive_rate < 1.0, "the false positive rate must be in (0, 1)");
+    let items = expected_items.max(1) as f64;
+    let ln2 = std::f64::consts::LN_2;
+    let bit_count = (-items * false_positive_rate.ln() / (ln2 * ln2)).ceil() as usize;
+    let hash_count = (bit_count as f64 / items * ln2).round().max(1.0) as u32;
+    (bit_count.max(1), hash_count)
+}
+
+/// returns the positions of the key in a filter of bit_count positions
+///
+/// The positions are h1 + i * h2 for two halves of a 128 bit hash, which is as good as
+/// hash_count independent hash functions but needs only one hash of the key.
+fn positions<K: ToBytes + ?Sized>(key: &K, bit_count: usize, hash_count: u32) -> impl Iterator<Item = usize> {
+    let (h1, h2) = murmur3_128(&key.to_bytes(), 0);
+    (0..hash_count as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_count as u64) as usize)
+}
+
+/// reads the header written by to_bytes and returns the number of bits, hash functions and the payload
+fn read_header<'a>(bytes: &'a [u8], magic: &[u8; 4]) -> Result<(usize, u32, &'a [u8]), BloomError> {
+    let invalid = |message: &str| BloomError::InvalidData(message.to_string());
+    if bytes.len() < HEADER_LENGTH || &bytes[..4] != magic {
+        return Err(invalid("missing header"));
+    }
+    let bit_count = u64::from_be_bytes(bytes[4..12].try_into().expect("8 bytes")) as usize;
+    let hash_count = u32::from_be_bytes(bytes[12..16].try_into().expect("4 bytes"));
+    if bit_count == 0 || hash_count == 0 {
+        return Err(invalid("a filter needs bits and hash functions"));
+    }
+    Ok((bit_count, hash_count, &bytes[HEADER_LENGTH..]))
+}
+
+fn write_header(magic: &[u8; 4], bit_count: usize, hash_count: u32, payload_length: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload_length);
+    bytes.extend_from_slice(magic);
+    bytes.extend((bit_count as u64).to_be_bytes());
+    bytes.extend(hash_count.to_be_bytes());
+    bytes
+}
+
+/// A set that answers membership queries with false positives but never with false negatives
+///
+/// Every key sets the bits at k positions derived from its hash, and a key is reported as contained if all
+/// of its bits are set. The filter never stores the keys, so it takes a few bits per key regardless of their size.
+/// Keys are hashed through their [ToBytes] representation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    words: Vec<u64>,
+    bit_count: usize,
+    hash_count: u32,
+}
+
+impl BloomFilter {
+    /// creates a filter that has a false positive rate of false_positive_rate once it holds expected_items keys
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let (bit_count, hash_count) = optimal_parameters(expected_items, false_positive_rate);
+        Self::with_parameters(bit_count, hash_count)
+    }
+
+    pub fn with_parameters(bit_count: usize, hash_count: u32) -> Self {
+        assert!(bit_count > 0 && hash_count > 0, "a filter needs bits and hash functions");
+        Self {
+            words: vec![0; bit_count.div_ceil(64)],
+            bit_count,
+            hash_count,
+        }
+    }
+
+    pub fn bit_count(&self) -> usize {
+        self.bit_count
+    }
+
+    pub fn hash_count(&self) -> u32 {
+        self.hash_count
+    }
+
+    fn bit(&self, position: usize) -> bool {
+        self.words[position / 64] & 1 << (position % 64) != 0
+    }
+
+    /// adds the key, returns false if all of its bits were already set
+    pub fn insert<K: ToBytes + ?Sized>(&mut self, key: &K) -> bool {
+        let mut changed = false;
+        for position in positions(key, self.bit_count, self.hash_count) {
+            changed |= !self.bit(position);
+            self.words[position / 64] |= 1 << (position % 64);
+        }
+        changed
+    }
+
+    /// returns whether the key may have been inserted, false means it was certainly not
+    pub fn contains<K: ToBytes + ?Sized>(&self, key: &K) -> bool {
+        positions(key, self.bit_count, self.hash_count).all(|position| self.bit(position))
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// returns the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// estimates the number of distinct keys inserted from the number of set bits
+    pub fn estimated_len(&self) -> f64 {
+        let m = self.bit_count as f64;
+        -m / self.hash_count as f64 * (1.0 - self.count_ones() as f64 / m).ln()
+    }
+
+    /// returns the chance that a key that was not inserted is reported as contained
+    pub fn false_positive_rate(&self) -> f64 {
+        (self.count_ones() as f64 / self.bit_count as f64).powi(self.hash_count as i32)
+    }
+
+    fn check_compatible(&self, other: &Self) -> Result<(), BloomError> {
+        if self.bit_count != other.bit_count || self.hash_count != other.hash_count {
+            return Err(BloomError::IncompatibleFilters);
+        }
+        Ok(())
+    }
+
+    /// returns a filter that contains every key of both filters, as if they had been inserted into one filter
+    pub fn union(&self, other: &Self) -> Result<Self, BloomError> {
+        self.check_compatible(other)?;
+        let words = self.words.iter().zip(&other.words).map(|(a, b)| a | b).collect();
+        Ok(Self { words, ..*self })
+    }
+
+    /// returns a filter that contains every key of both filters
+    ///
+    /// It may have more false positives than a filter of only the common keys,
+    /// because bits set by different keys in each filter survive as well.
+    pub fn intersection(&self, other: &Self) -> Result<Self, BloomError> {
+        self.check_compatible(other)?;
+        let words = self.words.iter().zip(&other.words).map(|(a, b)| a & b).collect();
+        Ok(Self { words, ..*self })
+    }
+
+    /// serializes the filter as the magic bytes BLM1, the number of bits as big endian u64,
+    /// the number of hash functions as big endian u32 and the bits as big endian u64 words
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = write_header(BLOOM_MAGIC, self.bit_count, self.hash_count, 8 * self.words.len());
+        for word in &self.words {
+            bytes.extend(word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// loads a filter serialized by [BloomFilter::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let (bit_count, hash_count, payload) = read_header(bytes, BLOOM_MAGIC)?;
+        if bit_count.div_ceil(64).checked_mul(8) != Some(payload.len()) {
+            return Err(BloomError::InvalidData("the number of words does not match the number of bits".to_string()));
+        }
+        let words = payload.chunks_exact(8).map(|word| u64::from_be_bytes(word.try_into().expect("8 bytes"))).collect();
+        Ok(Self { words, bit_count, hash_count })
+    }
+}
+
+/// A Bloom filter that keeps a counter instead of a bit at each position, so keys can be removed again
+///
+/// Counters saturate at 255. A saturated counter is never decremented, which keeps the filter free
+/// of false negatives at the cost of positions that can no longer be cleared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    hash_count: u32,
+}
+
+impl CountingBloomFilter {
+    /// creates a filter that has a false positive rate of false_positive_rate once it holds expected_items keys
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let (counter_count, hash_count) = optimal_parameters(expected_items, false_positive_rate);
+        Self::with_parameters(counter_count, hash_count)
+    }
+
+    pub fn with_parameters(counter_count: usize, hash_count: u32) -> Self {
+        assert!(counter_count > 0 && hash_count > 0, "a filter needs counters and hash functions");
+        Self {
+            counters: vec![0; counter_count],
+            hash_count,
+        }
+    }
+
+    pub fn counter_count(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn hash_count(&self) -> u32 {
+        self.hash_count
+    }
+
+    pub fn insert<K: ToBytes + ?Sized>(&mut self, key: &K) {
+        for position in positions(key, self.counters.len(), self.hash_count) {
+            self.counters[position] = self.counters[position].saturating_add(1);
+        }
+    }
+
+    /// removes one insertion of the key, returns false and changes nothing if the key is not contained
+    ///
+    /// Removing a key that was never inserted but is a false positive removes parts of other keys,
+    /// so only keys known to be inserted should be removed.
+    pub fn remove<K: ToBytes + ?Sized>(&mut self, key: &K) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        for position in positions(key, self.counters.len(), self.hash_count) {
+            if self.counters[position] < u8::MAX {
+                self.counters[position] -= 1;
+            }
+        }
+        true
+    }
+
+    pub fn contains<K: ToBytes + ?Sized>(&self, key: &K) -> bool {
+        positions(key, self.counters.len(), self.hash_count).all(|position| self.counters[position] > 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+    }
+
+    fn check_compatible(&self, other: &Self) -> Result<(), BloomError> {
+        if self.counters.len() != other.counters.len() || self.hash_count != other.hash_count {
+            return Err(BloomError::IncompatibleFilters);
+        }
+        Ok(())
+    }
+
+    /// returns a filter holding the insertions of both filters
+    pub fn union(&self, other: &Self) -> Result<Self, BloomError> {
+        self.check_compatible(other)?;
+        let counters = self.counters.iter().zip(&other.counters).map(|(a, b)| a.saturating_add(*b)).collect();
+        Ok(Self { counters, hash_count: self.hash_count })
+    }
+
+    /// returns a filter that contains every key of both filters, counting each position as often as the smaller filter
+    pub fn intersection(&self, other: &Self) -> Result<Self, BloomError> {
+        self.check_compatible(other)?;
+        let counters = self.counters.iter().zip(&other.counters).map(|(a, b)| *a.min(b)).collect();
+        Ok(Self { counters, hash_count: self.hash_count })
+    }
+
+    /// returns the plain filter with a bit set wherever a counter is not zero
+    pub fn to_bloom_filter(&self) -> BloomFilter {
+        let mut filter = BloomFilter::with_parameters(self.counters.len(), self.hash_count);
+        for (position, _) in self.counters.iter().enumerate().filter(|(_, &counter)| counter > 0) {
+            filter.words[position / 64] |= 1 << (position % 64);
+        }
+        filter
+    }
+
+    /// serializes the filter as the magic bytes CBF1, the number of counters as big endian u64,
+    /// the number of hash functions as big endian u32 and a byte per counter
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = write_header(COUNTING_MAGIC, self.counters.len(), self.hash_count, self.counters.len());
+        bytes.extend_from_slice(&self.counters);
+        bytes
+    }
+
+    /// loads a filter serialized by [CountingBloomFilter::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let (counter_count, hash_count, payload) = read_header(bytes, COUNTING_MAGIC)?;
+        if counter_count != payload.len() {
+            return Err(BloomError::InvalidData("the number of counters does not match the header".to_string()));
+        }
+        Ok(Self {
+            counters: payload.to_vec(),
+            hash_count,
+        })
+    }
+}
+
+#[test]
+pub fn bloom_filter_test() {
+    let mut filter = BloomFilter::new(10_000, 0.01);
+    // about 9.6 bits and 7 hash functions per key are optimal for a rate of 1%
+    assert_eq!(filter.hash_count(), 7);
+    assert!((95_000..97_000).contains(&filter.bit_count()));
+
+    for i in 0..10_000u32 {
+        filter.insert(&i);
+    }
+    assert!((0..10_000u32).all(|i| filter.contains(&i)), "a Bloom filter has no false negatives");
+    let false_positives = (10_000..110_000u32).filter(|i| filter.contains(i)).count();
+    assert!(false_positives < 1_300, "{} false positives in 100000 queries", false_positives);
+    assert!((filter.false_positive_rate() - 0.01).abs() < 0.002);
+    assert!((filter.estimated_len() - 10_000.0).abs() < 200.0);
+
+    assert!(filter.insert("new key"));
+    assert!(!filter.insert("new key"));
+    assert!(filter.contains(&String::from("new key")));
+    filter.clear();
+    assert_eq!(filter.count_ones(), 0);
+    assert!(!filter.contains("new key"));
+}
+
+#[test]
+pub fn bloom_filter_set_operations_test() {
+    let mut evens = BloomFilter::new(1000, 0.01);
+    let mut small = BloomFilter::new(1000, 0.01);
+    for i in 0..1000u32 {
+        evens.insert(&(2 * i));
+        small.insert(&i);
+    }
+    let union = evens.union(&small).unwrap();
+    assert!((0..1000u32).all(|i| union.contains(&(2 * i)) && union.contains(&i)));
+    let intersection = evens.intersection(&small).unwrap();
+    assert!((0..500u32).all(|i| intersection.contains(&(2 * i))));
+    assert!(intersection.count_ones() <= evens.count_ones().min(small.count_ones()));
+    assert_eq!(evens.union(&BloomFilter::new(10, 0.01)), Err(BloomError::IncompatibleFilters));
+    assert_eq!(evens.intersection(&BloomFilter::with_parameters(evens.bit_count(), 3)), Err(BloomError::IncompatibleFilters));
+}
+
+#[test]
+pub fn bloom_filter_serialization_test() {
+    let mut filter = BloomFilter::new(100, 0.05);
+    for word in ["alpha", "beta", "gamma"] {
+        filter.insert(word);
+    }
+    let bytes = filter.to_bytes();
+    assert_eq!(&bytes[..4], b"BLM1");
+    let loaded = BloomFilter::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, filter);
+    assert!(loaded.contains("beta"));
+    assert!(matches!(BloomFilter::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::InvalidData(_))));
+    assert!(matches!(BloomFilter::from_bytes(b"BLM1"), Err(BloomError::InvalidData(_))));
+
+    let mut counting = CountingBloomFilter::new(100, 0.05);
+    counting.insert("alpha");
+    let bytes = counting.to_bytes();
+    assert_eq!(CountingBloomFilter::from_bytes(&bytes).unwrap(), counting);
+    assert!(matches!(BloomFilter::from_bytes(&bytes), Err(BloomError::InvalidData(_))));
+    assert!(matches!(CountingBloomFilter::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::InvalidData(_))));
+}
+
+#[test]
+pub fn counting_bloom_filter_test() {
+    let mut filter = CountingBloomFilter::new(1000, 0.01);
+    for i in 0..1000u32 {
+        filter.insert(&i);
+    }
+    for i in (0..1000u32).step_by(2) {
+        assert!(filter.remove(&i));
+    }
+    assert!((1..1000u32).step_by(2).all(|i| filter.contains(&i)));
+    let remaining = (0..1000u32).step_by(2).filter(|i| filter.contains(i)).count();
+    assert!(remaining < 20, "{} removed keys are still contained", remaining);
+
+    // a key inserted twice has to be removed twice
+    filter.insert("twice");
+    filter.insert("twice");
+    assert!(filter.remove("twice"));
+    assert!(filter.contains("twice"));
+    assert!(filter.remove("twice"));
+    assert!(!filter.contains("twice"));
+    assert!(!filter.remove("never inserted"));
+
+    let plain = filter.to_bloom_filter();
+    assert!((1..1000u32).step_by(2).all(|i| plain.contains(&i)));
+
+    let mut other = CountingBloomFilter::new(1000, 0.01);
+    other.insert("other");
+    let mut union = filter.union(&other).unwrap();
+    assert!(union.contains("other") && union.contains(&1u32));
+    assert!(union.remove("other"));
+    assert!(!filter.intersection(&other).unwrap().contains("other"));
+    assert_eq!(filter.union(&CountingBloomFilter::new(10, 0.01)), Err(BloomError::IncompatibleFilters));
+}
@@ -0,0 +1,589 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+
+use crate::hash::hashmap::HashMap;
+use crate::hash::mod_hash::ModHashBuilder;
+
+/// marks the missing neighbour at either end of a list
+const NIL: usize = usize::MAX;
+
+/// Counts how lookups and insertions of a cache went
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// returns the share of lookups that found their key, 0 if there were none
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+}
+
+/// called with the key and value of every entry a cache evicts to make room
+pub type EvictionCallback<K, V> = Box<dyn FnMut(K, V)>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// the number of times the entry was used, only kept by [LfuCache]
+    frequency: u64,
+    prev: usize,
+    next: usize,
+}
+
+/// the ends of a doubly linked list of nodes, the head is the most recently used one
+#[derive(Debug, Clone, Copy)]
+struct List {
+    head: usize,
+    tail: usize,
+}
+
+impl List {
+    const EMPTY: List = List { head: NIL, tail: NIL };
+
+    fn is_empty(&self) -> bool {
+        self.head == NIL
+    }
+}
+
+/// The nodes of a cache, linked into lists by index so that moving a node between lists takes constant time
+struct Nodes<K, V> {
+    slots: Vec<Option<Node<K, V>>>,
+    /// slots of removed nodes that new nodes can take
+    free: Vec<usize>,
+}
+
+impl<K, V> Nodes<K, V> {
+    fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<K, V> {
+        self.slots[index].as_ref().expect("index to refer to a live node")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<K, V> {
+        self.slots[index].as_mut().expect("index to refer to a live node")
+    }
+
+    /// stores a node that is not yet part of any list
+    fn allocate(&mut self, key: K, value: V) -> usize {
+        let node = Node { key, value, frequency: 1, prev: NIL, next: NIL };
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Some(node));
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /// takes out a node that was unlinked from its list
+    fn release(&mut self, index: usize) -> Node<K, V> {
+        self.free.push(index);
+        self.slots[index].take().expect("index to refer to a live node")
+    }
+
+    fn push_front(&mut self, list: &mut List, index: usize) {
+        let old_head = list.head;
+        let node = self.node_mut(index);
+        node.prev = NIL;
+        node.next = old_head;
+        match old_head {
+            NIL => list.tail = index,
+            _ => self.node_mut(old_head).prev = index,
+        }
+        list.head = index;
+    }
+
+    fn unlink(&mut self, list: &mut List, index: usize) {
+        let (prev, next) = {
+            let node = self.node(index);
+            (node.prev, node.next)
+        };
+        match prev {
+            NIL => list.head = next,
+            _ => self.node_mut(prev).next = next,
+        }
+        match next {
+            NIL => list.tail = prev,
+            _ => self.node_mut(next).prev = prev,
+        }
+    }
+
+    /// returns the nodes of the list from head to tail
+    fn iter(&self, list: List) -> impl Iterator<Item = &Node<K, V>> {
+        std::iter::successors((list.head != NIL).then(|| self.node(list.head)), |node| {
+            (node.next != NIL).then(|| self.node(node.next))
+        })
+    }
+}
+
+/// A cache of bounded size that evicts the least recently used entry to make room for a new one
+///
+/// A hash map finds the node of a key and the nodes form a list ordered by their last use,
+/// so lookups, insertions and evictions all take constant time.
+pub struct LruCache<K, V, S = ModHashBuilder> {
+    map: HashMap<K, usize, S>,
+    nodes: Nodes<K, V>,
+    order: List,
+    capacity: usize,
+    stats: CacheStats,
+    on_evict: Option<EvictionCallback<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher> LruCache<K, V, S> {
+    pub fn with_hasher(capacity: usize, hash_builder: S) -> Self {
+        assert!(capacity > 0, "a cache must hold at least one entry");
+        Self {
+            map: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+            nodes: Nodes::new(),
+            order: List::EMPTY,
+            capacity,
+            stats: CacheStats::default(),
+            on_evict: None,
+        }
+    }
+
+    /// calls callback with every entry that is evicted from now on, but not with removed or replaced ones
+    pub fn set_eviction_callback<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// returns the value of the key and marks it as most recently used, counting a hit or a miss
+    pub fn get<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.touch(key).map(|index| &self.nodes.node(index).value)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        self.touch(key).map(|index| &mut self.nodes.node_mut(index).value)
+    }
+
+    /// returns the value of the key without marking it as used or counting the lookup
+    pub fn peek<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key).map(|&index| &self.nodes.node(index).value)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// moves the node of the key to the front and returns it
+    fn touch<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        let Some(&index) = self.map.get(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        self.nodes.unlink(&mut self.order, index);
+        self.nodes.push_front(&mut self.order, index);
+        Some(index)
+    }
+
+    /// inserts the entry as the most recently used one, returning the value it replaced
+    /// if the cache is full, the least recently used entry is evicted first
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&index) = self.map.get(&key) {
+            self.nodes.unlink(&mut self.order, index);
+            self.nodes.push_front(&mut self.order, index);
+            return Some(std::mem::replace(&mut self.nodes.node_mut(index).value, value));
+        }
+        if self.map.len() == self.capacity {
+            let tail = self.order.tail;
+            self.nodes.unlink(&mut self.order, tail);
+            let evicted = self.nodes.release(tail);
+            self.map.remove(&evicted.key);
+            self.stats.evictions += 1;
+            if let Some(callback) = &mut self.on_evict {
+                callback(evicted.key, evicted.value);
+            }
+        }
+        let index = self.nodes.allocate(key.clone(), value);
+        self.nodes.push_front(&mut self.order, index);
+        self.map.put(key, index);
+        None
+    }
+
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.map.remove(key)?;
+        self.nodes.unlink(&mut self.order, index);
+        Some(self.nodes.release(index).value)
+    }
+
+    /// returns the entries from the most to the least recently used
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.nodes.iter(self.order).map(|node| (&node.key, &node.value))
+    }
+}
+
+/// A cache of bounded size that evicts the least frequently used entry to make room for a new one
+///
+/// Entries with the same number of uses form a list ordered by their last use, and the cache keeps
+/// one such list per frequency together with the lowest frequency in use. A use moves an entry to the
+/// list of the next frequency, and an eviction takes the least recently used entry of the lowest one,
+/// so all operations take constant time.
+pub struct LfuCache<K, V, S = ModHashBuilder> {
+    map: HashMap<K, usize, S>,
+    nodes: Nodes<K, V>,
+    frequencies: HashMap<u64, List>,
+    min_frequency: u64,
+    capacity: usize,
+    stats: CacheStats,
+    on_evict: Option<EvictionCallback<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher> LfuCache<K, V, S> {
+    pub fn with_hasher(capacity: usize, hash_builder: S) -> Self {
+        assert!(capacity > 0, "a cache must hold at least one entry");
+        Self {
+            map: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+            nodes: Nodes::new(),
+            frequencies: HashMap::new(),
+            min_frequency: 0,
+            capacity,
+            stats: CacheStats::default(),
+            on_evict: None,
+        }
+    }
+
+    /// calls callback with every entry that is evicted from now on, but not with removed or replaced ones
+    pub fn set_eviction_callback<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// returns how often the key was used, counting its insertion
+    pub fn frequency<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key).map(|&index| self.nodes.node(index).frequency)
+    }
+
+    /// returns the value of the key and counts a use of it, as well as a hit or a miss
+    pub fn get<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.touch(key).map(|index| &self.nodes.node(index).value)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        self.touch(key).map(|index| &mut self.nodes.node_mut(index).value)
+    }
+
+    /// returns the value of the key without counting a use or the lookup
+    pub fn peek<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key).map(|&index| &self.nodes.node(index).value)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    fn touch<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        let Some(&index) = self.map.get(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        self.promote(index);
+        Some(index)
+    }
+
+    /// takes the node out of the list of its frequency, dropping the list if it becomes empty
+    fn unlink(&mut self, index: usize) {
+        let frequency = self.nodes.node(index).frequency;
+        let list = self.frequencies.get_mut(&frequency).expect("every frequency in use to have a list");
+        self.nodes.unlink(list, index);
+        if list.is_empty() {
+            self.frequencies.remove(&frequency);
+        }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        let frequency = self.nodes.node(index).frequency;
+        if !self.frequencies.contains_key(&frequency) {
+            self.frequencies.put(frequency, List::EMPTY);
+        }
+        let list = self.frequencies.get_mut(&frequency).expect("the list to exist");
+        self.nodes.push_front(list, index);
+    }
+
+    /// moves the node to the list of the next frequency
+    fn promote(&mut self, index: usize) {
+        let frequency = self.nodes.node(index).frequency;
+        self.unlink(index);
+        if frequency == self.min_frequency && !self.frequencies.contains_key(&frequency) {
+            self.min_frequency += 1;
+        }
+        self.nodes.node_mut(index).frequency += 1;
+        self.push_front(index);
+    }
+
+    /// inserts the entry with a frequency of 1, or replaces the value of the key and counts a use of it
+    /// if the cache is full, the least recently used of the least frequently used entries is evicted first
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&index) = self.map.get(&key) {
+            self.promote(index);
+            return Some(std::mem::replace(&mut self.nodes.node_mut(index).value, value));
+        }
+        if self.map.len() == self.capacity {
+            let tail = self.frequencies.get(&self.min_frequency).expect("a full cache to have a lowest frequency").tail;
+            self.unlink(tail);
+            let evicted = self.nodes.release(tail);
+            self.map.remove(&evicted.key);
+            self.stats.evictions += 1;
+            if let Some(callback) = &mut self.on_evict {
+                callback(evicted.key, evicted.value);
+            }
+        }
+        let index = self.nodes.allocate(key.clone(), value);
+        self.push_front(index);
+        self.map.put(key, index);
+        self.min_frequency = 1;
+        None
+    }
+
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.map.remove(key)?;
+        let frequency = self.nodes.node(index).frequency;
+        self.unlink(index);
+        if frequency == self.min_frequency && !self.frequencies.contains_key(&frequency) {
+            // the next lowest frequency is unknown, but only needed once the cache is full again,
+            // which it cannot be before a new entry resets it to 1
+            self.min_frequency = 1;
+        }
+        Some(self.nodes.release(index).value)
+    }
+}
+
+#[test]
+pub fn lru_cache_test() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let evicted = Rc::new(RefCell::new(Vec::new()));
+    let mut cache = LruCache::new(3);
+    let log = Rc::clone(&evicted);
+    cache.set_eviction_callback(move |key, value| log.borrow_mut().push((key, value)));
+
+    for (key, value) in [("a", 1), ("b", 2), ("c", 3)] {
+        assert_eq!(cache.put(key, value), None);
+    }
+    assert_eq!(cache.get("a"), Some(&1));
+    assert_eq!(cache.put("d", 4), None);
+    // b was the least recently used entry after a was read
+    assert_eq!(*RefCell::borrow(&evicted), vec![("b", 2)]);
+    assert_eq!(cache.get("b"), None);
+    assert_eq!(cache.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec!["d", "a", "c"]);
+
+    // peeking does not protect an entry from eviction
+    assert_eq!(cache.peek("c"), Some(&3));
+    assert_eq!(cache.put("a", 10), Some(1));
+    cache.put("e", 5);
+    assert_eq!(*RefCell::borrow(&evicted), vec![("b", 2), ("c", 3)]);
+    *cache.get_mut("d").unwrap() += 1;
+    assert_eq!(cache.remove("d"), Some(5));
+    assert_eq!(cache.remove("d"), None);
+    assert_eq!(cache.len(), 2);
+    cache.put("f", 6);
+    assert_eq!(RefCell::borrow(&evicted).len(), 2);
+
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, evictions: 2 });
+    assert!((cache.stats().hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+pub fn lfu_cache_test() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let evicted = Rc::new(RefCell::new(Vec::new()));
+    let mut cache = LfuCache::new(3);
+    let log = Rc::clone(&evicted);
+    cache.set_eviction_callback(move |key, _| log.borrow_mut().push(key));
+
+    cache.put("a", 1);
+    cache.put("b", 2);
+    cache.put("c", 3);
+    cache.get("a");
+    cache.get("a");
+    cache.get("b");
+    assert_eq!(cache.frequency("a"), Some(3));
+    cache.put("d", 4);
+    // c was used least often
+    assert_eq!(*RefCell::borrow(&evicted), vec!["c"]);
+    cache.put("e", 5);
+    // d and e were both used once, d longer ago
+    assert_eq!(*RefCell::borrow(&evicted), vec!["c", "d"]);
+    assert_eq!(cache.put("b", 20), Some(2));
+    assert_eq!(cache.frequency("b"), Some(3));
+    cache.get("e");
+    cache.put("f", 6);
+    assert_eq!(*RefCell::borrow(&evicted), vec!["c", "d", "e"]);
+
+    assert_eq!(cache.remove("f"), Some(6));
+    assert_eq!(cache.len(), 2);
+    cache.put("g", 7);
+    cache.put("h", 8);
+    assert_eq!(*RefCell::borrow(&evicted), vec!["c", "d", "e", "g"]);
+    assert_eq!(cache.peek("a"), Some(&1));
+    assert_eq!(cache.get("c"), None);
+    assert_eq!(cache.stats().evictions, 4);
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+pub fn cache_random_test() {
+    // compares both caches with simple reference implementations that scan all entries
+    let mut lru = LruCache::new(16);
+    let mut lfu = LfuCache::new(16);
+    // (key, value, last use, frequency)
+    let mut lru_model: Vec<(u32, u32, u64)> = Vec::new();
+    let mut lfu_model: Vec<(u32, u32, u64, u64)> = Vec::new();
+    let mut random: u64 = 0x9E37_79B9_7F4A_7C15;
+    for time in 0..20_000u64 {
+        random ^= random << 13;
+        random ^= random >> 7;
+        random ^= random << 17;
+        let key = (random % 40) as u32;
+        let value = (random >> 32) as u32;
+        match random % 3 {
+            0 => {
+                let expected = lru_model.iter_mut().find(|entry| entry.0 == key).map(|entry| {
+                    entry.2 = time;
+                    entry.1
+                });
+                assert_eq!(lru.get(&key).copied(), expected);
+                let expected = lfu_model.iter_mut().find(|entry| entry.0 == key).map(|entry| {
+                    entry.2 = time;
+                    entry.3 += 1;
+                    entry.1
+                });
+                assert_eq!(lfu.get(&key).copied(), expected);
+            }
+            1 => {
+                lru.put(key, value);
+                match lru_model.iter_mut().find(|entry| entry.0 == key) {
+                    Some(entry) => *entry = (key, value, time),
+                    None => {
+                        if lru_model.len() == 16 {
+                            let oldest = (0..16).min_by_key(|&i| lru_model[i].2).unwrap();
+                            lru_model.swap_remove(oldest);
+                        }
+                        lru_model.push((key, value, time));
+                    }
+                }
+                lfu.put(key, value);
+                match lfu_model.iter_mut().find(|entry| entry.0 == key) {
+                    Some(entry) => *entry = (key, value, time, entry.3 + 1),
+                    None => {
+                        if lfu_model.len() == 16 {
+                            let victim = (0..16).min_by_key(|&i| (lfu_model[i].3, lfu_model[i].2)).unwrap();
+                            lfu_model.swap_remove(victim);
+                        }
+                        lfu_model.push((key, value, time, 1));
+                    }
+                }
+            }
+            _ => {
+                let expected = lru_model.iter().position(|entry| entry.0 == key).map(|i| lru_model.swap_remove(i).1);
+                assert_eq!(lru.remove(&key), expected);
+                let expected = lfu_model.iter().position(|entry| entry.0 == key).map(|i| lfu_model.swap_remove(i).1);
+                assert_eq!(lfu.remove(&key), expected);
+            }
+        }
+        assert_eq!(lru.len(), lru_model.len());
+        assert_eq!(lfu.len(), lfu_model.len());
+    }
+}
@@ -0,0 +1,387 @@
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// the number of hash tables, a key is looked up in the table chosen by the low byte of its hash
+const TABLE_COUNT: usize = 256;
+/// the header holds the position and the number of slots of every table as two u32
+const HEADER_LENGTH: usize = TABLE_COUNT * 8;
+
+/// numbers the temporary files of this process, so writers for the same path never share one
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+pub enum CdbError {
+    Io(io::Error),
+    /// the database would not fit into the 4 GiB addressable with u32 positions
+    TooLarge,
+    /// the file is not a database or got truncated
+    InvalidData(String),
+}
+
+impl Display for CdbError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CdbError::Io(error) => write!(f, "{}", error),
+            CdbError::TooLarge => write!(f, "the database exceeds 4 GiB"),
+            CdbError::InvalidData(message) => write!(f, "invalid database: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for CdbError {
+    fn from(error: io::Error) -> Self {
+        CdbError::Io(error)
+    }
+}
+
+/// the hash of the cdb format, djb2 with xor: h = (h * 33) ^ byte starting from 5381
+pub fn cdb_hash(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(5381u32, |hash, &byte| (hash << 5).wrapping_add(hash) ^ byte as u32)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().expect("4 bytes"))
+}
+
+/// Writes a constant database to a temporary file next to its final path, which only replaces the
+/// final path in [CdbWriter::finish]
+///
+/// A rename within a directory is atomic, so readers and a crash at any point see either the complete
+/// old database or the complete new one. Dropping the writer before finishing removes the temporary file.
+/// Every writer gets a temporary file of its own, so concurrent rebuilds of one path do not interfere and
+/// the last one to finish wins.
+pub struct CdbWriter {
+    path: PathBuf,
+    temp_path: PathBuf,
+    file: BufWriter<File>,
+    /// the position the next record is written at
+    position: u64,
+    /// the hash and position of every record
+    records: Vec<(u32, u32)>,
+    finished: bool,
+}
+
+impl CdbWriter {
+    /// starts writing a database that will be stored at path
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, CdbError> {
+        let path = path.as_ref().to_path_buf();
+        // create_new fails instead of truncating a file another writer still uses, like one left behind
+        // by an earlier process with the same id, in which case the next number is tried
+        let (temp_path, file) = loop {
+            let mut temp_name = path.file_name().map(OsString::from).unwrap_or_default();
+            temp_name.push(format!(".{}.{}.tmp", std::process::id(), TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)));
+            let temp_path = path.with_file_name(temp_name);
+            match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+                Ok(file) => break (temp_path, file),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error.into()),
+            }
+        };
+        let mut file = BufWriter::new(file);
+        // the header is filled in once the tables are written
+        file.write_all(&[0; HEADER_LENGTH])?;
+        Ok(Self {
+            path,
+            temp_path,
+            file,
+            position: HEADER_LENGTH as u64,
+            records: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// returns the position after length more bytes, or an error if it cannot be stored in a u32
+    fn advance(&self, length: usize) -> Result<u64, CdbError> {
+        let end = self.position + length as u64;
+        if end > u32::MAX as u64 {
+            return Err(CdbError::TooLarge);
+        }
+        Ok(end)
+    }
+
+    /// appends a record, a key can be added several times and keeps all of its values
+    pub fn add(&mut self, key: &[u8], value: &[u8]) -> Result<(), CdbError> {
+        let end = self.advance(8 + key.len() + value.len())?;
+        self.file.write_all(&(key.len() as u32).to_le_bytes())?;
+        self.file.write_all(&(value.len() as u32).to_le_bytes())?;
+        self.file.write_all(key)?;
+        self.file.write_all(value)?;
+        self.records.push((cdb_hash(key), self.position as u32));
+        self.position = end;
+        Ok(())
+    }
+
+    /// writes the hash tables and the header, then moves the database to its final path
+    pub fn finish(mut self) -> Result<(), CdbError> {
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        for table in 0..TABLE_COUNT {
+            let records: Vec<(u32, u32)> =
+                self.records.iter().copied().filter(|(hash, _)| hash & 0xff == table as u32).collect();
+            // twice as many slots as records keeps the probe sequences short, an empty slot has position 0
+            let slot_count = records.len() * 2;
+            let mut slots = vec![(0u32, 0u32); slot_count];
+            for (hash, position) in records {
+                let mut slot = (hash >> 8) as usize % slot_count;
+                while slots[slot].1 != 0 {
+                    slot = (slot + 1) % slot_count;
+                }
+                slots[slot] = (hash, position);
+            }
+            let end = self.advance(slot_count * 8)?;
+            header.extend_from_slice(&(self.position as u32).to_le_bytes());
+            header.extend_from_slice(&(slot_count as u32).to_le_bytes());
+            for (hash, position) in slots {
+                self.file.write_all(&hash.to_le_bytes())?;
+                self.file.write_all(&position.to_le_bytes())?;
+            }
+            self.position = end;
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.flush()?;
+        // the data has to be on disk before the rename makes it visible
+        self.file.get_ref().sync_all()?;
+        fs::rename(&self.temp_path, &self.path)?;
+        self.finished = true;
+        sync_directory(&self.path)?;
+        Ok(())
+    }
+}
+
+/// flushes the directory entry of path to disk, without it a crash after a rename can bring back the old entry
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> io::Result<()> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+/// directories cannot be opened as files on other platforms, so persisting the rename is left to the file system
+#[cfg(not(unix))]
+fn sync_directory(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl Drop for CdbWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// A read only hash table stored in a file in the cdb format, lookups read only the slots and records
+/// they need so the table never has to fit into memory
+///
+/// The file starts with a header of 256 (position, slot count) pairs, followed by the records as key length,
+/// value length, key and value, followed by the 256 open addressing hash tables of (hash, record position)
+/// slots. All numbers are little endian u32, as in the original cdb by D. J. Bernstein.
+pub struct Cdb {
+    file: Mutex<File>,
+    file_length: u64,
+    /// the position and number of slots of every table
+    tables: Vec<(u32, u32)>,
+}
+
+impl Cdb {
+    /// writes the pairs to a new database at path, replacing any database there atomically
+    pub fn build<P: AsRef<Path>, K: AsRef<[u8]>, V: AsRef<[u8]>, I: IntoIterator<Item = (K, V)>>(
+        path: P,
+        pairs: I,
+    ) -> Result<(), CdbError> {
+        let mut writer = CdbWriter::create(path)?;
+        for (key, value) in pairs {
+            writer.add(key.as_ref(), value.as_ref())?;
+        }
+        writer.finish()
+    }
+
+    /// opens the database at path, reading only its header
+    ///
+    /// The opened file stays readable after a rebuild replaced it, lookups keep seeing the old database
+    /// until it is opened again.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CdbError> {
+        let mut file = File::open(path)?;
+        let file_length = file.metadata()?.len();
+        let mut header = [0; HEADER_LENGTH];
+        file.read_exact(&mut header)
+            .map_err(|_| CdbError::InvalidData("the file is shorter than the header".to_string()))?;
+        let tables: Vec<(u32, u32)> =
+            header.chunks_exact(8).map(|pair| (read_u32(&pair[..4]), read_u32(&pair[4..]))).collect();
+        for &(position, slot_count) in &tables {
+            if position as u64 + slot_count as u64 * 8 > file_length {
+                return Err(CdbError::InvalidData("a hash table reaches past the end of the file".to_string()));
+            }
+        }
+        Ok(Self {
+            file: Mutex::new(file),
+            file_length,
+            tables,
+        })
+    }
+
+    /// returns the number of records
+    pub fn len(&self) -> usize {
+        self.tables.iter().map(|&(_, slot_count)| slot_count as usize / 2).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read_at(&self, position: u64, buffer: &mut [u8]) -> Result<(), CdbError> {
+        if position + buffer.len() as u64 > self.file_length {
+            return Err(CdbError::InvalidData(format!("read past the end of the file at {}", position)));
+        }
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(buffer)?;
+        Ok(())
+    }
+
+    /// returns the values of the key in the order they were added, stopping after limit values
+    fn find(&self, key: &[u8], limit: usize) -> Result<Vec<Vec<u8>>, CdbError> {
+        let hash = cdb_hash(key);
+        let (table_position, slot_count) = self.tables[(hash & 0xff) as usize];
+        let mut values = Vec::new();
+        if slot_count == 0 {
+            return Ok(values);
+        }
+        let start = (hash >> 8) % slot_count;
+        let mut slot = [0; 8];
+        for probe in 0..slot_count {
+            let index = (start + probe) % slot_count;
+            self.read_at(table_position as u64 + index as u64 * 8, &mut slot)?;
+            let record_position = read_u32(&slot[4..]) as u64;
+            if record_position == 0 || values.len() == limit {
+                break;
+            }
+            if read_u32(&slot) != hash {
+                continue;
+            }
+            let mut lengths = [0; 8];
+            self.read_at(record_position, &mut lengths)?;
+            let key_length = read_u32(&lengths) as usize;
+            if key_length != key.len() {
+                continue;
+            }
+            // corrupt lengths are checked against the file before they decide the size of an allocation
+            let record_length = key_length as u64 + read_u32(&lengths[4..]) as u64;
+            if record_position + 8 + record_length > self.file_length {
+                return Err(CdbError::InvalidData(format!("the record at {} reaches past the end of the file", record_position)));
+            }
+            let mut record = vec![0; record_length as usize];
+            self.read_at(record_position + 8, &mut record)?;
+            if &record[..key_length] == key {
+                values.push(record.split_off(key_length));
+            }
+        }
+        Ok(values)
+    }
+
+    /// returns the first value added for the key
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, CdbError> {
+        Ok(self.find(key, 1)?.pop())
+    }
+
+    /// returns all values added for the key in the order they were added
+    pub fn get_all(&self, key: &[u8]) -> Result<Vec<Vec<u8>>, CdbError> {
+        self.find(key, usize::MAX)
+    }
+}
+
+/// creates an empty directory for the files of one test
+#[cfg(test)]
+fn test_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("ad-cdb-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+pub fn cdb_test() {
+    let directory = test_directory("lookup");
+    let path = directory.join("table.cdb");
+    let pairs: Vec<(String, String)> = (0..10_000).map(|i| (format!("key-{}", i), format!("value-{}", i * i))).collect();
+    Cdb::build(&path, pairs.iter().map(|(key, value)| (key, value))).unwrap();
+
+    let cdb = Cdb::open(&path).unwrap();
+    assert_eq!(cdb.len(), 10_000);
+    for (key, value) in pairs.iter().step_by(7) {
+        assert_eq!(cdb.get(key.as_bytes()).unwrap(), Some(value.as_bytes().to_vec()));
+    }
+    assert_eq!(cdb.get(b"key-10000").unwrap(), None);
+    assert_eq!(cdb.get(b"").unwrap(), None);
+    assert_eq!(cdb_hash(b""), 5381);
+
+    let mut writer = CdbWriter::create(directory.join("duplicates.cdb")).unwrap();
+    writer.add(b"fruit", b"apple").unwrap();
+    writer.add(b"", b"empty key").unwrap();
+    writer.add(b"fruit", b"").unwrap();
+    writer.add(b"fruit", b"pear").unwrap();
+    writer.finish().unwrap();
+    let cdb = Cdb::open(directory.join("duplicates.cdb")).unwrap();
+    assert_eq!(cdb.get(b"fruit").unwrap(), Some(b"apple".to_vec()));
+    assert_eq!(cdb.get_all(b"fruit").unwrap(), vec![b"apple".to_vec(), Vec::new(), b"pear".to_vec()]);
+    assert_eq!(cdb.get(b"").unwrap(), Some(b"empty key".to_vec()));
+
+    Cdb::build(directory.join("empty.cdb"), Vec::<(&[u8], &[u8])>::new()).unwrap();
+    let cdb = Cdb::open(directory.join("empty.cdb")).unwrap();
+    assert!(cdb.is_empty());
+    assert_eq!(cdb.get(b"fruit").unwrap(), None);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+pub fn cdb_rebuild_test() {
+    let directory = test_directory("rebuild");
+    let path = directory.join("table.cdb");
+    Cdb::build(&path, [("version", "1")]).unwrap();
+    let old = Cdb::open(&path).unwrap();
+
+    // an abandoned rebuild leaves the database and the directory as they were
+    let mut writer = CdbWriter::create(&path).unwrap();
+    writer.add(b"version", b"2").unwrap();
+    drop(writer);
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    assert_eq!(Cdb::open(&path).unwrap().get(b"version").unwrap(), Some(b"1".to_vec()));
+
+    // concurrent writers of one path each write their own file, the one finishing last replaces the other
+    let mut first = CdbWriter::create(&path).unwrap();
+    let mut second = CdbWriter::create(&path).unwrap();
+    first.add(b"version", b"first").unwrap();
+    second.add(b"version", b"second").unwrap();
+    second.finish().unwrap();
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 2);
+    first.finish().unwrap();
+    assert_eq!(Cdb::open(&path).unwrap().get(b"version").unwrap(), Some(b"first".to_vec()));
+
+    Cdb::build(&path, [("version", "2")]).unwrap();
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    assert_eq!(old.get(b"version").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(Cdb::open(&path).unwrap().get(b"version").unwrap(), Some(b"2".to_vec()));
+
+    fs::write(directory.join("short.cdb"), [0; 100]).unwrap();
+    assert!(matches!(Cdb::open(directory.join("short.cdb")), Err(CdbError::InvalidData(_))));
+    let mut truncated = fs::read(&path).unwrap();
+    truncated.truncate(truncated.len() - 8);
+    fs::write(directory.join("truncated.cdb"), truncated).unwrap();
+    assert!(matches!(Cdb::open(directory.join("truncated.cdb")), Err(CdbError::InvalidData(_))));
+    assert!(matches!(Cdb::open(directory.join("missing.cdb")), Err(CdbError::Io(_))));
+
+    // a value length near 4 GiB is reported instead of allocated
+    let mut corrupt = fs::read(&path).unwrap();
+    corrupt[HEADER_LENGTH + 4..HEADER_LENGTH + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(directory.join("corrupt.cdb"), corrupt).unwrap();
+    let cdb = Cdb::open(directory.join("corrupt.cdb")).unwrap();
+    assert!(matches!(cdb.get(b"version"), Err(CdbError::InvalidData(_))));
+    fs::remove_dir_all(&directory).unwrap();
+}
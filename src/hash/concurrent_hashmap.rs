@@ -0,0 +1,225 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::hash::mod_hash::{mix, ModHashBuilder};
+use crate::hash::static_hashmap::StaticHashMap;
+
+/// A hash map that can be shared between threads, it splits the keys over a fixed number of
+/// [StaticHashMap] shards that each sit behind their own [RwLock]
+///
+/// A key is hashed once to pick its shard and again by the shard to pick its bucket. The shard index is
+/// taken from the [mix]ed hash, so the keys of one shard still spread over all of its buckets.
+/// Every operation locks exactly one shard, so threads working on keys in different shards never wait
+/// for each other and every operation on a single key takes effect atomically.
+pub struct ShardedHashMap<K, V, S = ModHashBuilder> {
+    shards: Vec<RwLock<StaticHashMap<K, V, S>>>,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> ShardedHashMap<K, V> {
+    /// creates a map with the given number of shards, each with buckets buckets
+    pub fn new(shards: usize, buckets: u32) -> Self {
+        Self::with_hasher(shards, buckets, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Clone> ShardedHashMap<K, V, S> {
+    /// creates a map with the given number of shards that all hash keys with hash_builder
+    pub fn with_hasher(shards: usize, buckets: u32, hash_builder: S) -> Self {
+        assert!(shards > 0, "a map needs at least one shard");
+        Self {
+            shards: (0..shards)
+                .map(|_| RwLock::new(StaticHashMap::with_hasher(buckets, hash_builder.clone())))
+                .collect(),
+            hash_builder,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ShardedHashMap<K, V, S> {
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// returns the index of the shard for the key
+    pub fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (mix(self.hash_builder.hash_one(key)) % self.shards.len() as u64) as usize
+    }
+
+    // a thread that panicked while holding a lock cannot have left a shard half updated, since every
+    // change is a single call on the StaticHashMap, so poisoned locks are simply taken over
+    fn read<Q: Hash + ?Sized>(&self, key: &Q) -> RwLockReadGuard<'_, StaticHashMap<K, V, S>> {
+        self.shards[self.shard(key)].read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write<Q: Hash + ?Sized>(&self, key: &Q) -> RwLockWriteGuard<'_, StaticHashMap<K, V, S>> {
+        self.shards[self.shard(key)].write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// returns a copy of the value for the key
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        V: Clone,
+    {
+        self.read(key).get(key).cloned()
+    }
+
+    /// calls f with the value for the key while holding the read lock of its shard
+    pub fn get_with<Q: Hash + Eq + ?Sized, R, F: FnOnce(Option<&V>) -> R>(&self, key: &Q, f: F) -> R
+    where
+        K: Borrow<Q>,
+    {
+        f(self.read(key).get(key))
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.read(key).contains_key(key)
+    }
+
+    /// inserts a key-value pair, returning the value previously stored for the key
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write(&key).put(key, value)
+    }
+
+    /// removes a key from the map, returning its value
+    pub fn remove<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.write(key).remove(key)
+    }
+
+    /// replaces the value for the key with the result of f, which gets the current value
+    ///
+    /// Returning None from f removes the key. The shard stays locked while f runs, so no other thread
+    /// can change the key in between and the returned new value is exactly the one that was stored.
+    pub fn compute<F: FnOnce(Option<&V>) -> Option<V>>(&self, key: K, f: F) -> Option<V>
+    where
+        V: Clone,
+    {
+        let mut shard = self.write(&key);
+        match f(shard.get(&key)) {
+            Some(value) => {
+                shard.put(key, value.clone());
+                Some(value)
+            }
+            None => {
+                shard.remove(&key);
+                None
+            }
+        }
+    }
+
+    /// returns the number of entries in all shards
+    ///
+    /// The shards are counted one after the other, so while other threads change the map the result
+    /// need not match the size at any single point in time.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap_or_else(PoisonError::into_inner).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().unwrap_or_else(PoisonError::into_inner).clear();
+        }
+    }
+}
+
+#[test]
+pub fn sharded_hashmap_test() {
+    let map = ShardedHashMap::new(8, 16);
+    assert_eq!(map.insert("a".to_string(), 1), None);
+    assert_eq!(map.insert("a".to_string(), 2), Some(1));
+    assert_eq!(map.get("a"), Some(2));
+    assert!(map.contains_key("a"));
+    assert_eq!(map.get_with("a", |value| value.map(|value| value * 10)), Some(20));
+    assert_eq!(map.compute("a".to_string(), |value| value.map(|value| value + 1)), Some(3));
+    assert_eq!(map.compute("b".to_string(), |value| Some(value.copied().unwrap_or(0) + 1)), Some(1));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.compute("a".to_string(), |_| None), None);
+    assert!(!map.contains_key("a"));
+    assert_eq!(map.remove("b"), Some(1));
+    assert_eq!(map.remove("b"), None);
+    assert!(map.is_empty());
+
+    for key in 0..1000u32 {
+        map.insert(key.to_string(), key);
+    }
+    let mut load = vec![0; map.shard_count()];
+    for key in 0..1000u32 {
+        load[map.shard(&key.to_string())] += 1;
+    }
+    assert!(load.iter().all(|&count| count > 60), "{:?}", load);
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+pub fn sharded_hashmap_threads_test() {
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    const THREADS: u32 = 8;
+    const OPERATIONS: u32 = 2_000;
+    let map = Arc::new(ShardedHashMap::new(4, 32));
+    let barrier = Arc::new(Barrier::new(THREADS as usize));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let map = Arc::clone(&map);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let mut counts = Vec::new();
+                let mut claimed = Vec::new();
+                for i in 0..OPERATIONS {
+                    // every thread increments the same few counters
+                    counts.push(map.compute(i % 4, |count| Some(count.copied().unwrap_or(0) + 1)).unwrap());
+                    // and races the other threads to claim and then remove shared keys
+                    let key = 1_000 + i;
+                    if map.compute(key, |owner| Some(owner.copied().unwrap_or(thread))) == Some(thread) {
+                        claimed.push(key);
+                    }
+                    map.insert(100_000 + thread * OPERATIONS + i, i);
+                }
+                // nobody removes a shared key before every thread tried to claim it
+                barrier.wait();
+                let removed: Vec<u32> = (1_000..1_000 + OPERATIONS).filter(|key| map.remove(key).is_some()).collect();
+                (counts, claimed, removed)
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+
+    // in a linearisable map the increments of a counter happen one after the other, so every count
+    // from 1 to the number of increments was returned to exactly one thread
+    for counter in 0..4 {
+        let mut returned: Vec<u32> =
+            results.iter().flat_map(|(counts, _, _)| counts.iter().skip(counter).step_by(4).copied()).collect();
+        returned.sort_unstable();
+        assert_eq!(returned, (1..=THREADS * OPERATIONS / 4).collect::<Vec<_>>());
+        assert_eq!(map.get(&(counter as u32)), Some(THREADS * OPERATIONS / 4));
+    }
+    // every shared key was claimed by exactly one thread and removed by exactly one thread
+    let mut claimed: Vec<u32> = results.iter().flat_map(|(_, claimed, _)| claimed.iter().copied()).collect();
+    let mut removed: Vec<u32> = results.iter().flat_map(|(_, _, removed)| removed.iter().copied()).collect();
+    claimed.sort_unstable();
+    removed.sort_unstable();
+    assert_eq!(claimed, (1_000..1_000 + OPERATIONS).collect::<Vec<_>>());
+    assert_eq!(removed, claimed);
+
+    assert_eq!(map.len(), 4 + (THREADS * OPERATIONS) as usize);
+    for thread in 0..THREADS {
+        for i in (0..OPERATIONS).step_by(97) {
+            assert_eq!(map.get(&(100_000 + thread * OPERATIONS + i)), Some(i));
+        }
+    }
+}
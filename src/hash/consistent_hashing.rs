@@ -0,0 +1,178 @@
+use std::hash::BuildHasher;
+
+use crate::hash::sharding::{hash_parts, Sharding};
+use crate::hash::to_bytes::ToBytes;
+use crate::hash::xxhash::XxHashBuilder;
+
+/// Maps keys to nodes by placing both on a ring of hash values, a key belongs to the first node after it
+///
+/// Every node is placed at several points of the ring, its virtual nodes, which evens out the share of
+/// the ring each node covers. Adding a node only moves the keys in front of its points to it, and removing
+/// one only moves its own keys, so about 1 / n of the keys move when the n-th node joins.
+#[derive(Debug, Clone)]
+pub struct HashRing<N, S = XxHashBuilder> {
+    /// the points of all virtual nodes sorted by hash, each with the index of its node
+    points: Vec<(u64, usize)>,
+    nodes: Vec<N>,
+    virtual_nodes: u32,
+    hash_builder: S,
+}
+
+impl<N: ToBytes + PartialEq> HashRing<N> {
+    /// creates an empty ring that places every node at virtual_nodes points
+    pub fn new(virtual_nodes: u32) -> Self {
+        Self::with_hasher(virtual_nodes, XxHashBuilder::default())
+    }
+}
+
+impl<N: ToBytes + PartialEq, S: BuildHasher> HashRing<N, S> {
+    pub fn with_hasher(virtual_nodes: u32, hash_builder: S) -> Self {
+        assert!(virtual_nodes > 0, "every node needs at least one point on the ring");
+        Self {
+            points: Vec::new(),
+            nodes: Vec::new(),
+            virtual_nodes,
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    fn point(&self, node: &N, virtual_node: u32) -> u64 {
+        hash_parts(&self.hash_builder, &[&node.to_bytes(), &virtual_node.to_be_bytes()])
+    }
+
+    /// adds a node to the ring, returns false if it is already on it
+    pub fn add_node(&mut self, node: N) -> bool {
+        if self.nodes.contains(&node) {
+            return false;
+        }
+        let index = self.nodes.len();
+        for virtual_node in 0..self.virtual_nodes {
+            self.points.push((self.point(&node, virtual_node), index));
+        }
+        self.points.sort_unstable();
+        self.nodes.push(node);
+        true
+    }
+
+    /// removes a node from the ring, returns false if it is not on it
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        let Some(index) = self.nodes.iter().position(|candidate| candidate == node) else {
+            return false;
+        };
+        self.nodes.swap_remove(index);
+        let moved = self.nodes.len();
+        self.points.retain(|&(_, node)| node != index);
+        // the last node took the place of the removed one
+        for point in self.points.iter_mut().filter(|(_, node)| *node == moved) {
+            point.1 = index;
+        }
+        true
+    }
+
+    /// returns the position in points of the first point at or after the hash of the key
+    fn successor<K: ToBytes + ?Sized>(&self, key: &K) -> usize {
+        let hash = hash_parts(&self.hash_builder, &[&key.to_bytes()]);
+        self.points.partition_point(|&(point, _)| point < hash) % self.points.len()
+    }
+
+    /// returns the node responsible for the key, or None if the ring is empty
+    pub fn node_for<K: ToBytes + ?Sized>(&self, key: &K) -> Option<&N> {
+        if self.points.is_empty() {
+            return None;
+        }
+        Some(&self.nodes[self.points[self.successor(key)].1])
+    }
+
+    /// returns up to count distinct nodes in the order they follow the key on the ring, for replicating it
+    pub fn nodes_for<K: ToBytes + ?Sized>(&self, key: &K, count: usize) -> Vec<&N> {
+        if self.points.is_empty() {
+            return Vec::new();
+        }
+        let mut indices: Vec<usize> = Vec::new();
+        let start = self.successor(key);
+        for offset in 0..self.points.len() {
+            if indices.len() == count.min(self.nodes.len()) {
+                break;
+            }
+            let index = self.points[(start + offset) % self.points.len()].1;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        indices.into_iter().map(|index| &self.nodes[index]).collect()
+    }
+}
+
+impl<N: ToBytes + PartialEq, S: BuildHasher> Sharding<N> for HashRing<N, S> {
+    fn node_for<K: ToBytes + ?Sized>(&self, key: &K) -> Option<&N> {
+        HashRing::node_for(self, key)
+    }
+}
+
+#[test]
+pub fn hash_ring_test() {
+    let mut ring = HashRing::new(200);
+    assert_eq!(ring.node_for("key"), None);
+    for node in 0..10u32 {
+        assert!(ring.add_node(node));
+    }
+    assert!(!ring.add_node(3));
+    assert_eq!(ring.len(), 10);
+
+    let keys: Vec<u32> = (0..100_000).collect();
+    let mut load = [0; 10];
+    for key in &keys {
+        load[*ring.node_for(key).unwrap() as usize] += 1;
+    }
+    // with 200 virtual nodes every node gets close to a tenth of the keys
+    assert!(load.iter().all(|&count| (7_000..13_000).contains(&count)), "{:?}", load);
+
+    let replicas = ring.nodes_for(&42u32, 3);
+    assert_eq!(replicas.len(), 3);
+    assert_eq!(replicas[0], ring.node_for(&42u32).unwrap());
+    assert!(replicas[0] != replicas[1] && replicas[1] != replicas[2] && replicas[0] != replicas[2]);
+    assert_eq!(ring.nodes_for(&42u32, 20).len(), 10);
+}
+
+#[test]
+pub fn hash_ring_moved_keys_test() {
+    use crate::hash::mod_hash::ModHashBuilder;
+    use crate::hash::sharding::moved_keys;
+
+    let keys: Vec<String> = (0..20_000).map(|i| format!("key-{}", i)).collect();
+    let mut ring = HashRing::with_hasher(100, ModHashBuilder::default());
+    for node in ["a", "b", "c", "d"] {
+        ring.add_node(node.to_string());
+    }
+
+    let mut grown = ring.clone();
+    grown.add_node("e".to_string());
+    let moves = moved_keys(&ring, &grown, &keys);
+    // only keys for the new node move, about a fifth of them
+    assert!(moves.iter().all(|moved| moved.to.as_deref() == Some("e")));
+    assert!((3_000..5_000).contains(&moves.len()), "{} keys moved", moves.len());
+
+    let mut shrunk = grown.clone();
+    assert!(shrunk.remove_node(&"b".to_string()));
+    assert!(!shrunk.remove_node(&"b".to_string()));
+    let moves = moved_keys(&grown, &shrunk, &keys);
+    assert!(moves.iter().all(|moved| moved.from.as_deref() == Some("b")));
+    let on_b = keys.iter().filter(|key| grown.node_for(*key).map(String::as_str) == Some("b")).count();
+    assert_eq!(moves.len(), on_b);
+
+    // removing and adding back restores the original assignment
+    shrunk.add_node("b".to_string());
+    assert!(moved_keys(&grown, &shrunk, &keys).is_empty());
+}
@@ -0,0 +1,193 @@
+use std::fmt::{Display, Formatter};
+
+use crate::hash::murmur3::murmur3_128;
+use crate::hash::to_bytes::ToBytes;
+
+#[derive(Debug, PartialEq)]
+pub enum CountMinError {
+    /// sketches with a different width or depth cannot be merged
+    DimensionMismatch,
+}
+
+impl Display for CountMinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CountMinError::DimensionMismatch => write!(f, "the sketches differ in width or depth"),
+        }
+    }
+}
+
+/// Estimates how often each key occurs in a stream in a fixed amount of memory
+///
+/// Every row of counters has its own hash function, an occurrence increments the counter of the key
+/// in each row, and the smallest of these counters is the estimate. Other keys can only add to a counter,
+/// so estimates are never too low. With a width of e / epsilon and a depth of ln(1 / delta), an estimate
+/// exceeds the true count by more than epsilon times the total count with a chance of at most delta.
+///
+/// With conservative update, an occurrence only raises the counters of the key that are below its new
+/// estimate. This keeps the same guarantee with much smaller errors, but the sketch can no longer
+/// subtract occurrences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountMinSketch {
+    /// depth rows of width counters each, stored row after row
+    counters: Vec<u64>,
+    width: usize,
+    depth: usize,
+    total: u64,
+    conservative: bool,
+}
+
+impl CountMinSketch {
+    /// creates a sketch whose estimates exceed the true count by at most epsilon times the total count
+    /// with a probability of at least 1 - delta
+    pub fn new(epsilon: f64, delta: f64) -> Self {
+        assert!(epsilon > 0.0 && delta > 0.0 && delta < 1.0, "epsilon must be positive and delta in (0, 1)");
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        Self::with_dimensions(width, depth)
+    }
+
+    pub fn with_dimensions(width: usize, depth: usize) -> Self {
+        assert!(width > 0 && depth > 0, "a sketch needs at least one counter");
+        Self {
+            counters: vec![0; width * depth],
+            width,
+            depth,
+            total: 0,
+            conservative: false,
+        }
+    }
+
+    /// makes the sketch use conservative update for the occurrences added from now on
+    pub fn conservative(mut self) -> Self {
+        self.conservative = true;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// returns the sum of all counts added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// returns the bound epsilon * total that estimates exceed with a probability of at most delta
+    pub fn error_bound(&self) -> f64 {
+        std::f64::consts::E / self.width as f64 * self.total as f64
+    }
+
+    /// returns the counter of the key in each row
+    fn cells<K: ToBytes + ?Sized>(&self, key: &K) -> impl Iterator<Item = usize> {
+        let (h1, h2) = murmur3_128(&key.to_bytes(), 0);
+        let width = self.width;
+        (0..self.depth).map(move |row| row * width + (h1.wrapping_add((row as u64).wrapping_mul(h2)) % width as u64) as usize)
+    }
+
+    /// records count more occurrences of the key
+    pub fn add<K: ToBytes + ?Sized>(&mut self, key: &K, count: u64) {
+        self.total += count;
+        let cells: Vec<usize> = self.cells(key).collect();
+        if self.conservative {
+            let estimate = cells.iter().map(|&cell| self.counters[cell]).min().expect("at least one row") + count;
+            for cell in cells {
+                self.counters[cell] = self.counters[cell].max(estimate);
+            }
+        } else {
+            for cell in cells {
+                self.counters[cell] += count;
+            }
+        }
+    }
+
+    pub fn insert<K: ToBytes + ?Sized>(&mut self, key: &K) {
+        self.add(key, 1);
+    }
+
+    /// returns an estimate of the number of occurrences of the key, which is never below the true number
+    pub fn estimate<K: ToBytes + ?Sized>(&self, key: &K) -> u64 {
+        self.cells(key).map(|cell| self.counters[cell]).min().expect("at least one row")
+    }
+
+    /// adds the occurrences recorded by other to this sketch
+    ///
+    /// The counters are summed, so the estimates stay upper bounds even for sketches with conservative update.
+    pub fn merge(&mut self, other: &Self) -> Result<(), CountMinError> {
+        if self.width != other.width || self.depth != other.depth {
+            return Err(CountMinError::DimensionMismatch);
+        }
+        for (counter, other_counter) in self.counters.iter_mut().zip(&other.counters) {
+            *counter += other_counter;
+        }
+        self.total += other.total;
+        Ok(())
+    }
+}
+
+/// returns a stream in which key i occurs about 20000 / (i + 1) times, shuffled deterministically,
+/// and the true count of every key
+#[cfg(test)]
+pub(crate) fn zipf_stream(keys: u32) -> (Vec<u32>, Vec<u64>) {
+    let counts: Vec<u64> = (0..keys).map(|key| (20_000 / (key as u64 + 1)).max(1)).collect();
+    let mut stream: Vec<u32> = (0..keys).flat_map(|key| std::iter::repeat_n(key, counts[key as usize] as usize)).collect();
+    let mut random: u64 = 0x2545_F491_4F6C_DD1D;
+    for i in (1..stream.len()).rev() {
+        random ^= random << 13;
+        random ^= random >> 7;
+        random ^= random << 17;
+        stream.swap(i, random as usize % (i + 1));
+    }
+    (stream, counts)
+}
+
+#[test]
+pub fn count_min_test() {
+    let (stream, counts) = zipf_stream(5_000);
+    let (epsilon, delta) = (0.001, 0.01);
+    let mut standard = CountMinSketch::new(epsilon, delta);
+    let mut conservative = CountMinSketch::new(epsilon, delta).conservative();
+    assert_eq!((standard.width(), standard.depth()), (2719, 5));
+    for key in &stream {
+        standard.insert(key);
+        conservative.insert(key);
+    }
+    assert_eq!(standard.total(), stream.len() as u64);
+
+    let bound = epsilon * stream.len() as f64;
+    let mut standard_error = 0;
+    let mut conservative_error = 0;
+    let mut exceeding = 0;
+    for (key, &count) in counts.iter().enumerate() {
+        let key = key as u32;
+        let (estimate, conservative_estimate) = (standard.estimate(&key), conservative.estimate(&key));
+        assert!(estimate >= count && conservative_estimate >= count, "estimates must never be too low");
+        assert!(conservative_estimate <= estimate);
+        if (estimate - count) as f64 > bound {
+            exceeding += 1;
+        }
+        standard_error += estimate - count;
+        conservative_error += conservative_estimate - count;
+    }
+    assert!(exceeding as f64 <= delta * counts.len() as f64, "{} estimates exceed the bound", exceeding);
+    assert!(conservative_error < standard_error);
+    assert_eq!(standard.estimate(&"never seen"), 0);
+}
+
+#[test]
+pub fn count_min_merge_test() {
+    let mut first = CountMinSketch::with_dimensions(500, 4);
+    let mut second = CountMinSketch::with_dimensions(500, 4);
+    first.add("apple", 10);
+    second.add("apple", 5);
+    second.add("pear", 3);
+    first.merge(&second).unwrap();
+    assert!(first.estimate("apple") >= 15);
+    assert!(first.estimate("pear") >= 3);
+    assert_eq!(first.total(), 18);
+    assert_eq!(first.merge(&CountMinSketch::with_dimensions(500, 5)), Err(CountMinError::DimensionMismatch));
+}
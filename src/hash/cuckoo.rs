@@ -0,0 +1,413 @@
+use std::borrow::Borrow;
+use std::fmt::{Display, Formatter};
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::hash::map::Map;
+use crate::hash::mod_hash::mix;
+use crate::hash::xxhash::XxHashBuilder;
+
+/// the number of buckets allocated by the first insertion
+const MIN_BUCKETS: usize = 4;
+/// the table grows once entries fill MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR of the slots
+const MAX_LOAD_NUMERATOR: usize = 4;
+const MAX_LOAD_DENOMINATOR: usize = 5;
+/// the number of entries an insertion may kick out of their slots before the table is rehashed
+const MAX_KICKS: usize = 500;
+/// the number of times the table is rehashed with new seeds before it is doubled instead
+const REHASHES_PER_SIZE: usize = 4;
+/// the number of rehashes an insertion may trigger before it gives up, the table doubles at most 3 times
+const MAX_REHASHES: usize = 4 * REHASHES_PER_SIZE;
+
+/// The error of [CuckooHashMap::try_put] when no placement for all keys was found, it returns the entry
+#[derive(Debug, PartialEq)]
+pub struct CuckooInsertError<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K, V> Display for CuckooInsertError<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no place for the key after {} rehashes, too many keys share their buckets under every seed", MAX_REHASHES)
+    }
+}
+
+/// A hash map where every key can only be stored in one of d buckets, one chosen by each of d hash functions
+///
+/// Lookups and removals look at no more than d buckets of a few slots each, so their worst case is constant.
+/// An insertion into d full buckets kicks an entry out of one of them and moves it to one of its other buckets,
+/// which may kick out another entry. If that chain gets longer than a fixed bound, the insertion is assumed
+/// to run in a cycle and the whole table is rehashed with new seeds for the hash functions.
+///
+/// The hash functions are derived from the [BuildHasher] S by hashing a seed before the key, so S has to
+/// mix the seed and the whole key into all bits of the hash, like the default [XxHashBuilder] does.
+/// Keys with equal hashes for every seed can never be separated, [ModHashBuilder](crate::hash::mod_hash::ModHashBuilder)
+/// for example reduces keys that are congruent modulo its modulus to the same hash for every seed.
+/// When rehashing cannot place all keys the insertion fails, see [CuckooHashMap::try_put].
+pub struct CuckooHashMap<K, V, S = XxHashBuilder> {
+    /// the slots of all buckets, bucket i holds the slots from i * slots_per_bucket
+    slots: Vec<Option<(K, V)>>,
+    bucket_count: usize,
+    slots_per_bucket: usize,
+    seeds: Vec<u64>,
+    len: usize,
+    rehashes: usize,
+    /// state of the xorshift generator used to pick seeds and the entries to kick out
+    random: u64,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> CuckooHashMap<K, V> {
+    /// creates a map with 2 hash functions and buckets of 4 slots
+    pub fn new() -> Self {
+        Self::with_parameters(2, 4, XxHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CuckooHashMap<K, V, S> {
+    /// creates a map that chooses between hash_functions buckets of slots_per_bucket slots for each key
+    pub fn with_parameters(hash_functions: usize, slots_per_bucket: usize, hash_builder: S) -> Self {
+        assert!(hash_functions >= 2, "cuckoo hashing needs at least 2 hash functions");
+        assert!(slots_per_bucket >= 1, "buckets need at least 1 slot");
+        let mut map = Self {
+            slots: Vec::new(),
+            bucket_count: 0,
+            slots_per_bucket,
+            seeds: vec![0; hash_functions],
+            len: 0,
+            rehashes: 0,
+            random: 0x9E37_79B9_7F4A_7C15,
+            hash_builder,
+        };
+        map.reseed();
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of slots
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// returns how often the table was rebuilt because an insertion could not find a place
+    pub fn rehashes(&self) -> usize {
+        self.rehashes
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        self.random
+    }
+
+    fn reseed(&mut self) {
+        for i in 0..self.seeds.len() {
+            self.seeds[i] = self.next_random();
+        }
+    }
+
+    /// returns the bucket chosen for the key by the hash function with the given index
+    fn bucket<Q: Hash + ?Sized>(&self, function: usize, key: &Q) -> usize {
+        let mut hasher = self.hash_builder.build_hasher();
+        hasher.write_u64(self.seeds[function]);
+        key.hash(&mut hasher);
+        mix(hasher.finish()) as usize & (self.bucket_count - 1)
+    }
+
+    /// returns the buckets chosen for the key by all hash functions
+    fn buckets<Q: Hash + ?Sized>(&self, key: &Q) -> Vec<usize> {
+        (0..self.seeds.len()).map(|function| self.bucket(function, key)).collect()
+    }
+
+    fn bucket_slots(&self, bucket: usize) -> std::ops::Range<usize> {
+        bucket * self.slots_per_bucket..(bucket + 1) * self.slots_per_bucket
+    }
+
+    fn find<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        if self.slots.is_empty() {
+            return None;
+        }
+        (0..self.seeds.len()).find_map(|function| {
+            self.bucket_slots(self.bucket(function, key))
+                .find(|&slot| self.slots[slot].as_ref().is_some_and(|(slot_key, _)| slot_key.borrow() == key))
+        })
+    }
+
+    /// picks the bucket and slot of the entry to kick out of full buckets
+    /// previous is the bucket the entry to be placed was just kicked out of, it should move to one of its other buckets
+    fn victim(&mut self, buckets: Vec<usize>, previous: Option<usize>) -> (usize, usize) {
+        let candidates: Vec<usize> = buckets.iter().copied().filter(|&bucket| Some(bucket) != previous).collect();
+        let candidates = if candidates.is_empty() { buckets } else { candidates };
+        let bucket = candidates[self.next_random() as usize % candidates.len()];
+        (bucket, bucket * self.slots_per_bucket + self.next_random() as usize % self.slots_per_bucket)
+    }
+
+    /// places an entry whose key is not in the map, kicking out other entries if its buckets are full
+    /// returns the entry with the table unchanged when the chain of kicks got too long
+    fn try_insert(&mut self, entry: (K, V)) -> Result<(), (K, V)> {
+        let mut entry = entry;
+        let mut previous = None;
+        let mut kicked = Vec::new();
+        for _ in 0..=MAX_KICKS {
+            let buckets = self.buckets(&entry.0);
+            let free = buckets.iter().flat_map(|&bucket| self.bucket_slots(bucket)).find(|&slot| self.slots[slot].is_none());
+            if let Some(slot) = free {
+                self.slots[slot] = Some(entry);
+                return Ok(());
+            }
+            let (bucket, slot) = self.victim(buckets, previous);
+            entry = self.slots[slot].replace(entry).expect("a full bucket to have no free slots");
+            kicked.push(slot);
+            previous = Some(bucket);
+        }
+        // swapping back along the chain returns every kicked entry to its slot and leaves the new one over
+        for &slot in kicked.iter().rev() {
+            entry = self.slots[slot].replace(entry).expect("a kicked out slot to be full");
+        }
+        Err(entry)
+    }
+
+    /// finds slots for all entries under the current seeds, returning the index of the entry for every slot
+    fn place_all(&mut self, entries: &[(K, V)]) -> Option<Vec<Option<usize>>> {
+        let mut slots = vec![None; self.bucket_count * self.slots_per_bucket];
+        'entries: for index in 0..entries.len() {
+            let mut current = index;
+            let mut previous = None;
+            for _ in 0..=MAX_KICKS {
+                let buckets = self.buckets(&entries[current].0);
+                let free = buckets.iter().flat_map(|&bucket| self.bucket_slots(bucket)).find(|&slot| slots[slot].is_none());
+                if let Some(slot) = free {
+                    slots[slot] = Some(current);
+                    continue 'entries;
+                }
+                let (bucket, slot) = self.victim(buckets, previous);
+                current = slots[slot].replace(current).expect("a full bucket to have no free slots");
+                previous = Some(bucket);
+            }
+            return None;
+        }
+        Some(slots)
+    }
+
+    /// rebuilds the table with new seeds so that it also holds entry, doubling it if rehashing at the given size keeps failing
+    ///
+    /// Entries are only moved once a placement for all of them was found, so after [MAX_REHASHES] failed attempts
+    /// the table is restored as it was and the entry is returned.
+    fn rebuild(&mut self, entry: (K, V), bucket_count: usize) -> Result<(), (K, V)> {
+        let old_seeds = self.seeds.clone();
+        let old_bucket_count = self.bucket_count;
+        let old_slot_count = self.slots.len();
+        let mut origins = Vec::with_capacity(self.len + 1);
+        let mut entries = Vec::with_capacity(self.len + 1);
+        for (slot, old_entry) in self.slots.drain(..).enumerate() {
+            if let Some(old_entry) = old_entry {
+                origins.push(slot);
+                entries.push(old_entry);
+            }
+        }
+        entries.push(entry);
+
+        self.bucket_count = bucket_count;
+        for attempt in 0..MAX_REHASHES {
+            if attempt > 0 && attempt % REHASHES_PER_SIZE == 0 {
+                self.bucket_count *= 2;
+            }
+            self.rehashes += 1;
+            self.reseed();
+            if let Some(placement) = self.place_all(&entries) {
+                let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+                self.slots = placement
+                    .into_iter()
+                    .map(|index| index.map(|index| entries[index].take().expect("every entry to be placed once")))
+                    .collect();
+                return Ok(());
+            }
+        }
+
+        self.seeds = old_seeds;
+        self.bucket_count = old_bucket_count;
+        self.slots.resize_with(old_slot_count, || None);
+        let entry = entries.pop().expect("the new entry to be the last");
+        for (slot, old_entry) in origins.into_iter().zip(entries) {
+            self.slots[slot] = Some(old_entry);
+        }
+        Err(entry)
+    }
+
+    /// inserts a key-value pair, returning the value previously stored for the key
+    ///
+    /// Fails and returns the pair if no placement for all keys is found after 16 rehashes,
+    /// which leaves the map unchanged.
+    pub fn try_put(&mut self, key: K, value: V) -> Result<Option<V>, CuckooInsertError<K, V>> {
+        if let Some(old_value) = self.get_mut(&key) {
+            return Ok(Some(std::mem::replace(old_value, value)));
+        }
+        let placed = if (self.len + 1) * MAX_LOAD_DENOMINATOR > self.slots.len() * MAX_LOAD_NUMERATOR {
+            self.rebuild((key, value), (self.bucket_count * 2).max(MIN_BUCKETS))
+        } else {
+            match self.try_insert((key, value)) {
+                Ok(()) => Ok(()),
+                Err(homeless) => self.rebuild(homeless, self.bucket_count),
+            }
+        };
+        placed.map_err(|(key, value)| CuckooInsertError { key, value })?;
+        self.len += 1;
+        Ok(None)
+    }
+
+    /// inserts a key-value pair like [CuckooHashMap::try_put], panicking if no place for the key is found
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        self.try_put(key, value).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_ref().map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_mut().map(|(_, value)| value)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.len -= 1;
+        self.slots[slot].take().map(|(_, value)| value)
+    }
+
+    /// returns an iterator over the entries in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> Default for CuckooHashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_parameters(2, 4, S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Map<K, V> for CuckooHashMap<K, V, S> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        CuckooHashMap::put(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        CuckooHashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        CuckooHashMap::get_mut(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        CuckooHashMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        CuckooHashMap::len(self)
+    }
+}
+
+#[test]
+pub fn cuckoo_test() {
+    let mut map = CuckooHashMap::new();
+    assert_eq!(map.get("a"), None);
+    assert_eq!(map.remove("a"), None);
+    for word in ["a", "bb", "ccc", "dddd"] {
+        assert_eq!(map.put(word.to_string(), word.len()), None);
+    }
+    assert_eq!(map.put("a".to_string(), 10), Some(1));
+    assert_eq!(map.get("a"), Some(&10));
+    assert_eq!(map.remove("bb"), Some(2));
+    assert!(!map.contains_key("bb"));
+    *map.get_mut("ccc").unwrap() += 1;
+    assert_eq!(map.get("ccc"), Some(&4));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.iter().count(), 3);
+}
+
+#[test]
+pub fn cuckoo_colliding_keys_test() {
+    use crate::hash::mod_hash::{ModHashBuilder, DEFAULT_MODULUS};
+
+    // the multiples of the modulus all have the same mod_hash, whatever seed is hashed before them
+    let keys: Vec<u32> = (0..200).map(|i| i * DEFAULT_MODULUS).collect();
+    let mut map = CuckooHashMap::new();
+    for &key in &keys {
+        assert_eq!(map.put(key, key), None);
+    }
+    assert!(keys.iter().all(|key| map.get(key) == Some(key)));
+
+    let mut map = CuckooHashMap::with_parameters(2, 4, ModHashBuilder::default());
+    let failed = keys.iter().position(|&key| map.try_put(key, key).is_err()).expect("the keys cannot all be placed");
+    // two buckets of 4 slots is all the room the keys share
+    assert!(failed <= 8, "the key {} failed", failed);
+    assert_eq!(map.len(), failed);
+    assert!(keys[..failed].iter().all(|key| map.get(key) == Some(key)));
+    assert_eq!(map.get(&keys[failed]), None);
+    assert_eq!(map.try_put(keys[failed], 0), Err(CuckooInsertError { key: keys[failed], value: 0 }));
+    assert_eq!(map.try_put(keys[0], 1), Ok(Some(keys[0])));
+}
+
+#[test]
+pub fn cuckoo_random_test() {
+    use std::collections::hash_map::RandomState;
+
+    use crate::hash::map::check_against_std;
+    use crate::hash::mod_hash::ModHashBuilder;
+
+    check_against_std(CuckooHashMap::new(), 64, 20_000);
+    check_against_std(CuckooHashMap::new(), 5_000, 20_000);
+    check_against_std(CuckooHashMap::with_parameters(3, 1, ModHashBuilder::default()), 5_000, 20_000);
+    check_against_std(CuckooHashMap::with_parameters(2, 2, RandomState::new()), 5_000, 20_000);
+}
+
+#[test]
+pub fn cuckoo_rehash_test() {
+    use crate::hash::mod_hash::ModHashBuilder;
+
+    // with a single slot per bucket and two hash functions, tables fill up long before the load limit,
+    // so insertions have to rehash with new seeds
+    let mut map = CuckooHashMap::with_parameters(2, 1, ModHashBuilder::default());
+    for i in 0..10_000u32 {
+        map.put(i, i * 2);
+    }
+    assert!(map.rehashes() > 0);
+    assert_eq!(map.len(), 10_000);
+    for i in 0..10_000u32 {
+        // every key is in one of its two buckets
+        let slot = map.find(&i).unwrap();
+        let bucket = slot / map.slots_per_bucket;
+        assert!(bucket == map.bucket(0, &i) || bucket == map.bucket(1, &i));
+        assert_eq!(map.get(&i), Some(&(i * 2)));
+    }
+    assert_eq!(map.get(&10_000), None);
+}
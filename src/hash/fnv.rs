@@ -0,0 +1,90 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::hash::mod_hash::write_integers;
+
+const OFFSET_BASIS_32: u32 = 0x811c_9dc5;
+const PRIME_32: u32 = 0x0100_0193;
+const OFFSET_BASIS_64: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME_64: u64 = 0x0100_0000_01b3;
+
+/// This function returns the 32 bit FNV-1a hash of the bytes
+pub fn fnv1a_32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(OFFSET_BASIS_32, |hash, &byte| (hash ^ byte as u32).wrapping_mul(PRIME_32))
+}
+
+/// This function returns the 64 bit FNV-1a hash of the bytes
+///
+/// FNV-1a xors each byte into the hash and multiplies it by a prime. It is very fast for short keys,
+/// but mixes the last bytes poorly, so maps should not rely on the low bits of the hash alone.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(OFFSET_BASIS_64, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME_64))
+}
+
+/// A [Hasher] that computes [fnv1a_64] of the bytes written to it without buffering them
+pub struct FnvHasher {
+    hash: u64,
+}
+
+impl FnvHasher {
+    pub fn new() -> Self {
+        Self { hash: OFFSET_BASIS_64 }
+    }
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash = (self.hash ^ byte as u64).wrapping_mul(PRIME_64);
+        }
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+/// Creates [FnvHasher]s
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FnvHashBuilder;
+
+impl BuildHasher for FnvHashBuilder {
+    type Hasher = FnvHasher;
+
+    fn build_hasher(&self) -> FnvHasher {
+        FnvHasher::new()
+    }
+}
+
+#[test]
+pub fn fnv_test() {
+    // test vectors from the reference implementation by Landon Curt Noll
+    assert_eq!(fnv1a_32(b""), 0x811c_9dc5);
+    assert_eq!(fnv1a_32(b"a"), 0xe40c_292c);
+    assert_eq!(fnv1a_32(b"foobar"), 0xbf9c_f968);
+    assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+pub fn fnv_hasher_test() {
+    use crate::hash::to_bytes::ToBytes;
+
+    let mut hasher = FnvHasher::new();
+    hasher.write(b"foo");
+    hasher.write(b"bar");
+    assert_eq!(hasher.finish(), fnv1a_64(b"foobar"));
+    assert_eq!(FnvHashBuilder.hash_one(0x1234u32), fnv1a_64(&0x1234u32.to_bytes()));
+    assert_eq!(FnvHashBuilder.hash_one("abc"), FnvHashBuilder.hash_one(String::from("abc")));
+}
@@ -0,0 +1,266 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::iter::Chain;
+
+use crate::hash::mod_hash::ModHashBuilder;
+use crate::hash::static_hashmap::{self, StaticHashMap};
+
+/// A set of keys stored in the buckets of a [StaticHashMap] without values
+///
+/// It hashes keys and fills buckets exactly like the map, and just like the map
+/// it only changes its number of buckets when [HashSet::resize] is called.
+/// The set operations return iterators that look up each key while they are consumed.
+pub struct HashSet<K, S = ModHashBuilder> {
+    map: StaticHashMap<K, (), S>,
+}
+
+impl<K: Hash + Eq> HashSet<K> {
+    pub fn new(size: u32) -> Self {
+        Self::with_hasher(size, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> HashSet<K, S> {
+    /// creates a set with the given number of buckets that hashes keys with hash_builder
+    pub fn with_hasher(size: u32, hash_builder: S) -> Self {
+        Self {
+            map: StaticHashMap::with_hasher(size, hash_builder),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.map.bucket_count()
+    }
+
+    /// moves all keys into size new buckets
+    pub fn resize(&mut self, size: u32) {
+        self.map.resize(size);
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// adds the key, returns false if it was already in the set
+    pub fn insert(&mut self, key: K) -> bool {
+        match self.map.entry(key) {
+            static_hashmap::Entry::Occupied(_) => false,
+            static_hashmap::Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+        }
+    }
+
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// removes the key, returns false if it was not in the set
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.remove(key).is_some()
+    }
+
+    /// returns an iterator over the keys in bucket order
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter { iter: self.map.iter() }
+    }
+
+    /// returns the keys in self that are not in other
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, K, S> {
+        Difference { iter: self.iter(), other }
+    }
+
+    /// returns the keys in self that are also in other
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, K, S> {
+        // looking up the keys of the smaller set in the larger one takes fewer lookups
+        let (smaller, larger) = if self.len() <= other.len() { (self, other) } else { (other, self) };
+        Intersection {
+            iter: smaller.iter(),
+            other: larger,
+        }
+    }
+
+    /// returns the keys of self followed by the keys of other that are not in self
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, K, S> {
+        Union {
+            iter: self.iter().chain(other.difference(self)),
+        }
+    }
+
+    /// returns the keys that are in exactly one of the sets
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, K, S> {
+        SymmetricDifference {
+            iter: self.difference(other).chain(other.difference(self)),
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|key| other.contains(key))
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+/// An iterator over the keys of a [HashSet]
+pub struct Iter<'a, K> {
+    iter: static_hashmap::Iter<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+}
+
+/// The lazy iterator returned by [HashSet::difference]
+pub struct Difference<'a, K, S> {
+    iter: Iter<'a, K>,
+    other: &'a HashSet<K, S>,
+}
+
+impl<'a, K: Hash + Eq, S: BuildHasher> Iterator for Difference<'a, K, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|key| !other.contains(*key))
+    }
+}
+
+/// The lazy iterator returned by [HashSet::intersection]
+pub struct Intersection<'a, K, S> {
+    iter: Iter<'a, K>,
+    other: &'a HashSet<K, S>,
+}
+
+impl<'a, K: Hash + Eq, S: BuildHasher> Iterator for Intersection<'a, K, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|key| other.contains(*key))
+    }
+}
+
+/// The lazy iterator returned by [HashSet::union]
+pub struct Union<'a, K, S> {
+    iter: Chain<Iter<'a, K>, Difference<'a, K, S>>,
+}
+
+impl<'a, K: Hash + Eq, S: BuildHasher> Iterator for Union<'a, K, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+/// The lazy iterator returned by [HashSet::symmetric_difference]
+pub struct SymmetricDifference<'a, K, S> {
+    iter: Chain<Difference<'a, K, S>, Difference<'a, K, S>>,
+}
+
+impl<'a, K: Hash + Eq, S: BuildHasher> Iterator for SymmetricDifference<'a, K, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+impl<'a, K: Hash + Eq, S: BuildHasher> IntoIterator for &'a HashSet<K, S> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Collects the keys into a set with one bucket per key
+impl<K: Hash + Eq, S: BuildHasher + Default> FromIterator<K> for HashSet<K, S> {
+    fn from_iter<T: IntoIterator<Item = K>>(iter: T) -> Self {
+        Self {
+            map: iter.into_iter().map(|key| (key, ())).collect(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> Extend<K> for HashSet<K, S> {
+    fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+#[cfg(test)]
+fn sorted<'a>(keys: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+    let mut keys: Vec<u32> = keys.copied().collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+pub fn hash_set_test() {
+    let mut set = HashSet::new(7);
+    assert!(set.insert("a".to_string()));
+    assert!(set.insert("b".to_string()));
+    assert!(!set.insert("a".to_string()));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains("a"));
+    assert!(set.remove("a"));
+    assert!(!set.remove("a"));
+    assert!(!set.contains("a"));
+
+    set.extend((0..50).map(|i| i.to_string()));
+    assert_eq!(set.len(), 51);
+    set.resize(64);
+    assert_eq!(set.bucket_count(), 64);
+    assert!((0..50).all(|i| set.contains(&i.to_string())) && set.contains("b"));
+    assert_eq!(set.iter().count(), 51);
+    set.clear();
+    assert!(set.is_empty());
+}
+
+#[test]
+pub fn hash_set_operations_test() {
+    let evens: HashSet<u32> = (0..20).step_by(2).collect();
+    let small: HashSet<u32> = (0..10).collect();
+
+    assert_eq!(sorted(evens.union(&small)), vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 14, 16, 18]);
+    assert_eq!(sorted(evens.intersection(&small)), vec![0, 2, 4, 6, 8]);
+    assert_eq!(sorted(small.intersection(&evens)), vec![0, 2, 4, 6, 8]);
+    assert_eq!(sorted(evens.difference(&small)), vec![10, 12, 14, 16, 18]);
+    assert_eq!(sorted(small.difference(&evens)), vec![1, 3, 5, 7, 9]);
+    assert_eq!(sorted(evens.symmetric_difference(&small)), vec![1, 3, 5, 7, 9, 10, 12, 14, 16, 18]);
+
+    // the iterators are lazy, taking the first key does not look at the others
+    assert!(evens.union(&small).next().is_some());
+    let odds: HashSet<u32> = (1..20).step_by(2).collect();
+    assert!(evens.is_disjoint(&odds));
+    assert!(!evens.is_disjoint(&small));
+    let fours: HashSet<u32> = (0..20).step_by(4).collect();
+    assert!(fours.is_subset(&evens));
+    assert!(!evens.is_subset(&fours));
+    assert_eq!(evens.union(&odds).count(), 20);
+}
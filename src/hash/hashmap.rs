@@ -0,0 +1,284 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+
+use crate::hash::map::Map;
+use crate::hash::mod_hash::ModHashBuilder;
+
+/// the number of buckets allocated by the first insertion
+const MIN_BUCKETS: usize = 8;
+/// the map grows once the number of entries per bucket would exceed MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR
+const MAX_LOAD_NUMERATOR: usize = 3;
+const MAX_LOAD_DENOMINATOR: usize = 4;
+
+/// A hash map with separate chaining that grows as entries are added
+///
+/// Whenever an insertion would push the load factor above 3/4, the number of buckets is doubled
+/// and all entries are rehashed, so chains stay short and lookups take expected constant time.
+/// The map never shrinks on its own, [HashMap::shrink_to_fit] releases unused buckets on demand.
+/// For a fixed number of buckets use [StaticHashMap](crate::hash::static_hashmap::StaticHashMap).
+pub struct HashMap<K, V, S = ModHashBuilder> {
+    buckets: Vec<Vec<(K, V)>>,
+    len: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> HashMap<K, V> {
+    /// creates an empty map, no buckets are allocated until the first insertion
+    pub fn new() -> Self {
+        Self::with_hasher(ModHashBuilder::default())
+    }
+
+    /// creates an empty map that can hold at least capacity entries without rehashing
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    /// creates an empty map that hashes keys with hash_builder
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            buckets: Vec::new(),
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut map = Self::with_hasher(hash_builder);
+        map.reserve(capacity);
+        map
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of entries the map can hold before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR
+    }
+
+    /// returns the number of buckets
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// returns the average number of entries per bucket
+    pub fn load_factor(&self) -> f64 {
+        if self.buckets.is_empty() {
+            return 0.0;
+        }
+        self.len as f64 / self.buckets.len() as f64
+    }
+
+    /// makes room for at least additional more entries, so that inserting them does not rehash
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len + additional;
+        if required > self.capacity() {
+            self.resize(Self::buckets_for(required));
+        }
+    }
+
+    /// shrinks the buckets to the smallest power of two that holds the current entries, rehashing them
+    pub fn shrink_to_fit(&mut self) {
+        let bucket_count = if self.len == 0 { 0 } else { Self::buckets_for(self.len) };
+        if bucket_count < self.buckets.len() {
+            self.resize(bucket_count);
+        }
+    }
+
+    /// returns the smallest power of two number of buckets that can hold count entries
+    fn buckets_for(count: usize) -> usize {
+        let minimum = (count * MAX_LOAD_DENOMINATOR).div_ceil(MAX_LOAD_NUMERATOR);
+        minimum.next_power_of_two().max(MIN_BUCKETS)
+    }
+
+    /// moves all entries into a new set of buckets
+    fn resize(&mut self, bucket_count: usize) {
+        let mut buckets = Vec::with_capacity(bucket_count);
+        buckets.resize_with(bucket_count, Vec::new);
+        let old_buckets = std::mem::replace(&mut self.buckets, buckets);
+        for (key, value) in old_buckets.into_iter().flatten() {
+            let hash = self.hash(&key);
+            self.buckets[hash].push((key, value));
+        }
+    }
+
+    /// returns the index of the bucket for the key, the map must have buckets
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    /// returns the bucket and the index within it of the key
+    fn position<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<(usize, usize)>
+    where
+        K: Borrow<Q>,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let hash = self.hash(key);
+        let index = self.buckets[hash].iter().position(|pair| pair.0.borrow() == key)?;
+        Some((hash, index))
+    }
+
+    /// inserts a key-value pair, returning the value previously stored for the key
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old_value) = self.get_mut(&key) {
+            return Some(std::mem::replace(old_value, value));
+        }
+        self.reserve(1);
+        let hash = self.hash(&key);
+        self.buckets[hash].push((key, value));
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let (hash, index) = self.position(key)?;
+        Some(&self.buckets[hash][index].1)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let (hash, index) = self.position(key)?;
+        Some(&mut self.buckets[hash][index].1)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.position(key).is_some()
+    }
+
+    /// removes a key from the map, returning its value
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let (hash, index) = self.position(key)?;
+        self.len -= 1;
+        Some(self.buckets[hash].swap_remove(index).1)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Map<K, V> for HashMap<K, V, S> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::put(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        HashMap::get_mut(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        HashMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+#[test]
+pub fn hashmap_test() {
+    let mut map = HashMap::new();
+    assert_eq!(map.bucket_count(), 0);
+    assert_eq!(map.get(&1), None);
+    assert_eq!(map.put(10, "Hello, World!"), None);
+    assert_eq!(map.put(20, "Bye!"), None);
+    assert_eq!(map.put(10, "Hello again!"), Some("Hello, World!"));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&10), Some(&"Hello again!"));
+    assert_eq!(map.get(&30), None);
+    *map.get_mut(&20).unwrap() = "See you!";
+    assert_eq!(map.remove(&20), Some("See you!"));
+    assert_eq!(map.remove(&20), None);
+    assert!(!map.contains_key(&20));
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+pub fn hashmap_growth_test() {
+    let mut map = HashMap::new();
+    for i in 0..1000 {
+        map.put(i, i * i);
+        assert!(map.load_factor() <= 0.75);
+    }
+    assert_eq!(map.len(), 1000);
+    assert_eq!(map.bucket_count(), 2048);
+    for i in 0..1000 {
+        assert_eq!(map.get(&i), Some(&(i * i)));
+    }
+
+    for i in 0..990 {
+        assert_eq!(map.remove(&i), Some(i * i));
+    }
+    // removing never shrinks on its own
+    assert_eq!(map.bucket_count(), 2048);
+    map.shrink_to_fit();
+    assert_eq!(map.bucket_count(), 16);
+    for i in 990..1000 {
+        assert_eq!(map.get(&i), Some(&(i * i)));
+    }
+    for i in 990..1000 {
+        map.remove(&i);
+    }
+    map.shrink_to_fit();
+    assert_eq!(map.bucket_count(), 0);
+}
+
+#[test]
+pub fn hashmap_capacity_test() {
+    let mut map = HashMap::with_capacity(100);
+    assert!(map.capacity() >= 100);
+    let bucket_count = map.bucket_count();
+    for i in 0..100 {
+        map.put(i, ());
+    }
+    assert_eq!(map.bucket_count(), bucket_count);
+
+    map.reserve(500);
+    assert!(map.capacity() >= 600);
+    assert_eq!(map.len(), 100);
+    assert!((0..100).all(|i| map.contains_key(&i)));
+}
+
+#[test]
+pub fn hashmap_hasher_test() {
+    use std::collections::hash_map::RandomState;
+
+    let mut map = HashMap::with_hasher(RandomState::new());
+    for word in ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"] {
+        map.put(word.to_string(), word.len());
+    }
+    assert_eq!(map.len(), 10);
+    assert_eq!(map.get("c"), Some(&1));
+    assert_eq!(map.remove("j"), Some(1));
+    assert!(!map.contains_key("j"));
+}
@@ -0,0 +1,301 @@
+use std::fmt::{Display, Formatter};
+
+use crate::hash::to_bytes::ToBytes;
+use crate::hash::xxhash::xxhash64;
+
+/// the precision of the sparse representation, which is high enough to count small sets almost exactly
+const SPARSE_PRECISION: u32 = 25;
+const MIN_PRECISION: u32 = 4;
+const MAX_PRECISION: u32 = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum HyperLogLogError {
+    /// sketches with a different number of registers cannot be merged
+    PrecisionMismatch,
+}
+
+impl Display for HyperLogLogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HyperLogLogError::PrecisionMismatch => write!(f, "the sketches have a different precision"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Registers {
+    /// the index and rank at [SPARSE_PRECISION] of every hash seen, sorted by index
+    Sparse(Vec<(u32, u8)>),
+    /// the highest rank seen for each of the 2^precision registers
+    Dense(Vec<u8>),
+}
+
+/// returns the register of the hash, which is its first precision bits, and its rank,
+/// the position of the first 1 bit after them
+fn index_and_rank(hash: u64, precision: u32) -> (u32, u8) {
+    let index = (hash >> (64 - precision)) as u32;
+    let rank = (hash << precision).leading_zeros().min(64 - precision) + 1;
+    (index, rank as u8)
+}
+
+/// converts an entry of the sparse representation into the register and rank the hash has at precision
+fn dense_entry(sparse_index: u32, sparse_rank: u8, precision: u32) -> (usize, u8) {
+    let extra = SPARSE_PRECISION - precision;
+    let index = (sparse_index >> extra) as usize;
+    // the bits of the sparse index that are not part of the dense one come first in the rest of the hash
+    let low = sparse_index & ((1 << extra) - 1);
+    if low == 0 {
+        (index, extra as u8 + sparse_rank)
+    } else {
+        (index, (low.leading_zeros() - (32 - extra)) as u8 + 1)
+    }
+}
+
+/// Estimates the number of distinct keys in a stream in a fixed amount of memory
+///
+/// Every key is hashed, the first bits of the hash select one of m registers and the register keeps the largest
+/// number of leading zeros seen in the rest of the hash. Many distinct keys make long runs of zeros likely,
+/// so the harmonic mean of the registers estimates the count with a standard error of 1.04 / sqrt(m).
+///
+/// While few keys are seen, the sketch stores only the registers that were hit, at a higher precision,
+/// and counts them almost exactly. It switches to the dense registers once the sparse list would take
+/// more memory than them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    precision: u32,
+    registers: Registers,
+}
+
+impl HyperLogLog {
+    /// creates a sketch with 2^precision registers, precision must be between 4 and 16
+    pub fn new(precision: u32) -> Self {
+        assert!((MIN_PRECISION..=MAX_PRECISION).contains(&precision), "precision must be in 4..=16");
+        Self {
+            precision,
+            registers: Registers::Sparse(Vec::new()),
+        }
+    }
+
+    /// creates the smallest sketch whose standard error is at most relative_error
+    pub fn with_error(relative_error: f64) -> Self {
+        assert!(relative_error > 0.0, "the error must be positive");
+        let registers = (1.04 / relative_error).powi(2);
+        let precision = (registers.log2().ceil() as u32).max(MIN_PRECISION);
+        assert!(precision <= MAX_PRECISION, "an error of {} needs more than 2^16 registers", relative_error);
+        Self::new(precision)
+    }
+
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// returns the standard error of the estimates, relative to the true count
+    pub fn relative_error(&self) -> f64 {
+        1.04 / (self.register_count() as f64).sqrt()
+    }
+
+    fn register_count(&self) -> usize {
+        1 << self.precision
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.registers, Registers::Sparse(_))
+    }
+
+    pub fn insert<K: ToBytes + ?Sized>(&mut self, key: &K) {
+        let hash = xxhash64(&key.to_bytes(), 0);
+        match &mut self.registers {
+            Registers::Sparse(entries) => {
+                let (index, rank) = index_and_rank(hash, SPARSE_PRECISION);
+                match entries.binary_search_by_key(&index, |&(index, _)| index) {
+                    Ok(position) => entries[position].1 = entries[position].1.max(rank),
+                    Err(position) => entries.insert(position, (index, rank)),
+                }
+            }
+            Registers::Dense(registers) => {
+                let (index, rank) = index_and_rank(hash, self.precision);
+                registers[index as usize] = registers[index as usize].max(rank);
+            }
+        }
+        self.densify_if_large();
+    }
+
+    /// switches to the dense registers once the sparse entries take more memory than them
+    fn densify_if_large(&mut self) {
+        if let Registers::Sparse(entries) = &self.registers {
+            // a sparse entry takes 5 bytes and a register 1
+            if entries.len() * 5 > self.register_count() {
+                self.registers = Registers::Dense(self.dense_registers());
+            }
+        }
+    }
+
+    /// returns the registers at the precision of the sketch
+    fn dense_registers(&self) -> Vec<u8> {
+        match &self.registers {
+            Registers::Sparse(entries) => {
+                let mut registers = vec![0; self.register_count()];
+                for &(sparse_index, sparse_rank) in entries {
+                    let (index, rank) = dense_entry(sparse_index, sparse_rank, self.precision);
+                    registers[index] = registers[index].max(rank);
+                }
+                registers
+            }
+            Registers::Dense(registers) => registers.clone(),
+        }
+    }
+
+    /// returns the estimated number of distinct keys inserted
+    pub fn estimate(&self) -> f64 {
+        match &self.registers {
+            Registers::Sparse(entries) => {
+                // linear counting over the 2^25 sparse registers, of which almost none collide
+                let m = (1u64 << SPARSE_PRECISION) as f64;
+                m * (m / (m - entries.len() as f64)).ln()
+            }
+            Registers::Dense(registers) => {
+                let m = registers.len() as f64;
+                let alpha = match registers.len() {
+                    16 => 0.673,
+                    32 => 0.697,
+                    64 => 0.709,
+                    _ => 0.7213 / (1.0 + 1.079 / m),
+                };
+                let sum: f64 = registers.iter().map(|&rank| 2f64.powi(-(rank as i32))).sum();
+                let raw = alpha * m * m / sum;
+                let zeros = registers.iter().filter(|&&rank| rank == 0).count();
+                // for small counts the number of empty registers is the better estimate
+                if raw <= 2.5 * m && zeros > 0 {
+                    m * (m / zeros as f64).ln()
+                } else {
+                    raw
+                }
+            }
+        }
+    }
+
+    /// adds the keys of other to this sketch, as if they had been inserted into it
+    pub fn merge(&mut self, other: &Self) -> Result<(), HyperLogLogError> {
+        if self.precision != other.precision {
+            return Err(HyperLogLogError::PrecisionMismatch);
+        }
+        match (&mut self.registers, &other.registers) {
+            (Registers::Sparse(entries), Registers::Sparse(other_entries)) => {
+                let mut merged = Vec::with_capacity(entries.len() + other_entries.len());
+                let (mut i, mut j) = (0, 0);
+                while i < entries.len() || j < other_entries.len() {
+                    if j == other_entries.len() || (i < entries.len() && entries[i].0 < other_entries[j].0) {
+                        merged.push(entries[i]);
+                        i += 1;
+                    } else if i == entries.len() || other_entries[j].0 < entries[i].0 {
+                        merged.push(other_entries[j]);
+                        j += 1;
+                    } else {
+                        merged.push((entries[i].0, entries[i].1.max(other_entries[j].1)));
+                        i += 1;
+                        j += 1;
+                    }
+                }
+                *entries = merged;
+                self.densify_if_large();
+            }
+            _ => {
+                let mut registers = self.dense_registers();
+                for (register, rank) in registers.iter_mut().zip(other.dense_registers()) {
+                    *register = (*register).max(rank);
+                }
+                self.registers = Registers::Dense(registers);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn relative_difference(estimate: f64, count: usize) -> f64 {
+    (estimate - count as f64).abs() / count as f64
+}
+
+#[test]
+pub fn hyperloglog_test() {
+    let mut sketch = HyperLogLog::new(12);
+    assert_eq!(sketch.estimate(), 0.0);
+    let mut inserted = 0;
+    for count in [10, 100, 1_000, 10_000, 100_000, 300_000] {
+        while inserted < count {
+            sketch.insert(&(inserted as u32));
+            // inserting a key twice does not change the count
+            sketch.insert(&(inserted as u32));
+            inserted += 1;
+        }
+        let error = relative_difference(sketch.estimate(), count);
+        assert!(error <= 3.0 * sketch.relative_error(), "estimate {} for {} keys", sketch.estimate(), count);
+    }
+    assert!(!sketch.is_sparse());
+
+    let sketch = HyperLogLog::with_error(0.01);
+    assert_eq!(sketch.precision(), 14);
+    assert!(sketch.relative_error() <= 0.01);
+}
+
+#[test]
+pub fn hyperloglog_sparse_test() {
+    let mut sketch = HyperLogLog::new(14);
+    for i in 0..2_000u32 {
+        sketch.insert(&format!("user-{}", i));
+    }
+    // the sparse representation counts small sets far more precisely than the registers could
+    assert!(sketch.is_sparse());
+    assert!(relative_difference(sketch.estimate(), 2_000) < 0.002, "estimate {}", sketch.estimate());
+
+    // converting to dense registers gives the same registers as inserting into them directly
+    let mut dense = HyperLogLog::new(14);
+    dense.registers = Registers::Dense(vec![0; 1 << 14]);
+    for i in 0..2_000u32 {
+        dense.insert(&format!("user-{}", i));
+    }
+    assert_eq!(sketch.dense_registers(), dense.dense_registers());
+    for i in 2_000..4_000u32 {
+        sketch.insert(&format!("user-{}", i));
+    }
+    assert!(!sketch.is_sparse());
+    assert!(relative_difference(sketch.estimate(), 4_000) <= 3.0 * sketch.relative_error());
+}
+
+#[test]
+pub fn hyperloglog_merge_test() {
+    let mut evens = HyperLogLog::new(10);
+    let mut odds = HyperLogLog::new(10);
+    let mut small = HyperLogLog::new(10);
+    for i in 0..50_000u64 {
+        evens.insert(&(2 * i));
+        odds.insert(&(2 * i + 1));
+    }
+    for i in 0..50u64 {
+        small.insert(&(i * 1_000_003));
+    }
+
+    let mut all = evens.clone();
+    all.merge(&odds).unwrap();
+    assert!(relative_difference(all.estimate(), 100_000) <= 3.0 * all.relative_error());
+    // merging a sketch into itself changes nothing
+    let estimate = all.estimate();
+    all.merge(&all.clone()).unwrap();
+    assert_eq!(all.estimate(), estimate);
+
+    let mut sparse = small.clone();
+    sparse.merge(&small).unwrap();
+    assert!(sparse.is_sparse());
+    assert_eq!(sparse, small);
+    let mut other = HyperLogLog::new(10);
+    for i in 0..50u64 {
+        other.insert(&(i + 7));
+    }
+    sparse.merge(&other).unwrap();
+    assert!(relative_difference(sparse.estimate(), 100) < 0.02);
+    small.merge(&evens).unwrap();
+    assert!(!small.is_sparse());
+    assert!(relative_difference(small.estimate(), 50_049) <= 3.0 * small.relative_error());
+
+    assert_eq!(evens.merge(&HyperLogLog::new(11)), Err(HyperLogLogError::PrecisionMismatch));
+}
@@ -0,0 +1,49 @@
+/// The operations shared by the hash maps in this module, so they can be swapped for one another
+pub trait Map<K, V> {
+    /// inserts a key-value pair, returning the value previously stored for the key
+    fn put(&mut self, key: K, value: V) -> Option<V>;
+
+    fn get(&self, key: &K) -> Option<&V>;
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+
+    /// removes a key from the map, returning its value
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn len(&self) -> usize;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Applies a pseudo random sequence of insertions, updates and removals to map and to a std HashMap,
+/// checking after every operation that both agree
+///
+/// Small key ranges make most operations hit existing keys, which exercises deletion heavy workloads.
+#[cfg(test)]
+pub(crate) fn check_against_std<M: Map<u32, u32>>(mut map: M, key_range: u32, operations: usize) -> M {
+    let mut expected = std::collections::HashMap::new();
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for i in 0..operations {
+        // xorshift64, deterministic so failures can be reproduced
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let key = (state % key_range as u64) as u32;
+        if (state >> 32).is_multiple_of(3) {
+            assert_eq!(map.remove(&key), expected.remove(&key), "remove {} in operation {}", key, i);
+        } else {
+            assert_eq!(map.put(key, i as u32), expected.insert(key, i as u32), "put {} in operation {}", key, i);
+        }
+        assert_eq!(map.len(), expected.len());
+    }
+    for key in 0..key_range {
+        assert_eq!(map.get(&key), expected.get(&key), "get {}", key);
+    }
+    map
+}
@@ -1,4 +1,52 @@
 
+pub mod to_bytes;
+
 pub mod mod_hash;
 
-pub mod static_hashmap;
\ No newline at end of file
+pub mod fnv;
+
+pub mod murmur3;
+
+pub mod xxhash;
+
+pub mod siphash;
+
+pub mod map;
+
+pub mod static_hashmap;
+
+pub mod hashmap;
+
+pub mod open_addressing;
+
+pub mod robin_hood;
+
+pub mod cuckoo;
+
+pub mod perfect;
+
+pub mod rolling;
+
+pub mod bloom;
+
+pub mod hyperloglog;
+
+pub mod count_min;
+
+pub mod space_saving;
+
+pub mod sharding;
+
+pub mod consistent_hashing;
+
+pub mod rendezvous;
+
+pub mod cache;
+
+pub mod hash_set;
+
+pub mod multimap;
+
+pub mod concurrent_hashmap;
+
+pub mod cdb;
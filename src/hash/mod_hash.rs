@@ -1,3 +1,8 @@
+use std::hash::{BuildHasher, Hasher};
+
+/// the largest prime for which [mod_hash] cannot overflow, used as the default modulus of [ModHasher]
+pub const DEFAULT_MODULUS: u32 = 16_777_213;
+
 /// This function returns data mod m
 ///
 /// data can be an arbitrarily large number specified in big endian order
@@ -9,9 +14,104 @@ pub fn mod_hash(data: Vec<u8>, m: u32) -> u32 {
     sum
 }
 
+/// Spreads the bits of a hash over the whole word, so that hashes of similar keys look unrelated
+/// This is the finalizer of splitmix64.
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A [Hasher] that collects the bytes written to it and hashes them with [mod_hash]
+///
+/// Integers are written as their [ToBytes](crate::hash::to_bytes::ToBytes) representation, so a key hashed
+/// through [std::hash::Hash] gets the same hash as its bytes passed to [mod_hash] directly.
+pub struct ModHasher {
+    bytes: Vec<u8>,
+    modulus: u32,
+}
+
+impl ModHasher {
+    /// creates a hasher for the given modulus, which must be at most 2^24 so the hash cannot overflow
+    pub fn new(modulus: u32) -> Self {
+        assert!(modulus > 0 && modulus <= 1 << 24, "modulus must be in 1..=2^24");
+        Self {
+            bytes: Vec::new(),
+            modulus,
+        }
+    }
+}
+
+/// Implements the integer methods of [Hasher] by writing the [ToBytes](crate::hash::to_bytes::ToBytes)
+/// representation of the integer, instead of the native endian bytes std writes by default
+macro_rules! write_integers {
+    ($($method:ident: $integer:ty),*) => {
+        $(
+            fn $method(&mut self, i: $integer) {
+                self.write(&$crate::hash::to_bytes::ToBytes::to_bytes(&i))
+            }
+        )*
+    };
+}
+
+pub(crate) use write_integers;
+
+impl Hasher for ModHasher {
+    fn finish(&self) -> u64 {
+        mod_hash(self.bytes.clone(), self.modulus) as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+/// Creates [ModHasher]s with a fixed modulus, this is the default hasher of the maps in this module
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModHashBuilder {
+    modulus: u32,
+}
+
+impl ModHashBuilder {
+    pub fn new(modulus: u32) -> Self {
+        Self { modulus }
+    }
+}
+
+impl Default for ModHashBuilder {
+    fn default() -> Self {
+        Self::new(DEFAULT_MODULUS)
+    }
+}
+
+impl BuildHasher for ModHashBuilder {
+    type Hasher = ModHasher;
+
+    fn build_hasher(&self) -> ModHasher {
+        ModHasher::new(self.modulus)
+    }
+}
+
 #[test]
 pub fn mod_hash_test() {
     assert_eq!(mod_hash(vec![97], 5), 97 % 5);
     assert_eq!(mod_hash(123_456_789u32.to_be_bytes().into(), 27), 123_456_789u32 % 27);
     assert_eq!(mod_hash(vec![0x01, 0x1C, 0x4D, 0xFA, 0xF1], 97), 75);
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn mod_hasher_test() {
+    use crate::hash::to_bytes::ToBytes;
+
+    let builder = ModHashBuilder::new(97);
+    assert_eq!(builder.hash_one(123_456_789i32), mod_hash(123_456_789i32.to_bytes(), 97) as u64);
+    assert_eq!(builder.hash_one(0x011C4DFAF1u64), mod_hash(0x011C4DFAF1u64.to_bytes(), 97) as u64);
+    assert_eq!(builder.hash_one(0x011C4DFAF1u64), 75);
+    assert_eq!(builder.hash_one("abc"), builder.hash_one(String::from("abc")));
+    assert!(ModHashBuilder::default().hash_one(u64::MAX) < DEFAULT_MODULUS as u64);
+}
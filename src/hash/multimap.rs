@@ -0,0 +1,203 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+
+use crate::hash::mod_hash::ModHashBuilder;
+use crate::hash::static_hashmap::StaticHashMap;
+
+/// A map that stores any number of values per key, in the order they were inserted
+///
+/// The values of a key are kept in one entry of a [StaticHashMap], so the multimap hashes keys and fills
+/// buckets exactly like the map and only changes its number of buckets when [MultiMap::resize] is called.
+pub struct MultiMap<K, V, S = ModHashBuilder> {
+    map: StaticHashMap<K, Vec<V>, S>,
+    /// the number of values of all keys
+    len: usize,
+}
+
+impl<K: Hash + Eq, V> MultiMap<K, V> {
+    pub fn new(size: u32) -> Self {
+        Self::with_hasher(size, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> MultiMap<K, V, S> {
+    pub fn with_hasher(size: u32, hash_builder: S) -> Self {
+        Self {
+            map: StaticHashMap::with_hasher(size, hash_builder),
+            len: 0,
+        }
+    }
+
+    /// returns the number of values of all keys
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of distinct keys
+    pub fn key_count(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.map.bucket_count()
+    }
+
+    /// moves all keys into size new buckets
+    pub fn resize(&mut self, size: u32) {
+        self.map.resize(size);
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+
+    /// adds a value for the key after the values it already has
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.entry(key).or_insert_with(Vec::new).push(value);
+        self.len += 1;
+    }
+
+    /// returns the first value of the key
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key)?.first()
+    }
+
+    /// returns all values of the key in insertion order, an empty slice if it has none
+    pub fn get_all<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> &[V]
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// returns whether the value is one of the values of the key
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        V: PartialEq,
+    {
+        self.get_all(key).contains(value)
+    }
+
+    /// removes the first occurrence of the value from the values of the key, returns false if it was not there
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        V: PartialEq,
+    {
+        let Some(values) = self.map.get_mut(key) else {
+            return false;
+        };
+        let Some(index) = values.iter().position(|candidate| candidate == value) else {
+            return false;
+        };
+        values.remove(index);
+        self.len -= 1;
+        if values.is_empty() {
+            self.map.remove(key);
+        }
+        true
+    }
+
+    /// removes the key with all of its values, returning them in insertion order
+    pub fn remove_all<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+    {
+        let values = self.map.remove(key).unwrap_or_default();
+        self.len -= values.len();
+        values
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.map.keys()
+    }
+
+    /// returns every key once with all of its values
+    pub fn iter_all(&self) -> impl Iterator<Item = (&K, &[V])> {
+        self.map.iter().map(|(key, values)| (key, values.as_slice()))
+    }
+
+    /// returns a pair for every value, the values of a key follow each other
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+    }
+}
+
+/// Collects the pairs into a multimap with one bucket per pair, keeping every value
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for MultiMap<K, V, S> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let pairs: Vec<(K, V)> = iter.into_iter().collect();
+        let mut map = Self::with_hasher(pairs.len().max(1) as u32, S::default());
+        map.extend(pairs);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for MultiMap<K, V, S> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+#[test]
+pub fn multimap_test() {
+    let mut map = MultiMap::new(11);
+    map.insert("fruit".to_string(), "apple");
+    map.insert("fruit".to_string(), "pear");
+    map.insert("vegetable".to_string(), "leek");
+    map.insert("fruit".to_string(), "apple");
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.key_count(), 2);
+    assert_eq!(map.get("fruit"), Some(&"apple"));
+    assert_eq!(map.get_all("fruit"), &["apple", "pear", "apple"]);
+    assert_eq!(map.get_all("grain"), &[] as &[&str]);
+    assert!(map.contains("vegetable", &"leek"));
+    assert!(!map.contains("vegetable", &"pear"));
+
+    assert!(map.remove("fruit", &"apple"));
+    assert_eq!(map.get_all("fruit"), &["pear", "apple"]);
+    assert!(!map.remove("fruit", &"plum"));
+    assert!(map.remove("vegetable", &"leek"));
+    assert!(!map.contains_key("vegetable"));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.remove_all("fruit"), vec!["pear", "apple"]);
+    assert!(map.is_empty());
+    assert_eq!(map.remove_all("fruit"), Vec::<&str>::new());
+}
+
+#[test]
+pub fn multimap_iter_test() {
+    let mut map: MultiMap<u32, u32> = (0..30).map(|i| (i % 3, i)).collect();
+    assert_eq!(map.len(), 30);
+    assert_eq!(map.key_count(), 3);
+    assert_eq!(map.get_all(&1), (1..30).step_by(3).collect::<Vec<_>>().as_slice());
+    assert_eq!(map.iter().count(), 30);
+    assert!(map.iter_all().all(|(key, values)| values.len() == 10 && values.iter().all(|value| value % 3 == *key)));
+
+    map.resize(2);
+    assert_eq!(map.bucket_count(), 2);
+    assert_eq!(map.get_all(&2), (2..30).step_by(3).collect::<Vec<_>>().as_slice());
+    let mut keys: Vec<u32> = map.keys().copied().collect();
+    keys.sort();
+    assert_eq!(keys, vec![0, 1, 2]);
+    map.clear();
+    assert!(map.is_empty());
+}
@@ -0,0 +1,211 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::hash::mod_hash::write_integers;
+
+/// the final mix of the 32 bit variant, which makes every bit of the input affect every bit of the hash
+fn fmix32(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+/// reads up to 8 bytes as a little endian number, missing high bytes are zero
+fn read_le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u64)
+}
+
+/// This function returns the MurmurHash3_x86_32 hash of the bytes
+pub fn murmur3_32(bytes: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let scramble = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut h = seed;
+    let blocks = bytes.chunks_exact(4);
+    let tail = blocks.remainder();
+    for block in blocks {
+        h ^= scramble(read_le(block) as u32);
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    if !tail.is_empty() {
+        h ^= scramble(read_le(tail) as u32);
+    }
+    fmix32(h ^ bytes.len() as u32)
+}
+
+/// This function returns the MurmurHash3_x64_128 hash of the bytes as its two 64 bit halves
+///
+/// The reference implementation writes the first half before the second one, each in little endian order.
+pub fn murmur3_128(bytes: &[u8], seed: u32) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+    let scramble1 = |k: u64| k.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    let scramble2 = |k: u64| k.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+
+    let mut h1 = seed as u64;
+    let mut h2 = seed as u64;
+    let blocks = bytes.chunks_exact(16);
+    let tail = blocks.remainder();
+    for block in blocks {
+        h1 ^= scramble1(read_le(&block[..8]));
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dc_e729);
+        h2 ^= scramble2(read_le(&block[8..]));
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+    if tail.len() > 8 {
+        h2 ^= scramble2(read_le(&tail[8..]));
+    }
+    if !tail.is_empty() {
+        h1 ^= scramble1(read_le(&tail[..tail.len().min(8)]));
+    }
+
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    (h1, h2)
+}
+
+/// A [Hasher] that collects the bytes written to it and hashes them with [murmur3_32]
+pub struct Murmur3Hasher32 {
+    bytes: Vec<u8>,
+    seed: u32,
+}
+
+/// A [Hasher] that collects the bytes written to it and returns the first half of [murmur3_128] of them
+pub struct Murmur3Hasher128 {
+    bytes: Vec<u8>,
+    seed: u32,
+}
+
+impl Murmur3Hasher32 {
+    pub fn new(seed: u32) -> Self {
+        Self { bytes: Vec::new(), seed }
+    }
+}
+
+impl Murmur3Hasher128 {
+    pub fn new(seed: u32) -> Self {
+        Self { bytes: Vec::new(), seed }
+    }
+}
+
+impl Hasher for Murmur3Hasher32 {
+    fn finish(&self) -> u64 {
+        murmur3_32(&self.bytes, self.seed) as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+impl Hasher for Murmur3Hasher128 {
+    fn finish(&self) -> u64 {
+        murmur3_128(&self.bytes, self.seed).0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+/// Creates [Murmur3Hasher32]s with a fixed seed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Murmur3HashBuilder32 {
+    seed: u32,
+}
+
+/// Creates [Murmur3Hasher128]s with a fixed seed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Murmur3HashBuilder128 {
+    seed: u32,
+}
+
+impl Murmur3HashBuilder32 {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl Murmur3HashBuilder128 {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for Murmur3HashBuilder32 {
+    type Hasher = Murmur3Hasher32;
+
+    fn build_hasher(&self) -> Murmur3Hasher32 {
+        Murmur3Hasher32::new(self.seed)
+    }
+}
+
+impl BuildHasher for Murmur3HashBuilder128 {
+    type Hasher = Murmur3Hasher128;
+
+    fn build_hasher(&self) -> Murmur3Hasher128 {
+        Murmur3Hasher128::new(self.seed)
+    }
+}
+
+#[test]
+pub fn murmur3_32_test() {
+    // test vectors of the reference implementation by Austin Appleby
+    assert_eq!(murmur3_32(b"", 0), 0);
+    assert_eq!(murmur3_32(b"", 1), 0x514e_28b7);
+    assert_eq!(murmur3_32(b"", 0xffff_ffff), 0x81f1_6f39);
+    assert_eq!(murmur3_32(b"test", 0), 0xba6b_d213);
+    assert_eq!(murmur3_32(b"abc", 0), 0xb3dd_93fa);
+    assert_eq!(murmur3_32(b"aaaa", 0x9747_b28c), 0x5a97_808a);
+    assert_eq!(murmur3_32(b"Hello, world!", 0), 0xc036_3e43);
+    assert_eq!(murmur3_32(b"The quick brown fox jumps over the lazy dog", 0), 0x2e4f_f723);
+}
+
+#[test]
+pub fn murmur3_128_test() {
+    assert_eq!(murmur3_128(b"", 0), (0, 0));
+    assert_eq!(murmur3_128(b"hello", 0), (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19));
+    assert_eq!(
+        murmur3_128(b"The quick brown fox jumps over the lazy dog", 0),
+        (0xe34b_bc7b_bc07_1b6c, 0x7a43_3ca9_c49a_9347)
+    );
+}
+
+#[test]
+pub fn murmur3_hasher_test() {
+    use crate::hash::to_bytes::ToBytes;
+
+    let mut hasher = Murmur3Hasher32::new(0);
+    hasher.write(b"Hello, ");
+    hasher.write(b"world!");
+    assert_eq!(hasher.finish(), 0xc036_3e43);
+    let builder = Murmur3HashBuilder128::new(7);
+    assert_eq!(builder.hash_one(0x1234u32), murmur3_128(&0x1234u32.to_bytes(), 7).0);
+    assert_ne!(builder.hash_one(1u8), Murmur3HashBuilder128::new(8).hash_one(1u8));
+}
@@ -0,0 +1,341 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::marker::PhantomData;
+
+use crate::hash::map::Map;
+use crate::hash::mod_hash::ModHashBuilder;
+
+/// the number of slots allocated by the first insertion
+const MIN_SLOTS: usize = 8;
+
+/// Decides which slot is probed next when the previous ones were taken
+pub trait ProbeSequence {
+    /// whether the sequence uses the step, which costs a second hash of the key
+    const USES_STEP: bool;
+
+    /// returns the slot probed after attempt unsuccessful probes, wrapped to the table by mask
+    /// step is an odd number derived from a second hash if the sequence uses it
+    fn slot(home: usize, step: usize, attempt: usize, mask: usize) -> usize;
+}
+
+/// Probes the slots following the home slot one by one
+pub struct LinearProbing;
+
+/// Probes slots at triangular number offsets from the home slot, which visits every slot of a power of two table
+pub struct QuadraticProbing;
+
+/// Probes slots at multiples of a step taken from a second hash, so colliding keys follow different sequences
+pub struct DoubleHashing;
+
+impl ProbeSequence for LinearProbing {
+    const USES_STEP: bool = false;
+
+    fn slot(home: usize, _step: usize, attempt: usize, mask: usize) -> usize {
+        home.wrapping_add(attempt) & mask
+    }
+}
+
+impl ProbeSequence for QuadraticProbing {
+    const USES_STEP: bool = false;
+
+    fn slot(home: usize, _step: usize, attempt: usize, mask: usize) -> usize {
+        home.wrapping_add(attempt * (attempt + 1) / 2) & mask
+    }
+}
+
+impl ProbeSequence for DoubleHashing {
+    const USES_STEP: bool = true;
+
+    fn slot(home: usize, step: usize, attempt: usize, mask: usize) -> usize {
+        home.wrapping_add(attempt.wrapping_mul(step)) & mask
+    }
+}
+
+enum Slot<K, V> {
+    Empty,
+    /// marks a removed entry, so that probe sequences passing through it are not cut short
+    Tombstone,
+    Occupied(K, V),
+}
+
+/// A hash map that stores its entries directly in an array of slots and resolves collisions by probing
+/// the slots in the order given by P
+///
+/// Removed entries leave tombstones behind. Once entries and tombstones fill 3/4 of the slots, the table
+/// is rebuilt without tombstones, and doubled in size if more than half of the slots hold entries.
+pub struct OpenAddressingMap<K, V, P: ProbeSequence, S = ModHashBuilder> {
+    slots: Vec<Slot<K, V>>,
+    len: usize,
+    tombstones: usize,
+    hash_builder: S,
+    probing: PhantomData<P>,
+}
+
+pub type LinearProbingMap<K, V, S = ModHashBuilder> = OpenAddressingMap<K, V, LinearProbing, S>;
+pub type QuadraticProbingMap<K, V, S = ModHashBuilder> = OpenAddressingMap<K, V, QuadraticProbing, S>;
+pub type DoubleHashingMap<K, V, S = ModHashBuilder> = OpenAddressingMap<K, V, DoubleHashing, S>;
+
+impl<K: Hash + Eq, V, P: ProbeSequence> OpenAddressingMap<K, V, P> {
+    pub fn new() -> Self {
+        Self::with_hasher(ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, P: ProbeSequence, S: BuildHasher> OpenAddressingMap<K, V, P, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+            tombstones: 0,
+            hash_builder,
+            probing: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of slots
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// returns the number of slots holding tombstones of removed entries
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// returns the home slot of the key and the step of its probe sequence
+    fn home_and_step<Q: Hash + ?Sized>(&self, key: &Q) -> (usize, usize) {
+        let home = self.hash_builder.hash_one(key) as usize;
+        if !P::USES_STEP {
+            return (home, 0);
+        }
+        let mut hasher = self.hash_builder.build_hasher();
+        // a prefix makes the second hash differ from the first one
+        hasher.write_u8(0x5a);
+        key.hash(&mut hasher);
+        (home, hasher.finish() as usize | 1)
+    }
+
+    /// returns the slot holding the key
+    fn find<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let (home, step) = self.home_and_step(key);
+        let mask = self.slots.len() - 1;
+        for attempt in 0..self.slots.len() {
+            let slot = P::slot(home, step, attempt, mask);
+            match &self.slots[slot] {
+                Slot::Empty => return None,
+                Slot::Occupied(slot_key, _) if slot_key.borrow() == key => return Some(slot),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// returns the first slot in the probe sequence of the key that is free or holds a tombstone
+    fn free_slot(&self, key: &K) -> usize {
+        let (home, step) = self.home_and_step(key);
+        let mask = self.slots.len() - 1;
+        (0..self.slots.len())
+            .map(|attempt| P::slot(home, step, attempt, mask))
+            .find(|&slot| !matches!(self.slots[slot], Slot::Occupied(..)))
+            .expect("the table to have a free slot")
+    }
+
+    /// rebuilds the table with the given number of slots, dropping all tombstones
+    fn rehash(&mut self, capacity: usize) {
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || Slot::Empty);
+        let old_slots = std::mem::replace(&mut self.slots, slots);
+        self.tombstones = 0;
+        for slot in old_slots {
+            if let Slot::Occupied(key, value) = slot {
+                let free = self.free_slot(&key);
+                self.slots[free] = Slot::Occupied(key, value);
+            }
+        }
+    }
+
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old_value) = self.get_mut(&key) {
+            return Some(std::mem::replace(old_value, value));
+        }
+
+        let capacity = self.slots.len();
+        if (self.len + self.tombstones + 1) * 4 > capacity * 3 {
+            if (self.len + 1) * 2 > capacity {
+                self.rehash((capacity * 2).max(MIN_SLOTS));
+            } else {
+                // tombstones take up at least a quarter of the table, clearing them makes enough room
+                self.rehash(capacity);
+            }
+        }
+
+        let slot = self.free_slot(&key);
+        if let Slot::Tombstone = self.slots[slot] {
+            self.tombstones -= 1;
+        }
+        self.slots[slot] = Slot::Occupied(key, value);
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        match &self.slots[self.find(key)?] {
+            Slot::Occupied(_, value) => Some(value),
+            _ => unreachable!("find to return an occupied slot"),
+        }
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        match &mut self.slots[slot] {
+            Slot::Occupied(_, value) => Some(value),
+            _ => unreachable!("find to return an occupied slot"),
+        }
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.find(key).is_some()
+    }
+
+    /// removes a key from the map, leaving a tombstone in its slot
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.len -= 1;
+        self.tombstones += 1;
+        match std::mem::replace(&mut self.slots[slot], Slot::Tombstone) {
+            Slot::Occupied(_, value) => Some(value),
+            _ => unreachable!("find to return an occupied slot"),
+        }
+    }
+
+    /// returns an iterator over the entries in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().filter_map(|slot| match slot {
+            Slot::Occupied(key, value) => Some((key, value)),
+            _ => None,
+        })
+    }
+}
+
+impl<K: Hash + Eq, V, P: ProbeSequence, S: BuildHasher + Default> Default for OpenAddressingMap<K, V, P, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, P: ProbeSequence, S: BuildHasher> Map<K, V> for OpenAddressingMap<K, V, P, S> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        OpenAddressingMap::put(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        OpenAddressingMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        OpenAddressingMap::get_mut(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        OpenAddressingMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        OpenAddressingMap::len(self)
+    }
+}
+
+#[test]
+pub fn open_addressing_test() {
+    fn check<P: ProbeSequence>() {
+        let mut map: OpenAddressingMap<String, usize, P> = OpenAddressingMap::new();
+        assert_eq!(map.get("a"), None);
+        for word in ["a", "bb", "ccc", "dddd"] {
+            assert_eq!(map.put(word.to_string(), word.len()), None);
+        }
+        assert_eq!(map.put("a".to_string(), 10), Some(1));
+        assert_eq!(map.get("a"), Some(&10));
+        assert_eq!(map.remove("bb"), Some(2));
+        assert_eq!(map.remove("bb"), None);
+        assert!(!map.contains_key("bb"));
+        assert!(map.contains_key("ccc"));
+        *map.get_mut("ccc").unwrap() += 1;
+        assert_eq!(map.get("ccc"), Some(&4));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.iter().count(), 3);
+    }
+    check::<LinearProbing>();
+    check::<QuadraticProbing>();
+    check::<DoubleHashing>();
+}
+
+#[test]
+pub fn open_addressing_deletion_test() {
+    use crate::hash::map::check_against_std;
+
+    check_against_std(LinearProbingMap::new(), 64, 20_000);
+    check_against_std(QuadraticProbingMap::new(), 64, 20_000);
+    check_against_std(DoubleHashingMap::new(), 64, 20_000);
+    check_against_std(LinearProbingMap::new(), 5_000, 20_000);
+    check_against_std(QuadraticProbingMap::new(), 5_000, 20_000);
+    check_against_std(DoubleHashingMap::new(), 5_000, 20_000);
+}
+
+#[test]
+pub fn tombstone_cleanup_test() {
+    let mut map = LinearProbingMap::new();
+    for i in 0..100 {
+        map.put(i, i);
+    }
+    let capacity = map.capacity();
+
+    // a long series of insertions and removals of fresh keys keeps the size constant,
+    // so the tombstones they leave behind must be cleared without growing the table
+    for i in 100..10_000 {
+        map.put(i, i);
+        assert_eq!(map.remove(&(i - 100)), Some(i - 100));
+        assert_eq!(map.len(), 100);
+        assert!((map.len() + map.tombstones()) * 4 <= map.capacity() * 3);
+    }
+    assert_eq!(map.capacity(), capacity);
+    assert!((9_900..10_000).all(|i| map.get(&i) == Some(&i)));
+
+    for i in 9_900..10_000 {
+        map.remove(&i);
+    }
+    assert!(map.is_empty());
+    let tombstones = map.tombstones();
+    assert!(tombstones >= 100);
+    // new entries take over the tombstones or clear them in a rehash instead of growing the table
+    for i in 0..100 {
+        map.put(i, i);
+    }
+    assert!(map.tombstones() < tombstones);
+    assert_eq!(map.capacity(), capacity);
+}
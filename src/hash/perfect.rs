@@ -0,0 +1,294 @@
+use std::fmt::{Display, Formatter};
+
+use crate::hash::fnv::fnv1a_64;
+use crate::hash::mod_hash::mix;
+use crate::hash::to_bytes::ToBytes;
+
+/// the first bytes of a serialized [MinimalPerfectHash]
+const MAGIC: &[u8; 4] = b"MPH1";
+
+#[derive(Debug, PartialEq)]
+pub enum PerfectHashError {
+    /// the same key was given twice, so no function can map the keys to distinct indices
+    DuplicateKey,
+    /// no seed up to the limit of the builder places all keys of a bucket in free slots
+    NoSeedFound,
+    /// the bytes passed to [MinimalPerfectHash::from_bytes] do not describe a function
+    InvalidData(String),
+}
+
+impl Display for PerfectHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PerfectHashError::DuplicateKey => write!(f, "the keys contain duplicates"),
+            PerfectHashError::NoSeedFound => write!(f, "no seed places all keys, try smaller buckets"),
+            PerfectHashError::InvalidData(message) => write!(f, "invalid perfect hash data: {}", message),
+        }
+    }
+}
+
+/// hashes the bytes of a key with 64 bit FNV-1a, every other hash is derived from this one
+fn key_hash<K: ToBytes + ?Sized>(key: &K) -> u64 {
+    fnv1a_64(&key.to_bytes())
+}
+
+/// returns the slot of a key with the given hash when its bucket uses seed
+fn slot(hash: u64, seed: u32, slot_count: usize) -> usize {
+    (mix(hash ^ mix(seed as u64 + 1)) % slot_count as u64) as usize
+}
+
+/// Configures how a [MinimalPerfectHash] is built
+///
+/// The keys are split into buckets, and for the largest buckets first a seed is searched that moves
+/// all keys of the bucket to slots that are still free. Larger buckets need fewer seeds to be stored
+/// but make the search slower.
+pub struct PerfectHashBuilder {
+    average_bucket_size: usize,
+    max_seed: u32,
+}
+
+impl PerfectHashBuilder {
+    /// creates a builder for buckets of 4 keys on average that tries up to 2^20 seeds per bucket
+    pub fn new() -> Self {
+        Self {
+            average_bucket_size: 4,
+            max_seed: 1 << 20,
+        }
+    }
+
+    /// sets the average number of keys per bucket, above 5 the last buckets often find no seed
+    pub fn average_bucket_size(mut self, average_bucket_size: usize) -> Self {
+        assert!(average_bucket_size > 0, "buckets must hold at least one key on average");
+        self.average_bucket_size = average_bucket_size;
+        self
+    }
+
+    pub fn max_seed(mut self, max_seed: u32) -> Self {
+        self.max_seed = max_seed;
+        self
+    }
+
+    /// builds a function that maps each of the keys to a distinct index below keys.len()
+    pub fn build<K: ToBytes>(&self, keys: &[K]) -> Result<MinimalPerfectHash, PerfectHashError> {
+        let hashes: Vec<u64> = keys.iter().map(key_hash).collect();
+        let mut sorted = hashes.clone();
+        sorted.sort_unstable();
+        // two distinct keys with the same 64 bit hash are too unlikely to tell apart from duplicates
+        if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(PerfectHashError::DuplicateKey);
+        }
+
+        let slot_count = keys.len();
+        let bucket_count = slot_count.div_ceil(self.average_bucket_size).max(1);
+        let mut buckets = vec![Vec::new(); bucket_count];
+        for &hash in &hashes {
+            buckets[(mix(hash) % bucket_count as u64) as usize].push(hash);
+        }
+        let mut order: Vec<usize> = (0..bucket_count).collect();
+        order.sort_by_key(|&bucket| std::cmp::Reverse(buckets[bucket].len()));
+
+        let mut seeds = vec![0; bucket_count];
+        let mut taken = vec![false; slot_count];
+        let mut slots = Vec::new();
+        for bucket in order {
+            if buckets[bucket].is_empty() {
+                break;
+            }
+            let seed = (0..self.max_seed).find(|&seed| {
+                slots.clear();
+                for &hash in &buckets[bucket] {
+                    let slot = slot(hash, seed, slot_count);
+                    if taken[slot] || slots.contains(&slot) {
+                        return false;
+                    }
+                    slots.push(slot);
+                }
+                true
+            }).ok_or(PerfectHashError::NoSeedFound)?;
+            for &slot in &slots {
+                taken[slot] = true;
+            }
+            seeds[bucket] = seed;
+        }
+
+        Ok(MinimalPerfectHash { slot_count, seeds })
+    }
+}
+
+impl Default for PerfectHashBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A function that maps each key of a fixed set to a distinct index from 0 to the number of keys
+///
+/// Computing an index takes one hash of the key and one seed lookup. Keys outside of the set
+/// are mapped to arbitrary indices, so a map built on it has to compare the key it finds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinimalPerfectHash {
+    slot_count: usize,
+    seeds: Vec<u32>,
+}
+
+impl MinimalPerfectHash {
+    /// returns the number of keys the function was built for
+    pub fn len(&self) -> usize {
+        self.slot_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slot_count == 0
+    }
+
+    /// returns the index of the key, panics if the function was built for no keys
+    pub fn index<K: ToBytes + ?Sized>(&self, key: &K) -> usize {
+        assert!(self.slot_count > 0, "a function for no keys has no indices");
+        let hash = key_hash(key);
+        let bucket = (mix(hash) % self.seeds.len() as u64) as usize;
+        slot(hash, self.seeds[bucket], self.slot_count)
+    }
+
+    /// serializes the function as the magic bytes MPH1, the number of keys and of buckets as
+    /// big endian u64 and the seed of each bucket as big endian u32
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(20 + 4 * self.seeds.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend((self.slot_count as u64).to_be_bytes());
+        bytes.extend((self.seeds.len() as u64).to_be_bytes());
+        for seed in &self.seeds {
+            bytes.extend(seed.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// loads a function serialized by [MinimalPerfectHash::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PerfectHashError> {
+        let invalid = |message: &str| PerfectHashError::InvalidData(message.to_string());
+        if bytes.len() < 20 || &bytes[..4] != MAGIC {
+            return Err(invalid("missing header"));
+        }
+        let slot_count = u64::from_be_bytes(bytes[4..12].try_into().expect("8 bytes"));
+        let bucket_count = u64::from_be_bytes(bytes[12..20].try_into().expect("8 bytes"));
+        if bucket_count == 0 {
+            return Err(invalid("no buckets"));
+        }
+        if bucket_count.checked_mul(4) != Some(bytes.len() as u64 - 20) {
+            return Err(invalid("the number of seeds does not match the number of buckets"));
+        }
+        let seeds = bytes[20..].chunks_exact(4).map(|seed| u32::from_be_bytes(seed.try_into().expect("4 bytes"))).collect();
+        Ok(Self {
+            slot_count: slot_count as usize,
+            seeds,
+        })
+    }
+}
+
+/// A read only map over a fixed set of keys where every lookup touches exactly one slot
+pub struct PerfectHashMap<K, V> {
+    function: MinimalPerfectHash,
+    slots: Vec<(K, V)>,
+}
+
+impl<K: ToBytes + Eq, V> PerfectHashMap<K, V> {
+    /// builds the map with the default [PerfectHashBuilder]
+    pub fn new(pairs: Vec<(K, V)>) -> Result<Self, PerfectHashError> {
+        Self::with_builder(pairs, &PerfectHashBuilder::new())
+    }
+
+    pub fn with_builder(pairs: Vec<(K, V)>, builder: &PerfectHashBuilder) -> Result<Self, PerfectHashError> {
+        let keys: Vec<&K> = pairs.iter().map(|(key, _)| key).collect();
+        let function = builder.build(&keys)?;
+        let mut slots: Vec<Option<(K, V)>> = (0..pairs.len()).map(|_| None).collect();
+        for (key, value) in pairs {
+            let index = function.index(&key);
+            slots[index] = Some((key, value));
+        }
+        let slots = slots.into_iter().map(|slot| slot.expect("a perfect hash to fill every slot")).collect();
+        Ok(Self { function, slots })
+    }
+
+    pub fn function(&self) -> &MinimalPerfectHash {
+        &self.function
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let (slot_key, value) = &self.slots[self.function.index(key)];
+        if slot_key == key { Some(value) } else { None }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// returns an iterator over the entries in index order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[test]
+pub fn minimal_perfect_hash_test() {
+    for average_bucket_size in [1, 3, 5] {
+        let keys: Vec<u32> = (0..10_000).map(|i| i * 7919).collect();
+        let function = PerfectHashBuilder::new().average_bucket_size(average_bucket_size).build(&keys).unwrap();
+        assert_eq!(function.len(), keys.len());
+        let mut seen = vec![false; keys.len()];
+        for key in &keys {
+            let index = function.index(key);
+            assert!(!seen[index], "two keys map to index {}", index);
+            seen[index] = true;
+        }
+    }
+
+    let words = ["apple", "banana", "cherry", "date", "elderberry", "fig", "grape"];
+    let function = PerfectHashBuilder::new().build(&words).unwrap();
+    let mut indices: Vec<usize> = words.iter().map(|word| function.index(word)).collect();
+    indices.sort();
+    assert_eq!(indices, (0..words.len()).collect::<Vec<_>>());
+
+    assert_eq!(PerfectHashBuilder::new().build(&[1, 2, 1]), Err(PerfectHashError::DuplicateKey));
+    assert!(PerfectHashBuilder::new().build::<u8>(&[]).unwrap().is_empty());
+}
+
+#[test]
+pub fn minimal_perfect_hash_serialization_test() {
+    let keys: Vec<String> = (0..1000).map(|i| format!("key-{}", i)).collect();
+    let function = PerfectHashBuilder::new().build(&keys).unwrap();
+    let bytes = function.to_bytes();
+    assert_eq!(&bytes[..4], b"MPH1");
+    let loaded = MinimalPerfectHash::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, function);
+    assert!(keys.iter().all(|key| loaded.index(key) == function.index(key)));
+
+    assert!(matches!(MinimalPerfectHash::from_bytes(b"MPH"), Err(PerfectHashError::InvalidData(_))));
+    assert!(matches!(MinimalPerfectHash::from_bytes(&bytes[..bytes.len() - 1]), Err(PerfectHashError::InvalidData(_))));
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(MinimalPerfectHash::from_bytes(&wrong_magic), Err(PerfectHashError::InvalidData(_))));
+}
+
+#[test]
+pub fn perfect_hash_map_test() {
+    let map = PerfectHashMap::new((0..500).map(|i| (format!("key-{}", i), i)).collect()).unwrap();
+    assert_eq!(map.len(), 500);
+    assert!((0..500).all(|i| map.get(&format!("key-{}", i)) == Some(&i)));
+    assert_eq!(map.get(&"key-500".to_string()), None);
+    assert!(!map.contains_key(&"missing".to_string()));
+    assert_eq!(map.iter().count(), 500);
+
+    let empty: PerfectHashMap<u32, u32> = PerfectHashMap::new(Vec::new()).unwrap();
+    assert_eq!(empty.get(&1), None);
+    assert!(PerfectHashMap::new(vec![(1, 'a'), (1, 'b')]).is_err());
+}
@@ -0,0 +1,144 @@
+use std::hash::BuildHasher;
+
+use crate::hash::sharding::{hash_parts, Sharding};
+use crate::hash::to_bytes::ToBytes;
+use crate::hash::xxhash::XxHashBuilder;
+
+/// Maps keys to nodes by highest random weight: every node scores the key with a hash of both,
+/// and the node with the highest score is responsible for it
+///
+/// Removing a node only moves its own keys, each to the node with its second highest score, and a new
+/// node only takes the keys it scores highest. Unlike a [HashRing](crate::hash::consistent_hashing::HashRing)
+/// it needs no virtual nodes to spread keys evenly, but finding the node of a key takes time linear in the nodes.
+#[derive(Debug, Clone)]
+pub struct RendezvousHasher<N, S = XxHashBuilder> {
+    nodes: Vec<N>,
+    hash_builder: S,
+}
+
+impl<N: ToBytes + PartialEq> RendezvousHasher<N> {
+    pub fn new() -> Self {
+        Self::with_hasher(XxHashBuilder::default())
+    }
+}
+
+impl<N: ToBytes + PartialEq> Default for RendezvousHasher<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: ToBytes + PartialEq, S: BuildHasher> RendezvousHasher<N, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            nodes: Vec::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    /// adds a node, returns false if it is already present
+    pub fn add_node(&mut self, node: N) -> bool {
+        if self.nodes.contains(&node) {
+            return false;
+        }
+        self.nodes.push(node);
+        true
+    }
+
+    /// removes a node, returns false if it is not present
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        let Some(index) = self.nodes.iter().position(|candidate| candidate == node) else {
+            return false;
+        };
+        self.nodes.swap_remove(index);
+        true
+    }
+
+    /// returns the score of the node for the key bytes, the length of the node bytes separates
+    /// the two parts so that different splits of the same bytes score differently
+    fn score(&self, node: &N, key: &[u8]) -> u64 {
+        let node = node.to_bytes();
+        hash_parts(&self.hash_builder, &[&(node.len() as u64).to_be_bytes(), &node, key])
+    }
+
+    /// returns the node responsible for the key, or None if there are no nodes
+    pub fn node_for<K: ToBytes + ?Sized>(&self, key: &K) -> Option<&N> {
+        let key = key.to_bytes();
+        self.nodes.iter().max_by_key(|node| self.score(node, &key))
+    }
+
+    /// returns up to count nodes with the highest scores for the key, the highest first, for replicating it
+    pub fn nodes_for<K: ToBytes + ?Sized>(&self, key: &K, count: usize) -> Vec<&N> {
+        let key = key.to_bytes();
+        let mut scored: Vec<(u64, &N)> = self.nodes.iter().map(|node| (self.score(node, &key), node)).collect();
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        scored.into_iter().take(count).map(|(_, node)| node).collect()
+    }
+}
+
+impl<N: ToBytes + PartialEq, S: BuildHasher> Sharding<N> for RendezvousHasher<N, S> {
+    fn node_for<K: ToBytes + ?Sized>(&self, key: &K) -> Option<&N> {
+        RendezvousHasher::node_for(self, key)
+    }
+}
+
+#[test]
+pub fn rendezvous_test() {
+    let mut hasher = RendezvousHasher::new();
+    assert_eq!(hasher.node_for("key"), None);
+    for node in 0..8u32 {
+        assert!(hasher.add_node(node));
+    }
+    assert!(!hasher.add_node(0));
+
+    let mut load = [0; 8];
+    for key in 0..80_000u32 {
+        load[*hasher.node_for(&key).unwrap() as usize] += 1;
+    }
+    assert!(load.iter().all(|&count| (9_000..11_000).contains(&count)), "{:?}", load);
+
+    let replicas = hasher.nodes_for(&7u32, 3);
+    assert_eq!(replicas.len(), 3);
+    assert_eq!(replicas[0], hasher.node_for(&7u32).unwrap());
+    assert!(replicas[0] != replicas[1] && replicas[1] != replicas[2] && replicas[0] != replicas[2]);
+}
+
+#[test]
+pub fn rendezvous_moved_keys_test() {
+    use crate::hash::sharding::moved_keys;
+
+    let keys: Vec<u64> = (0..20_000).collect();
+    let mut hasher = RendezvousHasher::new();
+    for node in ["a", "b", "c", "d"] {
+        hasher.add_node(node);
+    }
+
+    let mut grown = hasher.clone();
+    grown.add_node("e");
+    let moves = moved_keys(&hasher, &grown, &keys);
+    assert!(moves.iter().all(|moved| moved.to == Some("e")));
+    assert!((3_500..4_500).contains(&moves.len()), "{} keys moved", moves.len());
+
+    let mut shrunk = grown.clone();
+    assert!(shrunk.remove_node(&"c"));
+    let moves = moved_keys(&grown, &shrunk, &keys);
+    // the keys of the removed node go to the node that scored them second highest
+    for moved in &moves {
+        assert_eq!(moved.from, Some("c"));
+        assert_eq!(moved.to, Some(*grown.nodes_for(moved.key, 2)[1]));
+    }
+    let on_c = keys.iter().filter(|key| grown.node_for(*key) == Some(&"c")).count();
+    assert_eq!(moves.len(), on_c);
+}
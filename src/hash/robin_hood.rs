@@ -0,0 +1,267 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+
+use crate::hash::map::Map;
+use crate::hash::mod_hash::ModHashBuilder;
+
+/// the number of slots allocated by the first insertion
+const MIN_SLOTS: usize = 8;
+
+struct Bucket<K, V> {
+    key: K,
+    value: V,
+    /// the number of slots between the home slot of the key and the slot it is stored in
+    distance: usize,
+}
+
+/// An open addressing hash map with linear probing that keeps probe sequences short by letting
+/// an inserted entry take the slot of an entry that is closer to its home slot
+///
+/// Because entries are ordered by their distance from home, a lookup can stop as soon as it meets an entry
+/// closer to its home than the key would be. Removal shifts the following entries back by one slot instead
+/// of leaving tombstones. The table doubles once entries fill 7/8 of the slots.
+pub struct RobinHoodMap<K, V, S = ModHashBuilder> {
+    slots: Vec<Option<Bucket<K, V>>>,
+    len: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> RobinHoodMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> RobinHoodMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of slots
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// returns the longest distance of an entry from its home slot
+    pub fn max_distance(&self) -> usize {
+        self.slots.iter().flatten().map(|bucket| bucket.distance).max().unwrap_or(0)
+    }
+
+    fn home<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hash_builder.hash_one(key) as usize & (self.slots.len() - 1)
+    }
+
+    fn find<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let mask = self.slots.len() - 1;
+        let mut slot = self.home(key);
+        for distance in 0..self.slots.len() {
+            match &self.slots[slot] {
+                // the key would have displaced this entry, so it cannot be further along
+                Some(bucket) if bucket.distance < distance => return None,
+                Some(bucket) if bucket.key.borrow() == key => return Some(slot),
+                Some(_) => slot = (slot + 1) & mask,
+                None => return None,
+            }
+        }
+        None
+    }
+
+    /// places an entry whose key is not in the map, swapping it with richer entries along the way
+    fn insert_new(&mut self, key: K, value: V) {
+        let mask = self.slots.len() - 1;
+        let mut slot = self.home(&key);
+        let mut bucket = Bucket { key, value, distance: 0 };
+        loop {
+            match &mut self.slots[slot] {
+                None => {
+                    self.slots[slot] = Some(bucket);
+                    return;
+                }
+                Some(resident) => {
+                    if resident.distance < bucket.distance {
+                        std::mem::swap(resident, &mut bucket);
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+            bucket.distance += 1;
+        }
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || None);
+        let old_slots = std::mem::replace(&mut self.slots, slots);
+        for bucket in old_slots.into_iter().flatten() {
+            self.insert_new(bucket.key, bucket.value);
+        }
+    }
+
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old_value) = self.get_mut(&key) {
+            return Some(std::mem::replace(old_value, value));
+        }
+        if (self.len + 1) * 8 > self.slots.len() * 7 {
+            self.resize((self.slots.len() * 2).max(MIN_SLOTS));
+        }
+        self.insert_new(key, value);
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_ref().map(|bucket| &bucket.value)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_mut().map(|bucket| &mut bucket.value)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.find(key).is_some()
+    }
+
+    /// removes a key from the map and shifts the entries after it back towards their home slots
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let mut slot = self.find(key)?;
+        let mask = self.slots.len() - 1;
+        let removed = self.slots[slot].take().expect("find to return an occupied slot");
+        self.len -= 1;
+
+        let mut next = (slot + 1) & mask;
+        while self.slots[next].as_ref().is_some_and(|bucket| bucket.distance > 0) {
+            let mut bucket = self.slots[next].take().expect("slot to be occupied");
+            bucket.distance -= 1;
+            self.slots[slot] = Some(bucket);
+            slot = next;
+            next = (next + 1) & mask;
+        }
+        Some(removed.value)
+    }
+
+    /// returns an iterator over the entries in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|bucket| (&bucket.key, &bucket.value))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> Default for RobinHoodMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Map<K, V> for RobinHoodMap<K, V, S> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        RobinHoodMap::put(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        RobinHoodMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        RobinHoodMap::get_mut(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        RobinHoodMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        RobinHoodMap::len(self)
+    }
+}
+
+/// checks that every entry records its distance from home and that no entry is further from home
+/// than its predecessor allows, which is what lets lookups stop early
+#[cfg(test)]
+fn check_invariants<K: Hash + Eq, V, S: BuildHasher>(map: &RobinHoodMap<K, V, S>) {
+    let mask = map.slots.len().wrapping_sub(1);
+    for (slot, bucket) in map.slots.iter().enumerate() {
+        if let Some(bucket) = bucket {
+            assert_eq!((map.home(&bucket.key) + bucket.distance) & mask, slot, "distance of the entry in slot {}", slot);
+            if bucket.distance > 0 {
+                let previous = map.slots[(slot + mask) & mask].as_ref().expect("the slot before a displaced entry to be taken");
+                assert!(previous.distance + 1 >= bucket.distance, "entry in slot {} is poorer than its predecessor allows", slot);
+            }
+        }
+    }
+    assert_eq!(map.slots.iter().flatten().count(), map.len);
+}
+
+#[test]
+pub fn robin_hood_test() {
+    let mut map = RobinHoodMap::new();
+    assert_eq!(map.get("a"), None);
+    assert_eq!(map.remove("a"), None);
+    for word in ["a", "bb", "ccc", "dddd"] {
+        assert_eq!(map.put(word.to_string(), word.len()), None);
+    }
+    assert_eq!(map.put("a".to_string(), 10), Some(1));
+    assert_eq!(map.get("a"), Some(&10));
+    assert_eq!(map.remove("bb"), Some(2));
+    assert!(!map.contains_key("bb"));
+    *map.get_mut("ccc").unwrap() += 1;
+    assert_eq!(map.get("ccc"), Some(&4));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.iter().count(), 3);
+    check_invariants(&map);
+}
+
+#[test]
+pub fn robin_hood_deletion_test() {
+    use crate::hash::map::check_against_std;
+
+    let map = check_against_std(RobinHoodMap::new(), 64, 20_000);
+    check_invariants(&map);
+    let map = check_against_std(RobinHoodMap::new(), 5_000, 20_000);
+    check_invariants(&map);
+
+    let mut map = RobinHoodMap::new();
+    for i in 0..1000u32 {
+        map.put(i, i);
+    }
+    for i in (0..1000).filter(|i| i % 3 != 0) {
+        assert_eq!(map.remove(&i), Some(i));
+        if i % 50 == 0 {
+            check_invariants(&map);
+        }
+    }
+    check_invariants(&map);
+    // backward shifting leaves every entry as close to home as before, there is nothing to clean up
+    assert!(map.max_distance() <= 8);
+    assert!((0..1000).all(|i| map.contains_key(&i) == (i % 3 == 0)));
+}
@@ -0,0 +1,268 @@
+use std::ops::Range;
+
+/// the base of the polynomial, larger than every byte so that each byte is one digit
+pub const DEFAULT_BASE: u64 = 257;
+/// the modulus of [RollingHash] and [PrefixHashes] unless another one is given
+pub const FIRST_MODULUS: u64 = 1_000_000_007;
+/// the modulus of the second hash of [DoubleRollingHash] and [DoublePrefixHashes]
+pub const SECOND_MODULUS: u64 = 998_244_353;
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+/// This function returns the bytes read as the digits of a number in the given base, mod modulus
+///
+/// The first byte is the most significant digit, so for base 256 this is the same as [mod_hash](crate::hash::mod_hash::mod_hash).
+pub fn polynomial_hash(bytes: &[u8], base: u64, modulus: u64) -> u64 {
+    bytes.iter().fold(0, |hash, &byte| (mul_mod(hash, base, modulus) + byte as u64) % modulus)
+}
+
+/// returns base^exponent mod modulus
+fn pow_mod(base: u64, exponent: usize, modulus: u64) -> u64 {
+    (0..exponent).fold(1 % modulus, |power, _| mul_mod(power, base, modulus))
+}
+
+/// The polynomial hash of a window of fixed length that slides over a text one byte at a time
+///
+/// Each slide removes the leading digit of the window and appends a new one, which takes constant time
+/// regardless of the length of the window. This is the hash used by the Rabin-Karp string search.
+#[derive(Debug, Clone)]
+pub struct RollingHash {
+    base: u64,
+    modulus: u64,
+    hash: u64,
+    window: usize,
+    /// the weight of the first byte of the window, base^(window - 1) mod modulus
+    leading_weight: u64,
+}
+
+impl RollingHash {
+    /// creates the hash of the first window of a text with [DEFAULT_BASE] and [FIRST_MODULUS]
+    pub fn new(window: &[u8]) -> Self {
+        Self::with_parameters(window, DEFAULT_BASE, FIRST_MODULUS)
+    }
+
+    /// creates the hash of the first window of a text, the modulus must be below 2^63
+    pub fn with_parameters(window: &[u8], base: u64, modulus: u64) -> Self {
+        assert!(modulus > 0 && modulus < 1 << 63, "modulus must be in 1..2^63");
+        Self {
+            base,
+            modulus,
+            hash: polynomial_hash(window, base, modulus),
+            window: window.len(),
+            leading_weight: pow_mod(base, window.len().saturating_sub(1), modulus),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// returns the number of bytes in the window
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// moves the window one byte further, outgoing is the first byte of the old window
+    /// and incoming the byte that follows its last one
+    pub fn slide(&mut self, outgoing: u8, incoming: u8) {
+        if self.window == 0 {
+            return;
+        }
+        let outgoing = mul_mod(outgoing as u64, self.leading_weight, self.modulus);
+        let remaining = (self.hash + self.modulus - outgoing) % self.modulus;
+        self.hash = (mul_mod(remaining, self.base, self.modulus) + incoming as u64) % self.modulus;
+    }
+}
+
+/// Two [RollingHash]es of the same window with different moduli
+///
+/// Two windows only collide if both hashes collide, which with the default moduli happens
+/// with a chance of about 1 in 10^18 instead of 1 in 10^9.
+#[derive(Debug, Clone)]
+pub struct DoubleRollingHash {
+    first: RollingHash,
+    second: RollingHash,
+}
+
+impl DoubleRollingHash {
+    /// creates the hashes of the first window with [FIRST_MODULUS] and [SECOND_MODULUS]
+    pub fn new(window: &[u8]) -> Self {
+        Self {
+            first: RollingHash::with_parameters(window, DEFAULT_BASE, FIRST_MODULUS),
+            second: RollingHash::with_parameters(window, DEFAULT_BASE, SECOND_MODULUS),
+        }
+    }
+
+    pub fn hash(&self) -> (u64, u64) {
+        (self.first.hash(), self.second.hash())
+    }
+
+    pub fn window(&self) -> usize {
+        self.first.window()
+    }
+
+    pub fn slide(&mut self, outgoing: u8, incoming: u8) {
+        self.first.slide(outgoing, incoming);
+        self.second.slide(outgoing, incoming);
+    }
+}
+
+/// The polynomial hashes of all prefixes of a text, which give the hash of any substring in constant time
+///
+/// The hash of bytes i..j is the hash of the prefix of length j minus the hash of the prefix of length i
+/// shifted by j - i digits. Building the table takes linear time and memory.
+#[derive(Debug, Clone)]
+pub struct PrefixHashes {
+    modulus: u64,
+    /// prefixes[i] is the hash of the first i bytes
+    prefixes: Vec<u64>,
+    /// powers[i] is base^i mod modulus
+    powers: Vec<u64>,
+}
+
+impl PrefixHashes {
+    /// hashes the prefixes with [DEFAULT_BASE] and [FIRST_MODULUS]
+    pub fn new(text: &[u8]) -> Self {
+        Self::with_parameters(text, DEFAULT_BASE, FIRST_MODULUS)
+    }
+
+    /// hashes the prefixes with the given parameters, the modulus must be below 2^63
+    pub fn with_parameters(text: &[u8], base: u64, modulus: u64) -> Self {
+        assert!(modulus > 0 && modulus < 1 << 63, "modulus must be in 1..2^63");
+        let mut prefixes = Vec::with_capacity(text.len() + 1);
+        let mut powers = Vec::with_capacity(text.len() + 1);
+        prefixes.push(0);
+        powers.push(1 % modulus);
+        for (i, &byte) in text.iter().enumerate() {
+            prefixes.push((mul_mod(prefixes[i], base, modulus) + byte as u64) % modulus);
+            powers.push(mul_mod(powers[i], base, modulus));
+        }
+        Self { modulus, prefixes, powers }
+    }
+
+    /// returns the length of the text
+    pub fn len(&self) -> usize {
+        self.prefixes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// returns the hash of the bytes in range, the same as [polynomial_hash] of them
+    pub fn substring(&self, range: Range<usize>) -> u64 {
+        assert!(range.start <= range.end && range.end <= self.len(), "range {:?} is out of bounds", range);
+        let shifted = mul_mod(self.prefixes[range.start], self.powers[range.end - range.start], self.modulus);
+        (self.prefixes[range.end] + self.modulus - shifted) % self.modulus
+    }
+}
+
+/// Prefix hashes of a text for both moduli of [DoubleRollingHash]
+#[derive(Debug, Clone)]
+pub struct DoublePrefixHashes {
+    first: PrefixHashes,
+    second: PrefixHashes,
+}
+
+impl DoublePrefixHashes {
+    pub fn new(text: &[u8]) -> Self {
+        Self {
+            first: PrefixHashes::with_parameters(text, DEFAULT_BASE, FIRST_MODULUS),
+            second: PrefixHashes::with_parameters(text, DEFAULT_BASE, SECOND_MODULUS),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.first.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_empty()
+    }
+
+    /// returns the hashes of the bytes in range, they equal [DoubleRollingHash::hash] of a window over them
+    pub fn substring(&self, range: Range<usize>) -> (u64, u64) {
+        (self.first.substring(range.clone()), self.second.substring(range))
+    }
+}
+
+/// Returns the positions of all occurrences of pattern in text, found with the Rabin-Karp algorithm
+///
+/// Windows whose hash matches the hash of the pattern are compared byte by byte,
+/// so collisions cost time but never produce false matches.
+pub fn rabin_karp_find_all(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    if pattern.len() > text.len() {
+        return Vec::new();
+    }
+    let target = DoubleRollingHash::new(pattern).hash();
+    let mut window = DoubleRollingHash::new(&text[..pattern.len()]);
+    let mut positions = Vec::new();
+    for start in 0..=text.len() - pattern.len() {
+        if start > 0 {
+            window.slide(text[start - 1], text[start + pattern.len() - 1]);
+        }
+        if window.hash() == target && &text[start..start + pattern.len()] == pattern {
+            positions.push(start);
+        }
+    }
+    positions
+}
+
+#[test]
+pub fn rolling_hash_test() {
+    use crate::hash::mod_hash::mod_hash;
+
+    let text = b"the quick brown fox jumps over the lazy dog";
+    for window in [0, 1, 5, 17] {
+        let mut rolling = RollingHash::new(&text[..window]);
+        let mut double = DoubleRollingHash::new(&text[..window]);
+        assert_eq!(rolling.window(), window);
+        for start in 0..text.len() - window {
+            assert_eq!(rolling.hash(), polynomial_hash(&text[start..start + window], DEFAULT_BASE, FIRST_MODULUS));
+            assert_eq!(double.hash().1, polynomial_hash(&text[start..start + window], DEFAULT_BASE, SECOND_MODULUS));
+            rolling.slide(text[start], text[start + window]);
+            double.slide(text[start], text[start + window]);
+        }
+    }
+
+    // in base 256 the polynomial hash is the number mod_hash reads from the bytes
+    let mut rolling = RollingHash::with_parameters(&text[..4], 256, 97);
+    rolling.slide(text[0], text[4]);
+    assert_eq!(rolling.hash(), mod_hash(text[1..5].to_vec(), 97) as u64);
+
+    // products of large residues must not overflow
+    let modulus = (1 << 63) - 25;
+    let mut rolling = RollingHash::with_parameters(&[0xff; 8], 1 << 40, modulus);
+    rolling.slide(0xff, 0x01);
+    assert_eq!(rolling.hash(), polynomial_hash(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01], 1 << 40, modulus));
+}
+
+#[test]
+pub fn prefix_hashes_test() {
+    let text = b"abracadabra abracadabra";
+    let prefixes = PrefixHashes::new(text);
+    let double = DoublePrefixHashes::new(text);
+    assert_eq!(prefixes.len(), text.len());
+    for start in 0..=text.len() {
+        for end in start..=text.len() {
+            assert_eq!(prefixes.substring(start..end), polynomial_hash(&text[start..end], DEFAULT_BASE, FIRST_MODULUS));
+            assert_eq!(double.substring(start..end), DoubleRollingHash::new(&text[start..end]).hash());
+        }
+    }
+    assert_eq!(double.substring(0..4), double.substring(7..11));
+    assert_eq!(double.substring(0..11), double.substring(12..23));
+    assert_ne!(double.substring(0..5), double.substring(1..6));
+    assert!(PrefixHashes::new(b"").is_empty());
+}
+
+#[test]
+pub fn rabin_karp_test() {
+    assert_eq!(rabin_karp_find_all(b"abracadabra", b"abra"), vec![0, 7]);
+    assert_eq!(rabin_karp_find_all(b"aaaa", b"aa"), vec![0, 1, 2]);
+    assert_eq!(rabin_karp_find_all(b"the quick brown fox jumps over the lazy dog", b"laz"), vec![35]);
+    assert_eq!(rabin_karp_find_all(b"foobar", b"baz"), Vec::<usize>::new());
+    assert_eq!(rabin_karp_find_all(b"ab", b"abc"), Vec::<usize>::new());
+    assert_eq!(rabin_karp_find_all(b"ab", b""), vec![0, 1, 2]);
+}
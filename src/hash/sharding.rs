@@ -0,0 +1,45 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::hash::mod_hash::mix;
+use crate::hash::to_bytes::ToBytes;
+
+/// Assigns keys to one of a changing set of nodes
+pub trait Sharding<N> {
+    /// returns the node responsible for the key, or None if there are no nodes
+    fn node_for<K: ToBytes + ?Sized>(&self, key: &K) -> Option<&N>;
+}
+
+/// A key that is assigned to a different node after the set of nodes changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move<'a, K, N> {
+    pub key: &'a K,
+    pub from: Option<N>,
+    pub to: Option<N>,
+}
+
+/// returns the keys that before and after assign to different nodes
+///
+/// To find the keys that move when a node is added or removed, clone the sharding, change the clone
+/// and compare it with the original.
+pub fn moved_keys<'a, K: ToBytes, N: Clone + PartialEq, S: Sharding<N>>(before: &S, after: &S, keys: &'a [K]) -> Vec<Move<'a, K, N>> {
+    keys.iter()
+        .filter_map(|key| {
+            let (from, to) = (before.node_for(key), after.node_for(key));
+            (from != to).then(|| Move {
+                key,
+                from: from.cloned(),
+                to: to.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// hashes the concatenation of the parts with a hasher of hash_builder and spreads the result over all bits,
+/// so that hash functions with small outputs such as [mod_hash](crate::hash::mod_hash::mod_hash) can be used
+pub(crate) fn hash_parts<S: BuildHasher>(hash_builder: &S, parts: &[&[u8]]) -> u64 {
+    let mut hasher = hash_builder.build_hasher();
+    for part in parts {
+        hasher.write(part);
+    }
+    mix(hasher.finish())
+}
@@ -0,0 +1,149 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::hash::mod_hash::write_integers;
+
+struct State {
+    v: [u64; 4],
+}
+
+impl State {
+    fn new(k0: u64, k1: u64) -> Self {
+        Self {
+            v: [k0 ^ 0x736f_6d65_7073_6575, k1 ^ 0x646f_7261_6e64_6f6d, k0 ^ 0x6c79_6765_6e65_7261, k1 ^ 0x7465_6462_7974_6573],
+        }
+    }
+
+    fn round(&mut self) {
+        let [v0, v1, v2, v3] = &mut self.v;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    /// mixes one 8 byte word of the message in with 2 compression rounds
+    fn compress(&mut self, word: u64) {
+        self.v[3] ^= word;
+        self.round();
+        self.round();
+        self.v[0] ^= word;
+    }
+
+    /// runs the 4 finalization rounds
+    fn finish(mut self) -> u64 {
+        self.v[2] ^= 0xff;
+        for _ in 0..4 {
+            self.round();
+        }
+        self.v.iter().fold(0, |hash, v| hash ^ v)
+    }
+}
+
+/// This function returns the SipHash-2-4 of the bytes under the 128 bit key k0, k1
+///
+/// SipHash is a keyed pseudorandom function: without the key, an attacker cannot construct keys that
+/// collide in a map, which makes it the safe choice for maps filled with untrusted input as long as the key
+/// is secret and chosen at random. The key bytes of the reference implementation are read as two little endian words.
+pub fn siphash24(bytes: &[u8], k0: u64, k1: u64) -> u64 {
+    let mut state = State::new(k0, k1);
+    let words = bytes.chunks_exact(8);
+    let tail = words.remainder();
+    for word in words {
+        state.compress(u64::from_le_bytes(word.try_into().expect("8 bytes")));
+    }
+    // the last word holds the remaining bytes and the length of the message in its top byte
+    let last = tail.iter().rev().fold(0, |word, &byte| word << 8 | byte as u64);
+    state.compress(last | (bytes.len() as u64) << 56);
+    state.finish()
+}
+
+/// A [Hasher] that collects the bytes written to it and hashes them with [siphash24]
+pub struct SipHasher24 {
+    bytes: Vec<u8>,
+    k0: u64,
+    k1: u64,
+}
+
+impl SipHasher24 {
+    pub fn new(k0: u64, k1: u64) -> Self {
+        Self { bytes: Vec::new(), k0, k1 }
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn finish(&self) -> u64 {
+        siphash24(&self.bytes, self.k0, self.k1)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+/// Creates [SipHasher24]s with a fixed key
+///
+/// The key of [SipHashBuilder::default] is zero and known to everyone, so maps using it are not protected
+/// against hash flooding. Maps filled with untrusted input need a builder from [SipHashBuilder::new] with a
+/// secret random key.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SipHashBuilder {
+    k0: u64,
+    k1: u64,
+}
+
+impl SipHashBuilder {
+    pub fn new(k0: u64, k1: u64) -> Self {
+        Self { k0, k1 }
+    }
+}
+
+impl BuildHasher for SipHashBuilder {
+    type Hasher = SipHasher24;
+
+    fn build_hasher(&self) -> SipHasher24 {
+        SipHasher24::new(self.k0, self.k1)
+    }
+}
+
+#[test]
+pub fn siphash24_test() {
+    // the vectors of the reference implementation hash the messages 00, 00 01, 00 01 02, ...
+    // under the key 00 01 .. 0f
+    let (k0, k1) = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+    let message: Vec<u8> = (0..64).collect();
+    assert_eq!(siphash24(&message[..0], k0, k1), 0x726f_db47_dd0e_0e31);
+    assert_eq!(siphash24(&message[..1], k0, k1), 0x74f8_39c5_93dc_67fd);
+    assert_eq!(siphash24(&message[..15], k0, k1), 0xa129_ca61_49be_45e5);
+    assert_eq!(siphash24(&message[..63], k0, k1), 0x958a_324c_eb06_4572);
+
+    // std still ships the SipHash-2-4 it used before switching to SipHash-1-3
+    #[allow(deprecated)]
+    for length in 0..64 {
+        let mut std_hasher = std::hash::SipHasher::new_with_keys(k0, k1);
+        std_hasher.write(&message[..length]);
+        assert_eq!(siphash24(&message[..length], k0, k1), std_hasher.finish());
+    }
+}
+
+#[test]
+pub fn siphash_hasher_test() {
+    use crate::hash::to_bytes::ToBytes;
+
+    let mut hasher = SipHasher24::new(1, 2);
+    hasher.write(b"hello ");
+    hasher.write(b"world");
+    assert_eq!(hasher.finish(), siphash24(b"hello world", 1, 2));
+    assert_eq!(SipHashBuilder::new(1, 2).hash_one(7u16), siphash24(&7u16.to_bytes(), 1, 2));
+    assert_ne!(SipHashBuilder::new(1, 2).hash_one(7u16), SipHashBuilder::new(2, 1).hash_one(7u16));
+}
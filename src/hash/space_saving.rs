@@ -0,0 +1,152 @@
+use std::hash::Hash;
+
+use crate::hash::hashmap::HashMap;
+
+/// A monitored key with its counted occurrences and the number of them that may belong to other keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counter<K> {
+    pub key: K,
+    pub count: u64,
+    /// the count of the key this counter was taken from, by which count may exceed the true count
+    pub error: u64,
+}
+
+/// Finds the most frequent keys of a stream while monitoring a fixed number of keys
+///
+/// A key that is not monitored takes over the counter with the smallest count, inheriting that count
+/// as its possible error. With c counters and n occurrences every count exceeds the true count by at most
+/// n / c, and every key that occurs more than n / c times is monitored. Finding the smallest counter
+/// scans all counters, so the structure is meant for a few hundred counters at most.
+pub struct SpaceSaving<K> {
+    counters: Vec<Counter<K>>,
+    positions: HashMap<K, usize>,
+    capacity: usize,
+    total: u64,
+}
+
+impl<K: Hash + Eq + Clone> SpaceSaving<K> {
+    /// creates a summary that monitors up to capacity keys
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "at least one key must be monitored");
+        Self {
+            counters: Vec::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
+            capacity,
+            total: 0,
+        }
+    }
+
+    /// creates a summary whose counts exceed the true counts by at most epsilon times the total count
+    pub fn with_error(epsilon: f64) -> Self {
+        assert!(epsilon > 0.0 && epsilon <= 1.0, "epsilon must be in (0, 1]");
+        Self::new((1.0 / epsilon).ceil() as usize)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// returns the sum of all counts added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// returns the bound total / capacity on the error of every count
+    pub fn error_bound(&self) -> u64 {
+        self.total / self.capacity as u64
+    }
+
+    /// records count more occurrences of the key
+    pub fn add(&mut self, key: K, count: u64) {
+        self.total += count;
+        if let Some(&position) = self.positions.get(&key) {
+            self.counters[position].count += count;
+            return;
+        }
+        if self.counters.len() < self.capacity {
+            self.positions.put(key.clone(), self.counters.len());
+            self.counters.push(Counter { key, count, error: 0 });
+            return;
+        }
+        let (position, smallest) = self
+            .counters
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, counter)| counter.count)
+            .map(|(position, counter)| (position, counter.count))
+            .expect("capacity to be positive");
+        self.positions.remove(&self.counters[position].key);
+        self.positions.put(key.clone(), position);
+        self.counters[position] = Counter {
+            key,
+            count: smallest + count,
+            error: smallest,
+        };
+    }
+
+    pub fn insert(&mut self, key: K) {
+        self.add(key, 1);
+    }
+
+    /// returns the counter of the key if it is monitored
+    pub fn get(&self, key: &K) -> Option<&Counter<K>> {
+        self.positions.get(key).map(|&position| &self.counters[position])
+    }
+
+    /// returns the k counters with the highest counts, the highest first
+    pub fn top(&self, k: usize) -> Vec<&Counter<K>> {
+        let mut counters: Vec<&Counter<K>> = self.counters.iter().collect();
+        counters.sort_by_key(|counter| std::cmp::Reverse(counter.count));
+        counters.truncate(k);
+        counters
+    }
+
+    /// returns the counters whose key certainly occurs more than threshold times,
+    /// because even after subtracting the error their count exceeds it
+    pub fn guaranteed_above(&self, threshold: u64) -> Vec<&Counter<K>> {
+        self.counters.iter().filter(|counter| counter.count - counter.error > threshold).collect()
+    }
+}
+
+#[test]
+pub fn space_saving_test() {
+    use crate::hash::count_min::zipf_stream;
+
+    let (stream, counts) = zipf_stream(5_000);
+    let mut summary = SpaceSaving::with_error(0.005);
+    assert_eq!(summary.capacity(), 200);
+    for &key in &stream {
+        summary.insert(key);
+    }
+    assert_eq!(summary.total(), stream.len() as u64);
+
+    let bound = summary.error_bound();
+    for (key, &count) in counts.iter().enumerate() {
+        match summary.get(&(key as u32)) {
+            Some(counter) => {
+                assert!(counter.count >= count && counter.count - counter.error <= count);
+                assert!(counter.count - count <= bound);
+            }
+            None => assert!(count <= bound, "key {} occurs {} times but is not monitored", key, count),
+        }
+    }
+
+    let top: Vec<u32> = summary.top(5).iter().map(|counter| counter.key).collect();
+    assert_eq!(top, vec![0, 1, 2, 3, 4]);
+    let guaranteed: Vec<u32> = summary.guaranteed_above(1_000).iter().map(|counter| counter.key).collect();
+    assert!(guaranteed.iter().all(|&key| counts[key as usize] > 1_000));
+    assert!(guaranteed.contains(&0) && guaranteed.contains(&9));
+}
+
+#[test]
+pub fn space_saving_eviction_test() {
+    let mut summary = SpaceSaving::new(2);
+    summary.add("a", 5);
+    summary.add("b", 2);
+    summary.add("c", 1);
+    assert!(summary.get(&"b").is_none());
+    assert_eq!(summary.get(&"c"), Some(&Counter { key: "c", count: 3, error: 2 }));
+    summary.insert("a");
+    assert_eq!(summary.top(1)[0].count, 6);
+    assert_eq!(summary.top(5).len(), 2);
+}
@@ -1,23 +1,30 @@
-use crate::hash::mod_hash::mod_hash;
-
-pub trait ToBytes {
-    fn to_bytes(&self) -> Vec<u8>;
-}
-
-impl ToBytes for i32 {
-    fn to_bytes(&self) -> Vec<u8> {
-        Vec::from(self.to_be_bytes())
-    }
-}
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
 
+use crate::hash::map::Map;
+use crate::hash::mod_hash::ModHashBuilder;
+pub use crate::hash::to_bytes::ToBytes;
 
-pub struct StaticHashMap<K: ToBytes, V> {
+/// A hash map with a fixed number of buckets that resolves collisions by chaining
+///
+/// Keys are hashed with the [BuildHasher] S, which defaults to [ModHashBuilder], and compared with [Eq].
+pub struct StaticHashMap<K, V, S = ModHashBuilder> {
     size: u32,
     heads: Vec<Option<Vec<(K, V)>>>,
+    len: usize,
+    hash_builder: S,
 }
 
-impl<K: ToBytes, V> StaticHashMap<K, V> {
+impl<K: Hash + Eq, V> StaticHashMap<K, V> {
     pub fn new(size: u32) -> Self {
+        Self::with_hasher(size, ModHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> StaticHashMap<K, V, S> {
+    /// creates a map with the given number of buckets that hashes keys with hash_builder
+    pub fn with_hasher(size: u32, hash_builder: S) -> Self {
+        assert!(size > 0, "a map needs at least one bucket");
         let mut heads = Vec::with_capacity(size as usize);
         for _ in 0..size {
             heads.push(None);
@@ -25,46 +32,496 @@ impl<K: ToBytes, V> StaticHashMap<K, V> {
         Self {
             size,
             heads,
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// returns the number of buckets
+    pub fn bucket_count(&self) -> usize {
+        self.size as usize
+    }
+
+    /// moves all entries into size new buckets
+    ///
+    /// The map never changes its number of buckets on its own, call this once chains get too long.
+    pub fn resize(&mut self, size: u32) {
+        assert!(size > 0, "a map needs at least one bucket");
+        let old_heads = std::mem::replace(&mut self.heads, (0..size).map(|_| None).collect());
+        self.size = size;
+        for (key, value) in old_heads.into_iter().flatten().flatten() {
+            let hash = self.hash(&key);
+            self.heads[hash].get_or_insert_with(Vec::new).push((key, value));
         }
     }
 
-    pub fn hash(&self, key: &K) -> usize {
-        mod_hash(key.to_bytes(), self.size) as usize
+    /// returns the index of the bucket for the key
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hash_builder.hash_one(key) % self.size as u64) as usize
+    }
+
+    /// returns the index of the key within its bucket
+    fn position<Q: Hash + Eq + ?Sized>(&self, hash: usize, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        self.heads[hash].as_ref()?.iter().position(|pair| pair.0.borrow() == key)
     }
 
-    pub fn put(&mut self, key: K, value: V) {
+    /// inserts a key-value pair, returning the value previously stored for the key
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let hash = self.hash(key);
+        let index = self.position(hash, key)?;
+        self.heads[hash].as_ref().map(|vec| &vec[index].1)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let hash = self.hash(key);
+        let index = self.position(hash, key)?;
+        self.heads[hash].as_mut().map(|vec| &mut vec[index].1)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.position(self.hash(key), key).is_some()
+    }
+
+    /// removes a key from the map, returning its value
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let hash = self.hash(key);
+        let index = self.position(hash, key)?;
+        self.len -= 1;
+        self.heads[hash].as_mut().map(|vec| vec.swap_remove(index).1)
+    }
+
+    /// returns the entry for the key, which can be used to insert or update its value with a single lookup
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
         let hash = self.hash(&key);
-        match &mut self.heads[hash] {
-            None => {
-                let mut vec = Vec::new();
-                vec.push((key, value));
-                self.heads[hash] = Some(vec);
+        match self.position(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                pair: &mut self.heads[hash].as_mut().expect("bucket to contain the key")[index],
+            }),
+            None => Entry::Vacant(VacantEntry {
+                key,
+                bucket: &mut self.heads[hash],
+                len: &mut self.len,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// removes all entries, keeping the number of buckets
+    pub fn clear(&mut self) {
+        for head in self.heads.iter_mut() {
+            *head = None;
+        }
+        self.len = 0;
+    }
+
+    /// returns an iterator over the entries in bucket order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            heads: self.heads.iter(),
+            bucket: [].iter(),
+        }
+    }
+
+    /// returns an iterator over the entries in bucket order that allows changing the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            heads: self.heads.iter_mut(),
+            bucket: [].iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// A view into a single entry of a [StaticHashMap], which is either occupied or vacant
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    pair: &'a mut (K, V),
+}
+
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    bucket: &'a mut Option<Vec<(K, V)>>,
+    len: &'a mut usize,
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// returns the value of the entry, inserting default if it is vacant
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// returns the value of the entry, inserting the result of default if it is vacant
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// calls f with the value of the entry if it is occupied
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.pair.0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.pair.1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.pair.1
+    }
+
+    /// converts the entry into a reference to its value that lives as long as the map is borrowed
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.pair.1
+    }
+
+    /// replaces the value of the entry, returning the old value
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(&mut self.pair.1, value)
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// inserts the value for the key of the entry, returning a reference to it
+    pub fn insert(self, value: V) -> &'a mut V {
+        *self.len += 1;
+        let vec = self.bucket.get_or_insert_with(Vec::new);
+        vec.push((self.key, value));
+        &mut vec.last_mut().expect("pair to be pushed").1
+    }
+}
+
+/// An iterator over the entries of a [StaticHashMap]
+pub struct Iter<'a, K, V> {
+    heads: std::slice::Iter<'a, Option<Vec<(K, V)>>>,
+    bucket: std::slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.bucket.next() {
+                return Some((key, value));
             }
-            Some(vec) => {
-                if let Some(pair) = vec.iter().enumerate().find(|pair| pair.1.0.to_bytes() == key.to_bytes()) {
-                    vec.remove(pair.0);
-                }
-                vec.push((key, value));
+            if let Some(vec) = self.heads.next()? {
+                self.bucket = vec.iter();
             }
-        };
+        }
     }
+}
 
-    pub fn get(&mut self, key: K) -> Option<&V> {
-        let hash = self.hash(&key);
-        if let Some(vec) = &self.heads[hash] {
-            vec.iter().find(|pair| pair.0.to_bytes() == key.to_bytes()).map(|pair| &pair.1)
-        } else {
-            None
+/// An iterator over the entries of a [StaticHashMap] that allows changing the values
+pub struct IterMut<'a, K, V> {
+    heads: std::slice::IterMut<'a, Option<Vec<(K, V)>>>,
+    bucket: std::slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.bucket.next() {
+                return Some((key, value));
+            }
+            if let Some(vec) = self.heads.next()? {
+                self.bucket = vec.iter_mut();
+            }
         }
     }
 }
 
+/// An iterator that moves the entries out of a [StaticHashMap]
+pub struct IntoIter<K, V> {
+    heads: std::vec::IntoIter<Option<Vec<(K, V)>>>,
+    bucket: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.bucket.next() {
+                return Some(pair);
+            }
+            if let Some(vec) = self.heads.next()? {
+                self.bucket = vec.into_iter();
+            }
+        }
+    }
+}
+
+impl<K, V, S> IntoIterator for StaticHashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            heads: self.heads.into_iter(),
+            bucket: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> IntoIterator for &'a StaticHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> IntoIterator for &'a mut StaticHashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Collects the pairs into a map with one bucket per pair, later pairs overwrite earlier ones with the same key
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for StaticHashMap<K, V, S> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let pairs: Vec<(K, V)> = iter.into_iter().collect();
+        let mut map = Self::with_hasher(pairs.len().max(1) as u32, S::default());
+        map.extend(pairs);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for StaticHashMap<K, V, S> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.put(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Map<K, V> for StaticHashMap<K, V, S> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        StaticHashMap::put(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        StaticHashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        StaticHashMap::get_mut(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        StaticHashMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        StaticHashMap::len(self)
+    }
+}
+
 #[test]
 pub fn static_hashmap_test() {
     let mut map = StaticHashMap::new(101);
     map.put(10, "Hello, World!");
     map.put(20, "Bye!");
-    assert_eq!(map.get(10), Some(&"Hello, World!"));
-    assert_eq!(map.get(20), Some(&"Bye!"));
-    assert_eq!(map.get(30), None);
-}
\ No newline at end of file
+    assert_eq!(map.get(&10), Some(&"Hello, World!"));
+    assert_eq!(map.get(&20), Some(&"Bye!"));
+    assert_eq!(map.get(&30), None);
+}
+
+#[test]
+pub fn static_hashmap_remove_test() {
+    let mut map = StaticHashMap::new(7);
+    assert!(map.is_empty());
+    for i in 0..20 {
+        assert_eq!(map.put(i, i * 10), None);
+    }
+    assert_eq!(map.put(3, 33), Some(30));
+    assert_eq!(map.len(), 20);
+    assert!(map.contains_key(&3));
+
+    *map.get_mut(&4).unwrap() += 1;
+    assert_eq!(map.get(&4), Some(&41));
+    assert_eq!(map.remove(&4), Some(41));
+    assert_eq!(map.remove(&4), None);
+    assert!(!map.contains_key(&4));
+    assert_eq!(map.len(), 19);
+    assert!((0..20).filter(|&i| i != 4).all(|i| map.contains_key(&i)));
+
+    map.resize(64);
+    assert_eq!(map.bucket_count(), 64);
+    assert_eq!(map.len(), 19);
+    assert!((0..20).filter(|&i| i != 4).all(|i| map.get(&i) == Some(&if i == 3 { 33 } else { i * 10 })));
+
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(&3), None);
+    assert_eq!(map.iter().count(), 0);
+}
+
+#[test]
+pub fn static_hashmap_iter_test() {
+    let mut map: StaticHashMap<i32, i32> = (0..10).map(|i| (i, i * i)).collect();
+    assert_eq!(map.len(), 10);
+
+    let mut pairs: Vec<(i32, i32)> = map.iter().map(|(&key, &value)| (key, value)).collect();
+    pairs.sort();
+    assert_eq!(pairs, (0..10).map(|i| (i, i * i)).collect::<Vec<_>>());
+
+    for (_, value) in &mut map {
+        *value += 1;
+    }
+    for value in map.values_mut() {
+        *value *= 2;
+    }
+    let mut keys: Vec<i32> = map.keys().copied().collect();
+    keys.sort();
+    assert_eq!(keys, (0..10).collect::<Vec<_>>());
+    assert_eq!(map.values().sum::<i32>(), (0..10).map(|i| (i * i + 1) * 2).sum());
+
+    map.extend([(3, 0), (10, 100)]);
+    assert_eq!(map.len(), 11);
+    assert_eq!(map.get(&3), Some(&0));
+    assert_eq!((&map).into_iter().count(), 11);
+
+    let mut pairs: Vec<(i32, i32)> = map.into_iter().collect();
+    pairs.sort();
+    assert_eq!(pairs.first(), Some(&(0, 2)));
+    assert_eq!(pairs.last(), Some(&(10, 100)));
+}
+
+#[test]
+pub fn static_hashmap_entry_test() {
+    let mut counts = StaticHashMap::new(11);
+    for word in [1, 2, 1, 3, 1, 2] {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(counts.get(&1), Some(&3));
+    assert_eq!(counts.get(&2), Some(&2));
+    assert_eq!(counts.get(&3), Some(&1));
+    assert_eq!(counts.len(), 3);
+
+    counts.entry(1).and_modify(|count| *count *= 10).or_insert(0);
+    counts.entry(4).and_modify(|count| *count *= 10).or_insert_with(|| 7);
+    assert_eq!(counts.get(&1), Some(&30));
+    assert_eq!(counts.get(&4), Some(&7));
+    assert_eq!(counts.len(), 4);
+
+    match counts.entry(2) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &2);
+            assert_eq!(entry.insert(5), 2);
+            assert_eq!(entry.get(), &5);
+        }
+        Entry::Vacant(_) => panic!("2 is in the map"),
+    }
+    match counts.entry(5) {
+        Entry::Occupied(_) => panic!("5 is not in the map"),
+        Entry::Vacant(entry) => assert_eq!(entry.key(), &5),
+    }
+    assert_eq!(counts.len(), 4);
+}
+
+#[test]
+pub fn static_hashmap_hasher_test() {
+    use std::collections::hash_map::RandomState;
+
+    let mut map = StaticHashMap::new(13);
+    map.put(String::from("one"), 1);
+    map.put(String::from("two"), 2);
+    map.put(String::from("one"), 11);
+    assert_eq!(map.get("one"), Some(&11));
+    assert_eq!(map.get(&String::from("two")), Some(&2));
+    assert_eq!(map.remove("two"), Some(2));
+    assert_eq!(map.len(), 1);
+
+    let mut map = StaticHashMap::with_hasher(13, RandomState::new());
+    for key in [(1, 'a'), (2, 'b'), (1, 'b')] {
+        *map.entry(key).or_insert(0) += key.0;
+    }
+    map.put((2, 'b'), 5);
+    assert_eq!(map.get(&(1, 'b')), Some(&1));
+    assert_eq!(map.get(&(2, 'b')), Some(&5));
+    assert_eq!(map.get(&(3, 'c')), None);
+
+    let map: StaticHashMap<&str, usize> = ["a", "bb", "ccc"].into_iter().map(|word| (word, word.len())).collect();
+    assert_eq!(map.hash("bb"), map.hash(&"bb"));
+    assert_eq!(map.get("ccc"), Some(&3));
+}
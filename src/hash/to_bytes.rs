@@ -0,0 +1,90 @@
+/// Converts a value to the bytes that are fed into a hash function
+///
+/// Integers are converted to their big endian representation, so that [mod_hash](crate::hash::mod_hash::mod_hash)
+/// sees them as the numbers they represent. Strings are converted to their UTF-8 bytes. Tuples and arrays
+/// concatenate the bytes of their elements.
+pub trait ToBytes {
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_to_bytes_for_integers {
+    ($($integer:ty),*) => {
+        $(
+            impl ToBytes for $integer {
+                fn to_bytes(&self) -> Vec<u8> {
+                    Vec::from(self.to_be_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl ToBytes for str {
+    fn to_bytes(&self) -> Vec<u8> {
+        Vec::from(self.as_bytes())
+    }
+}
+
+impl ToBytes for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_str().to_bytes()
+    }
+}
+
+impl ToBytes for [u8] {
+    fn to_bytes(&self) -> Vec<u8> {
+        Vec::from(self)
+    }
+}
+
+impl ToBytes for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl<T: ToBytes + ?Sized> ToBytes for &T {
+    fn to_bytes(&self) -> Vec<u8> {
+        (**self).to_bytes()
+    }
+}
+
+impl<T: ToBytes, const N: usize> ToBytes for [T; N] {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.iter().flat_map(|element| element.to_bytes()).collect()
+    }
+}
+
+macro_rules! impl_to_bytes_for_tuples {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: ToBytes),+> ToBytes for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn to_bytes(&self) -> Vec<u8> {
+                    let ($($name,)+) = self;
+                    let mut bytes = Vec::new();
+                    $(bytes.extend($name.to_bytes());)+
+                    bytes
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_tuples!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E), (A, B, C, D, E, F));
+
+#[test]
+pub fn to_bytes_test() {
+    assert_eq!(258i32.to_bytes(), vec![0, 0, 1, 2]);
+    assert_eq!((-1i8).to_bytes(), vec![255]);
+    assert_eq!(258u16.to_bytes(), vec![1, 2]);
+    assert_eq!(1u128.to_bytes().len(), 16);
+    assert_eq!(usize::MAX.to_bytes(), vec![255; std::mem::size_of::<usize>()]);
+    assert_eq!("ab".to_bytes(), vec![97, 98]);
+    assert_eq!(String::from("ab").to_bytes(), vec![97, 98]);
+    assert_eq!(vec![1u8, 2, 3].to_bytes(), vec![1, 2, 3]);
+    assert_eq!([1u16, 2].to_bytes(), vec![0, 1, 0, 2]);
+    assert_eq!((1u8, "a", [2u8; 2]).to_bytes(), vec![1, 97, 2, 2]);
+}
@@ -0,0 +1,149 @@
+use std::hash::{BuildHasher, Hasher};
+
+use crate::hash::mod_hash::write_integers;
+
+const PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().expect("4 bytes"))
+}
+
+/// mixes 8 bytes of input into one of the four accumulators
+fn round(accumulator: u64, input: u64) -> u64 {
+    accumulator.wrapping_add(input.wrapping_mul(PRIME_2)).rotate_left(31).wrapping_mul(PRIME_1)
+}
+
+fn merge_round(hash: u64, accumulator: u64) -> u64 {
+    (hash ^ round(0, accumulator)).wrapping_mul(PRIME_1).wrapping_add(PRIME_4)
+}
+
+/// This function returns the 64 bit xxHash (XXH64) of the bytes
+///
+/// Inputs of 32 bytes or more are consumed in stripes of four independent lanes, which is what makes
+/// xxHash fast on long keys. The rest is mixed in 8, 4 and 1 bytes at a time.
+pub fn xxhash64(bytes: &[u8], seed: u64) -> u64 {
+    let stripes = bytes.chunks_exact(32);
+    let mut rest = stripes.remainder();
+    let mut hash = if bytes.len() >= 32 {
+        let mut lanes = [
+            seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+            seed.wrapping_add(PRIME_2),
+            seed,
+            seed.wrapping_sub(PRIME_1),
+        ];
+        for stripe in stripes {
+            for (lane, input) in lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+                *lane = round(*lane, read_u64(input));
+            }
+        }
+        let hash = lanes[0]
+            .rotate_left(1)
+            .wrapping_add(lanes[1].rotate_left(7))
+            .wrapping_add(lanes[2].rotate_left(12))
+            .wrapping_add(lanes[3].rotate_left(18));
+        lanes.iter().fold(hash, |hash, &lane| merge_round(hash, lane))
+    } else {
+        seed.wrapping_add(PRIME_5)
+    };
+    hash = hash.wrapping_add(bytes.len() as u64);
+
+    while rest.len() >= 8 {
+        hash ^= round(0, read_u64(rest));
+        hash = hash.rotate_left(27).wrapping_mul(PRIME_1).wrapping_add(PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        hash ^= (read_u32(rest) as u64).wrapping_mul(PRIME_1);
+        hash = hash.rotate_left(23).wrapping_mul(PRIME_2).wrapping_add(PRIME_3);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash ^= (byte as u64).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+/// A [Hasher] that collects the bytes written to it and hashes them with [xxhash64]
+pub struct XxHasher {
+    bytes: Vec<u8>,
+    seed: u64,
+}
+
+impl XxHasher {
+    pub fn new(seed: u64) -> Self {
+        Self { bytes: Vec::new(), seed }
+    }
+}
+
+impl Hasher for XxHasher {
+    fn finish(&self) -> u64 {
+        xxhash64(&self.bytes, self.seed)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    write_integers!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128, write_isize: isize
+    );
+}
+
+/// Creates [XxHasher]s with a fixed seed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct XxHashBuilder {
+    seed: u64,
+}
+
+impl XxHashBuilder {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for XxHashBuilder {
+    type Hasher = XxHasher;
+
+    fn build_hasher(&self) -> XxHasher {
+        XxHasher::new(self.seed)
+    }
+}
+
+#[test]
+pub fn xxhash64_test() {
+    // test vectors of the reference implementation, covering inputs shorter and longer than a stripe
+    assert_eq!(xxhash64(b"", 0), 0xef46_db37_51d8_e999);
+    assert_eq!(xxhash64(b"a", 0), 0xd24e_c4f1_a98c_6e5b);
+    assert_eq!(xxhash64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+    assert_eq!(xxhash64(b"Nobody inspects the spammish repetition", 0), 0xfbce_a83c_8a37_8bf1);
+    assert_eq!(xxhash64(b"The quick brown fox jumps over the lazy dog", 0), 0x0b24_2d36_1fda_71bc);
+}
+
+#[test]
+pub fn xxhash_hasher_test() {
+    use crate::hash::hashmap::HashMap;
+    use crate::hash::map::check_against_std;
+    use crate::hash::to_bytes::ToBytes;
+
+    let mut hasher = XxHasher::new(0);
+    hasher.write(b"Nobody inspects ");
+    hasher.write(b"the spammish repetition");
+    assert_eq!(hasher.finish(), 0xfbce_a83c_8a37_8bf1);
+    assert_eq!(XxHashBuilder::new(3).hash_one(42u64), xxhash64(&42u64.to_bytes(), 3));
+    check_against_std(HashMap::with_hasher(XxHashBuilder::default()), 5_000, 20_000);
+}
@@ -13,6 +13,14 @@ impl<T> ArrayStack<T> {
         }
     }
 
+    /// Creates a new ArrayStack that can hold capacity elements without reallocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArrayStack {
+            array: Vec::with_capacity(capacity),
+            stack_pointer: 0,
+        }
+    }
+
     /// Pushes a new value onto the stack
     pub fn push(&mut self, val: T) {
         if self.array.len() > self.stack_pointer {
@@ -37,6 +45,12 @@ impl<T> ArrayStack<T> {
     pub fn is_empty(&self) -> bool {
         self.stack_pointer == 0
     }
+
+    /// Removes all elements from the stack while keeping its allocated memory
+    pub fn clear(&mut self) {
+        self.array.clear();
+        self.stack_pointer = 0;
+    }
 }
 
 #[test]
@@ -79,4 +93,16 @@ pub fn is_empty_test() {
     assert!(stack.is_empty());
     stack.pop();
     assert!(stack.is_empty());
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn with_capacity_and_clear_test() {
+    let mut stack = ArrayStack::with_capacity(8);
+    assert!(stack.array.capacity() >= 8);
+    stack.push(1);
+    stack.push(2);
+    stack.clear();
+    assert!(stack.is_empty());
+    assert_eq!(stack.pop(), None);
+    assert!(stack.array.capacity() >= 8);
+}
@@ -6,6 +6,7 @@ static RADIX: u32 = 10;
 #[derive(Debug)]
 pub enum BigIntegerError {
     ParseError,
+    DivisionByZero,
 }
 
 /// Represents an immutable integer of arbitrary size
@@ -44,7 +45,7 @@ impl BigInteger {
             return Err(BigIntegerError::ParseError);
         }
 
-        Ok(Self { digits, neg })
+        Ok(Self { digits, neg }.trimmed())
     }
 
     pub fn from_i32(value: i32) -> Self {
@@ -291,10 +292,93 @@ impl BigInteger {
             neg: false
         }
     }
+
+    /// divides this BigInteger by other using long division
+    /// returns the quotient rounded towards zero and the remainder, which has the sign of this BigInteger
+    pub fn div_rem(&self, other: &Self) -> Result<(Self, Self), BigIntegerError> {
+        if other.is_zero() {
+            return Err(BigIntegerError::DivisionByZero);
+        }
+        let divisor = other.as_positive();
+        let mut quotient_digits = vec![0; self.digits.len()];
+        let mut remainder = ZERO.clone();
+        for i in (0..self.digits.len()).rev() {
+            let mut digits = remainder.digits.clone();
+            digits.insert(0, self.digits[i]);
+            remainder = Self { digits, neg: false }.trimmed();
+            while !divisor.greater_than(&remainder) {
+                remainder = remainder.sub(&divisor);
+                quotient_digits[i] += 1;
+            }
+        }
+
+        let quotient = Self {
+            digits: quotient_digits,
+            neg: self.neg != other.neg,
+        };
+        Ok((quotient.trimmed(), remainder.with_sign(self.neg).trimmed()))
+    }
+
+    /// raises this BigInteger to the given power by repeated squaring
+    pub fn pow(&self, exponent: u32) -> Self {
+        let mut result = Self::from_i32(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent /= 2;
+        }
+        result
+    }
+
+    /// returns the greatest common divisor of the magnitudes of this BigInteger and other
+    /// computed with euclid's algorithm
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut u = self.as_positive();
+        let mut v = other.as_positive();
+        while !v.is_zero() {
+            let (_, remainder) = u.div_rem(&v).expect("divisor to be non-zero");
+            u = v;
+            v = remainder;
+        }
+        u
+    }
+
+    /// returns the value of this BigInteger if it fits into an i64
+    pub fn to_i64(&self) -> Option<i64> {
+        let mut value: i64 = 0;
+        for &digit in self.digits.iter().rev() {
+            let digit = if self.neg { -(digit as i64) } else { digit as i64 };
+            value = value.checked_mul(10)?.checked_add(digit)?;
+        }
+        Some(value)
+    }
+
+    /// returns the nearest f64 to this BigInteger, which is infinite if it is too large
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().expect("the decimal representation to be a valid float")
+    }
+
+    /// removes leading zeros, so that zero is always represented without digits and without a sign
+    fn trimmed(mut self) -> Self {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+        if self.digits.is_empty() {
+            self.neg = false;
+        }
+        self
+    }
 }
 
 impl Display for BigInteger {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
         write!(
             f,
             "{}{}",
@@ -402,3 +486,49 @@ pub fn mul_test() {
     let c = BigInteger::from_i32(-472 * -9);
     assert!(a.mul(&b).equals(&c));
 }
+
+#[test]
+pub fn div_rem_test() {
+    let div_rem = |a: i32, b: i32| {
+        let (quotient, remainder) = BigInteger::from_i32(a).div_rem(&BigInteger::from_i32(b)).unwrap();
+        (quotient.to_string(), remainder.to_string())
+    };
+    assert_eq!(div_rem(4725, 15), ("315".to_string(), "0".to_string()));
+    assert_eq!(div_rem(4725, 16), ("295".to_string(), "5".to_string()));
+    assert_eq!(div_rem(7, 9), ("0".to_string(), "7".to_string()));
+    assert_eq!(div_rem(-7, 2), ("-3".to_string(), "-1".to_string()));
+    assert_eq!(div_rem(7, -2), ("-3".to_string(), "1".to_string()));
+    assert_eq!(div_rem(-8, -2), ("4".to_string(), "0".to_string()));
+    assert_eq!(div_rem(0, 5), ("0".to_string(), "0".to_string()));
+
+    let a = BigInteger::from_str("123456789012345678901234567890").unwrap();
+    let b = BigInteger::from_str("987654321").unwrap();
+    let (quotient, remainder) = a.div_rem(&b).unwrap();
+    assert!(quotient.mul(&b).add(&remainder).equals(&a));
+    assert!(b.greater_than(&remainder));
+    assert!(matches!(a.div_rem(&BigInteger::from_i32(0)), Err(BigIntegerError::DivisionByZero)));
+}
+
+#[test]
+pub fn pow_test() {
+    assert_eq!(BigInteger::from_i32(2).pow(100).to_string(), "1267650600228229401496703205376");
+    assert_eq!(BigInteger::from_i32(-3).pow(3).to_string(), "-27");
+    assert_eq!(BigInteger::from_i32(12).pow(0).to_string(), "1");
+}
+
+#[test]
+pub fn conversion_test() {
+    assert_eq!(BigInteger::from_str("-9223372036854775808").unwrap().to_i64(), Some(i64::MIN));
+    assert_eq!(BigInteger::from_str("9223372036854775808").unwrap().to_i64(), None);
+    assert_eq!(BigInteger::from_i32(-42).to_f64(), -42.0);
+    assert_eq!(BigInteger::from_i32(10).pow(400).to_f64(), f64::INFINITY);
+}
+
+#[test]
+pub fn gcd_test() {
+    assert_eq!(BigInteger::from_i32(84).gcd(&BigInteger::from_i32(-231)).to_string(), "21");
+    assert_eq!(BigInteger::from_i32(0).gcd(&BigInteger::from_i32(5)).to_string(), "5");
+    let a = BigInteger::from_i32(2).pow(80).mul(&BigInteger::from_i32(3));
+    let b = BigInteger::from_i32(2).pow(70).mul(&BigInteger::from_i32(5));
+    assert!(a.gcd(&b).equals(&BigInteger::from_i32(2).pow(70)));
+}
@@ -1,3 +1,4 @@
 pub mod eratos;
 pub mod gcd;
-pub mod big_integer;
\ No newline at end of file
+pub mod big_integer;
+pub mod rational;
@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter};
+
+use crate::numbers::big_integer::{BigInteger, BigIntegerError};
+
+/// Represents an immutable fraction of two BigIntegers
+/// The fraction is always kept in lowest terms with a positive denominator, so equal numbers have equal parts
+#[derive(Debug, Clone)]
+pub struct Rational {
+    numerator: BigInteger,
+    denominator: BigInteger,
+}
+
+impl Rational {
+    /// returns the fraction numerator / denominator reduced to lowest terms
+    /// throws an error if the denominator is zero
+    pub fn new(numerator: BigInteger, denominator: BigInteger) -> Result<Self, BigIntegerError> {
+        if denominator.is_zero() {
+            return Err(BigIntegerError::DivisionByZero);
+        }
+        let gcd = numerator.gcd(&denominator);
+        let (numerator, _) = numerator.div_rem(&gcd)?;
+        let (denominator, _) = denominator.div_rem(&gcd)?;
+        if denominator.is_negative() {
+            return Ok(Self {
+                numerator: numerator.negate(),
+                denominator: denominator.negate(),
+            });
+        }
+        Ok(Self { numerator, denominator })
+    }
+
+    pub fn from_integer(value: BigInteger) -> Self {
+        Self {
+            numerator: value,
+            denominator: BigInteger::from_i32(1),
+        }
+    }
+
+    pub fn from_i32(value: i32) -> Self {
+        Self::from_integer(BigInteger::from_i32(value))
+    }
+
+    /// returns the Rational represented by this string
+    /// accepts integers, fractions like -3/4 and decimals like 1.25
+    pub fn parse(value: &str) -> Result<Self, BigIntegerError> {
+        if let Some((numerator, denominator)) = value.split_once('/') {
+            return Self::new(BigInteger::from_str(numerator.trim())?, BigInteger::from_str(denominator.trim())?);
+        }
+        match value.split_once('.') {
+            Some((whole, fraction)) => {
+                if whole.is_empty() || whole == "-" || fraction.is_empty() || fraction.starts_with('-') {
+                    return Err(BigIntegerError::ParseError);
+                }
+                let numerator = BigInteger::from_str(&format!("{}{}", whole, fraction))?;
+                let denominator = BigInteger::from_i32(10).pow(fraction.len() as u32);
+                Self::new(numerator, denominator)
+            }
+            None if value.is_empty() => Err(BigIntegerError::ParseError),
+            None => Ok(Self::from_integer(BigInteger::from_str(value)?)),
+        }
+    }
+
+    pub fn numerator(&self) -> &BigInteger {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &BigInteger {
+        &self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator.equals(&BigInteger::from_i32(1))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numerator.is_negative()
+    }
+
+    pub fn negate(&self) -> Self {
+        Self {
+            numerator: self.numerator.negate(),
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let numerator = self.numerator.mul(&other.denominator).add(&other.numerator.mul(&self.denominator));
+        Self::new(numerator, self.denominator.mul(&other.denominator)).expect("denominators to be non-zero")
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.negate())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let numerator = self.numerator.mul(&other.numerator);
+        Self::new(numerator, self.denominator.mul(&other.denominator)).expect("denominators to be non-zero")
+    }
+
+    /// divides this Rational by other, throws an error if other is zero
+    pub fn div(&self, other: &Self) -> Result<Self, BigIntegerError> {
+        Self::new(self.numerator.mul(&other.denominator), self.denominator.mul(&other.numerator))
+    }
+
+    /// raises this Rational to an integer power, throws an error for a negative power of zero
+    pub fn pow(&self, exponent: i32) -> Result<Self, BigIntegerError> {
+        let power = Self {
+            numerator: self.numerator.pow(exponent.unsigned_abs()),
+            denominator: self.denominator.pow(exponent.unsigned_abs()),
+        };
+        if exponent < 0 {
+            return Self::from_i32(1).div(&power);
+        }
+        Ok(power)
+    }
+
+    /// returns true if this Rational represents a larger number than other
+    pub fn greater_than(&self, other: &Self) -> bool {
+        self.numerator.mul(&other.denominator).greater_than(&other.numerator.mul(&self.denominator))
+    }
+
+    pub fn equals(&self, other: &Self) -> bool {
+        self.numerator.equals(&other.numerator) && self.denominator.equals(&other.denominator)
+    }
+
+    /// returns the nearest f64 to this Rational
+    pub fn to_f64(&self) -> f64 {
+        let numerator = self.numerator.to_f64();
+        let denominator = self.denominator.to_f64();
+        if numerator.is_finite() && denominator.is_finite() {
+            return numerator / denominator;
+        }
+        // drops the lowest digits of both parts so that they fit into a float, which barely changes their ratio
+        let digits = self.numerator.to_string().len().max(self.denominator.to_string().len());
+        let scale = BigInteger::from_i32(10).pow((digits - 300) as u32);
+        let (numerator, _) = self.numerator.div_rem(&scale).expect("scale to be non-zero");
+        let (denominator, _) = self.denominator.div_rem(&scale).expect("scale to be non-zero");
+        numerator.to_f64() / denominator.to_f64()
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[test]
+pub fn rational_test() {
+    let parse = |value: &str| Rational::parse(value).unwrap();
+    assert_eq!(parse("6/-8").to_string(), "-3/4");
+    assert_eq!(parse("-1.25").to_string(), "-5/4");
+    assert_eq!(parse("0.10").to_string(), "1/10");
+    assert_eq!(parse("12").to_string(), "12");
+    assert_eq!(parse("0/5").to_string(), "0");
+    assert!(Rational::parse("1/0").is_err());
+    assert!(Rational::parse("1.").is_err());
+    assert!(Rational::parse("x").is_err());
+
+    let a = parse("1/2");
+    let b = parse("1/3");
+    assert_eq!(a.add(&b).to_string(), "5/6");
+    assert_eq!(a.sub(&b).to_string(), "1/6");
+    assert_eq!(b.sub(&a).to_string(), "-1/6");
+    assert_eq!(a.mul(&b).to_string(), "1/6");
+    assert_eq!(a.div(&b).unwrap().to_string(), "3/2");
+    assert!(a.div(&parse("0")).is_err());
+    assert_eq!(parse("-2/3").pow(3).unwrap().to_string(), "-8/27");
+    assert_eq!(parse("-2/3").pow(-2).unwrap().to_string(), "9/4");
+    assert!(parse("0").pow(-1).is_err());
+
+    assert!(a.greater_than(&b));
+    assert!(!b.greater_than(&a));
+    assert!(parse("-1/3").greater_than(&parse("-1/2")));
+    assert!(a.equals(&parse("2/4")));
+    assert!(a.mul(&parse("2")).is_integer());
+    assert_eq!(parse("-7/4").to_f64(), -1.75);
+    let large = BigInteger::from_i32(10).pow(400);
+    let seven_halves = Rational::new(large.mul(&BigInteger::from_i32(7)).add(&BigInteger::from_i32(1)), large.mul(&BigInteger::from_i32(2)));
+    assert!((seven_halves.unwrap().to_f64() - 3.5).abs() < 1e-12);
+}
@@ -0,0 +1,347 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+type Link<T> = Option<Box<AvlTreeNode<T>>>;
+
+struct AvlTreeNode<T> {
+    value: T,
+    /// the number of nodes on the longest path down to a leaf, a leaf has height 1
+    height: u32,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+fn height<T>(link: &Link<T>) -> u32 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+impl<T: Ord> AvlTreeNode<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn update_height(&mut self) {
+        self.height = 1 + height(&self.left).max(height(&self.right));
+    }
+
+    /// returns the height of the left subtree minus the height of the right one
+    fn balance_factor(&self) -> i64 {
+        height(&self.left) as i64 - height(&self.right) as i64
+    }
+
+    /// lifts the left child above the node
+    fn rotate_right(mut self: Box<Self>) -> Box<Self> {
+        let mut left = self.left.take().expect("rotating right needs a left child");
+        self.left = left.right.take();
+        self.update_height();
+        left.right = Some(self);
+        left.update_height();
+        left
+    }
+
+    /// lifts the right child above the node
+    fn rotate_left(mut self: Box<Self>) -> Box<Self> {
+        let mut right = self.right.take().expect("rotating left needs a right child");
+        self.right = right.left.take();
+        self.update_height();
+        right.left = Some(self);
+        right.update_height();
+        right
+    }
+
+    /// restores the balance of a node whose subtrees differ in height by at most 2
+    fn rebalance(mut self: Box<Self>) -> Box<Self> {
+        self.update_height();
+        let balance = self.balance_factor();
+        if balance > 1 {
+            // a left child leaning right has to be turned first, otherwise the rotation only mirrors the imbalance
+            if self.left.as_ref().is_some_and(|left| left.balance_factor() < 0) {
+                self.left = self.left.take().map(|left| left.rotate_left());
+            }
+            self.rotate_right()
+        } else if balance < -1 {
+            if self.right.as_ref().is_some_and(|right| right.balance_factor() > 0) {
+                self.right = self.right.take().map(|right| right.rotate_right());
+            }
+            self.rotate_left()
+        } else {
+            self
+        }
+    }
+
+    /// inserts the value below link, sets inserted to false if it is already there
+    fn insert(link: Link<T>, value: T, inserted: &mut bool) -> Box<Self> {
+        let Some(mut node) = link else {
+            *inserted = true;
+            return Box::new(Self::new(value));
+        };
+        match value.cmp(&node.value) {
+            Ordering::Less => node.left = Some(Self::insert(node.left.take(), value, inserted)),
+            Ordering::Greater => node.right = Some(Self::insert(node.right.take(), value, inserted)),
+            Ordering::Equal => return node,
+        }
+        node.rebalance()
+    }
+
+    /// removes the smallest value of the subtree, returning the rest of the subtree and the value
+    fn remove_min(mut node: Box<Self>) -> (Link<T>, T) {
+        match node.left.take() {
+            Some(left) => {
+                let (left, min) = Self::remove_min(left);
+                node.left = left;
+                (Some(node.rebalance()), min)
+            }
+            None => (node.right.take(), node.value),
+        }
+    }
+
+    /// removes the value from below link, storing it in removed
+    fn remove<Q: Ord + ?Sized>(link: Link<T>, value: &Q, removed: &mut Option<T>) -> Link<T>
+    where
+        T: Borrow<Q>,
+    {
+        let mut node = link?;
+        match value.cmp(node.value.borrow()) {
+            Ordering::Less => node.left = Self::remove(node.left.take(), value, removed),
+            Ordering::Greater => node.right = Self::remove(node.right.take(), value, removed),
+            Ordering::Equal => {
+                let node = *node;
+                let (left, right) = match (node.left, node.right) {
+                    (None, child) | (child, None) => {
+                        *removed = Some(node.value);
+                        return child;
+                    }
+                    (Some(left), Some(right)) => (left, right),
+                };
+                // the in-order successor takes the place of the removed value
+                let (right, successor) = Self::remove_min(right);
+                *removed = Some(node.value);
+                let mut replacement = Box::new(Self::new(successor));
+                replacement.left = Some(left);
+                replacement.right = right;
+                return Some(replacement.rebalance());
+            }
+        }
+        Some(node.rebalance())
+    }
+}
+
+/// A binary search tree that keeps the heights of the two subtrees of every node within one of each other
+///
+/// Insertions and removals rotate the nodes on the path back to the root whenever a subtree gets too high,
+/// so the tree height stays below 1.45 log2(n + 2) and all operations take logarithmic time.
+/// Equal values are stored once.
+pub struct AvlTree<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+impl<T: Ord> AvlTree<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the number of nodes on the longest path from the root to a leaf
+    pub fn height(&self) -> u32 {
+        height(&self.root)
+    }
+
+    /// inserts the value, returns false if it is already in the tree
+    pub fn insert(&mut self, value: T) -> bool {
+        let mut inserted = false;
+        self.root = Some(AvlTreeNode::insert(self.root.take(), value, &mut inserted));
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// removes the value from the tree and returns it
+    pub fn remove<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+    {
+        let mut removed = None;
+        self.root = AvlTreeNode::remove(self.root.take(), value, &mut removed);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// returns the value in the tree that is equal to value
+    pub fn get<Q: Ord + ?Sized>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+    {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            match value.cmp(node.value.borrow()) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => link = &node.right,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        None
+    }
+
+    pub fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.get(value).is_some()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some(&node.value)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some(&node.value)
+    }
+
+    /// returns an iterator over the values in ascending order
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    /// checks that the values are ordered, the heights are correct and every node is balanced
+    #[cfg(test)]
+    fn check_invariants(&self) {
+        fn check<T: Ord>(link: &Link<T>, lower: Option<&T>, upper: Option<&T>) -> (u32, usize) {
+            let Some(node) = link else {
+                return (0, 0);
+            };
+            assert!(lower.is_none_or(|lower| *lower < node.value), "a value is not above its left ancestor");
+            assert!(upper.is_none_or(|upper| node.value < *upper), "a value is not below its right ancestor");
+            let (left_height, left_len) = check(&node.left, lower, Some(&node.value));
+            let (right_height, right_len) = check(&node.right, Some(&node.value), upper);
+            assert_eq!(node.height, 1 + left_height.max(right_height), "a stored height is wrong");
+            assert!(left_height.abs_diff(right_height) <= 1, "a node is out of balance");
+            (node.height, 1 + left_len + right_len)
+        }
+        let (_, len) = check(&self.root, None, None);
+        assert_eq!(len, self.len, "the length does not match the number of nodes");
+    }
+}
+
+impl<T: Ord> Default for AvlTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for AvlTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for value in iter {
+            tree.insert(value);
+        }
+        tree
+    }
+}
+
+/// An in-order iterator over an [AvlTree], it keeps the path to the next value on a stack
+pub struct Iter<'a, T> {
+    stack: Vec<&'a AvlTreeNode<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, mut link: &'a Link<T>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.value)
+    }
+}
+
+#[test]
+pub fn avl_tree_test() {
+    let mut tree = AvlTree::new();
+    assert_eq!(tree.height(), 0);
+    assert_eq!(tree.min(), None);
+    for value in 0..1000 {
+        assert!(tree.insert(value));
+        tree.check_invariants();
+    }
+    assert!(!tree.insert(500));
+    assert_eq!(tree.len(), 1000);
+    // sorted input would make an unbalanced tree 1000 levels deep
+    assert!(tree.height() <= 14, "height {}", tree.height());
+    assert_eq!(tree.min(), Some(&0));
+    assert_eq!(tree.max(), Some(&999));
+    assert!(tree.iter().copied().eq(0..1000));
+
+    assert_eq!(tree.remove(&500), Some(500));
+    assert_eq!(tree.remove(&500), None);
+    assert!(!tree.contains(&500));
+    assert_eq!(tree.get(&501), Some(&501));
+    for value in (0..1000).rev() {
+        tree.remove(&value);
+        tree.check_invariants();
+    }
+    assert!(tree.is_empty());
+
+    let words: AvlTree<String> = ["pear", "apple", "fig"].into_iter().map(String::from).collect();
+    assert!(words.contains("fig"));
+    assert_eq!(words.iter().map(String::as_str).collect::<Vec<_>>(), vec!["apple", "fig", "pear"]);
+}
+
+#[test]
+pub fn avl_tree_random_test() {
+    use std::collections::BTreeSet;
+
+    let mut tree = AvlTree::new();
+    let mut expected = BTreeSet::new();
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    for i in 0..20_000 {
+        // xorshift64, deterministic so failures can be reproduced
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let value = (state % 500) as u32;
+        match (state >> 32) % 3 {
+            0 => assert_eq!(tree.remove(&value), expected.take(&value), "remove {} in operation {}", value, i),
+            1 => assert_eq!(tree.insert(value), expected.insert(value), "insert {} in operation {}", value, i),
+            _ => assert_eq!(tree.contains(&value), expected.contains(&value), "contains {} in operation {}", value, i),
+        }
+        tree.check_invariants();
+        assert_eq!(tree.len(), expected.len());
+    }
+    assert!(tree.iter().eq(expected.iter()));
+    assert_eq!(tree.min(), expected.first());
+    assert_eq!(tree.max(), expected.last());
+}
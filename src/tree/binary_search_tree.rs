@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 
 struct BinarySearchTreeNode<T: PartialOrd> {
@@ -14,94 +15,215 @@ impl<T: PartialOrd> BinarySearchTreeNode<T> {
             right: None,
         }
     }
+}
 
-    fn insert(&mut self, value: T) {
-        if value < self.value {
-            if let Some(left) = &mut self.left {
-                left.insert(value);
-            } else {
-                self.left = Some(Box::new(BinarySearchTreeNode::new(value)));
-            }
-        } else {
-            if let Some(right) = &mut self.right {
-                right.insert(value);
-            } else {
-                self.right = Some(Box::new(BinarySearchTreeNode::new(value)));
-            }
+pub struct BinarySearchTree<T: PartialOrd> {
+    root: Option<Box<BinarySearchTreeNode<T>>>,
+    len: usize,
+}
+
+impl<T: PartialOrd> BinarySearchTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
         }
     }
 
-    fn traverse_inorder<'a>(&'a self, nodes: &mut Vec<&'a T>) {
-        if let Some(left) = &self.left {
-            left.traverse_inorder(nodes);
-        }
-        nodes.push(&self.value);
-        if let Some(right) = &self.right {
-            right.traverse_inorder(nodes);
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// inserts the value below all values equal to it, walking down in a loop so deep trees cannot overflow the stack
+    pub fn insert(&mut self, value: T) {
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            link = if value < node.value { &mut node.left } else { &mut node.right };
         }
+        *link = Some(Box::new(BinarySearchTreeNode::new(value)));
+        self.len += 1;
     }
 
-    fn traverse_preorder<'a>(&'a self, nodes: &mut Vec<&'a T>) {
-        nodes.push(&self.value);
-        if let Some(left) = &self.left {
-            left.traverse_preorder(nodes);
+    pub fn contains(&self, value: &T) -> bool {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if *value < node.value {
+                link = &node.left;
+            } else if *value > node.value {
+                link = &node.right;
+            } else {
+                return node.value == *value;
+            }
         }
-        if let Some(right) = &self.right {
-            right.traverse_preorder(nodes);
+        false
+    }
+
+    /// removes one value equal to value and returns it
+    ///
+    /// A node with two children is replaced by its in-order successor, the smallest value of its right subtree.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let mut link = &mut self.root;
+        loop {
+            let node = link.as_ref()?;
+            match value.partial_cmp(&node.value)? {
+                Ordering::Less => link = &mut link.as_mut().expect("checked above").left,
+                Ordering::Greater => link = &mut link.as_mut().expect("checked above").right,
+                Ordering::Equal => break,
+            }
         }
+        let mut node = link.take().expect("the loop stops at a node");
+        *link = match (node.left.take(), node.right.take()) {
+            (None, child) | (child, None) => child,
+            (Some(left), Some(mut right)) => {
+                if right.left.is_none() {
+                    right.left = Some(left);
+                    Some(right)
+                } else {
+                    let mut parent = &mut right;
+                    while parent.left.as_ref().is_some_and(|child| child.left.is_some()) {
+                        parent = parent.left.as_mut().expect("checked by the loop condition");
+                    }
+                    let mut successor = parent.left.take().expect("checked by the loop condition");
+                    parent.left = successor.right.take();
+                    successor.left = Some(left);
+                    successor.right = Some(right);
+                    Some(successor)
+                }
+            }
+        };
+        self.len -= 1;
+        Some(node.value)
     }
 
-    fn traverse_postorder<'a>(&'a self, nodes: &mut Vec<&'a T>) {
-        if let Some(left) = &self.left {
-            left.traverse_postorder(nodes);
+    pub fn min(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
         }
-        if let Some(right) = &self.right {
-            right.traverse_postorder(nodes);
+        Some(&node.value)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
         }
-        nodes.push(&self.value);
+        Some(&node.value)
     }
-}
 
-pub struct BinarySearchTree<T: PartialOrd> {
-    root: Option<BinarySearchTreeNode<T>>,
-}
+    /// returns the largest value that is at most value
+    pub fn floor(&self, value: &T) -> Option<&T> {
+        self.search_below(|candidate| candidate <= value)
+    }
 
-impl<T: PartialOrd> BinarySearchTree<T> {
-    pub fn new() -> Self {
-        Self {
-            root: None
+    /// returns the largest value that is smaller than value
+    pub fn predecessor(&self, value: &T) -> Option<&T> {
+        self.search_below(|candidate| candidate < value)
+    }
+
+    /// returns the smallest value that is at least value
+    pub fn ceiling(&self, value: &T) -> Option<&T> {
+        self.search_above(|candidate| candidate >= value)
+    }
+
+    /// returns the smallest value that is larger than value
+    pub fn successor(&self, value: &T) -> Option<&T> {
+        self.search_above(|candidate| candidate > value)
+    }
+
+    /// returns the largest value that matches, matches has to hold for all values up to some point
+    fn search_below<F: Fn(&T) -> bool>(&self, matches: F) -> Option<&T> {
+        let mut link = &self.root;
+        let mut found = None;
+        while let Some(node) = link {
+            if matches(&node.value) {
+                found = Some(&node.value);
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
         }
+        found
     }
 
-    pub fn insert(&mut self, value: T) {
-        if let Some(root) = &mut self.root {
-            root.insert(value)
-        } else {
-            self.root = Some(BinarySearchTreeNode::new(value));
+    /// returns the smallest value that matches, matches has to hold for all values from some point on
+    fn search_above<F: Fn(&T) -> bool>(&self, matches: F) -> Option<&T> {
+        let mut link = &self.root;
+        let mut found = None;
+        while let Some(node) = link {
+            if matches(&node.value) {
+                found = Some(&node.value);
+                link = &node.left;
+            } else {
+                link = &node.right;
+            }
+        }
+        found
+    }
+
+    /// returns the number of nodes on the longest path from the root to a leaf
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut level: Vec<&BinarySearchTreeNode<T>> = self.root.iter().map(|root| root.as_ref()).collect();
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|node| node.left.iter().chain(node.right.iter()))
+                .map(|node| node.as_ref())
+                .collect();
         }
+        height
     }
 
+    /// returns the values in ascending order, keeping the path to the next value on a stack instead of recursing
     pub fn traverse_inorder(&self) -> Vec<&T> {
         let mut nodes = Vec::new();
-        if let Some(root) = &self.root {
-            root.traverse_inorder(&mut nodes);
+        let mut stack = Vec::new();
+        let mut link = &self.root;
+        loop {
+            while let Some(node) = link {
+                stack.push(node);
+                link = &node.left;
+            }
+            let Some(node) = stack.pop() else {
+                break;
+            };
+            nodes.push(&node.value);
+            link = &node.right;
         }
         nodes
     }
 
+    /// returns every value before the values of its subtrees, using a stack of subtrees still to visit
     pub fn traverse_preorder(&self) -> Vec<&T> {
         let mut nodes = Vec::new();
-        if let Some(root) = &self.root {
-            root.traverse_preorder(&mut nodes);
+        let mut stack: Vec<&BinarySearchTreeNode<T>> = self.root.iter().map(|root| root.as_ref()).collect();
+        while let Some(node) = stack.pop() {
+            nodes.push(&node.value);
+            // the right subtree goes on the stack first so the left one is visited first
+            stack.extend(node.right.as_deref());
+            stack.extend(node.left.as_deref());
         }
         nodes
     }
 
+    /// returns every value after the values of its subtrees
+    ///
+    /// Visiting node, right and then left subtree with a stack gives exactly the reversed postorder.
     pub fn traverse_postorder(&self) -> Vec<&T> {
         let mut nodes = Vec::new();
-        if let Some(root) = &self.root {
-            root.traverse_postorder(&mut nodes);
+        let mut stack: Vec<&BinarySearchTreeNode<T>> = self.root.iter().map(|root| root.as_ref()).collect();
+        while let Some(node) = stack.pop() {
+            nodes.push(&node.value);
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
         }
+        nodes.reverse();
         nodes
     }
 
@@ -126,6 +248,17 @@ impl<T: PartialOrd> BinarySearchTree<T> {
     }
 }
 
+/// Frees the nodes in a loop, dropping the boxes recursively would overflow the stack on deep trees
+impl<T: PartialOrd> Drop for BinarySearchTree<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<BinarySearchTreeNode<T>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
 #[test]
 pub fn insert_test() {
     let mut tree = BinarySearchTree::new();
@@ -156,3 +289,76 @@ pub fn traverse_test() {
     assert_eq!(tree.traverse_postorder().into_iter().copied().collect::<Vec<i32>>(), vec![25, 35, 30, 45, 60, 50, 40]);
     assert_eq!(tree.traverse_levelorder().into_iter().copied().collect::<Vec<i32>>(), vec![40, 30, 50, 25, 35, 45, 60]);
 }
+
+#[test]
+pub fn search_test() {
+    let mut tree = BinarySearchTree::new();
+    assert_eq!(tree.min(), None);
+    assert_eq!(tree.floor(&10), None);
+    assert_eq!(tree.height(), 0);
+    for value in [40, 30, 50, 25, 35, 45, 60] {
+        tree.insert(value);
+    }
+    assert_eq!(tree.len(), 7);
+    assert_eq!(tree.height(), 3);
+    assert!(tree.contains(&35));
+    assert!(!tree.contains(&36));
+    assert_eq!(tree.min(), Some(&25));
+    assert_eq!(tree.max(), Some(&60));
+    assert_eq!(tree.floor(&44), Some(&40));
+    assert_eq!(tree.floor(&45), Some(&45));
+    assert_eq!(tree.floor(&24), None);
+    assert_eq!(tree.ceiling(&36), Some(&40));
+    assert_eq!(tree.ceiling(&35), Some(&35));
+    assert_eq!(tree.ceiling(&61), None);
+    assert_eq!(tree.successor(&35), Some(&40));
+    assert_eq!(tree.successor(&60), None);
+    assert_eq!(tree.predecessor(&45), Some(&40));
+    assert_eq!(tree.predecessor(&25), None);
+}
+
+#[test]
+pub fn remove_test() {
+    let mut tree = BinarySearchTree::new();
+    for value in [40, 30, 50, 25, 35, 45, 60, 55, 65, 52] {
+        tree.insert(value);
+    }
+    // a leaf, a node with one child and nodes with two children, whose successor is and is not their right child
+    assert_eq!(tree.remove(&25), Some(25));
+    assert_eq!(tree.remove(&30), Some(30));
+    assert_eq!(tree.remove(&60), Some(60));
+    assert_eq!(tree.remove(&40), Some(40));
+    assert_eq!(tree.remove(&40), None);
+    assert_eq!(tree.traverse_inorder().into_iter().copied().collect::<Vec<i32>>(), vec![35, 45, 50, 52, 55, 65]);
+    assert_eq!(tree.traverse_preorder().into_iter().copied().collect::<Vec<i32>>(), vec![45, 35, 50, 65, 55, 52]);
+    assert_eq!(tree.len(), 6);
+
+    tree.insert(50);
+    assert_eq!(tree.remove(&50), Some(50));
+    assert!(tree.contains(&50));
+    for value in [35, 45, 50, 52, 55, 65] {
+        assert_eq!(tree.remove(&value), Some(value));
+    }
+    assert!(tree.is_empty());
+    assert_eq!(tree.min(), None);
+}
+
+#[test]
+pub fn deep_tree_test() {
+    // sorted input turns the tree into a list, which must not overflow the stack
+    let mut tree = BinarySearchTree::new();
+    for value in 0..30_000 {
+        tree.insert(value);
+    }
+    assert_eq!(tree.len(), 30_000);
+    assert_eq!(tree.height(), 30_000);
+    assert!(tree.contains(&29_999));
+    assert_eq!(tree.max(), Some(&29_999));
+    assert_eq!(tree.floor(&1_000_000), Some(&29_999));
+    assert!(tree.traverse_inorder().into_iter().copied().eq(0..30_000));
+    assert!(tree.traverse_preorder().into_iter().copied().eq(0..30_000));
+    assert!(tree.traverse_postorder().into_iter().copied().eq((0..30_000).rev()));
+    assert_eq!(tree.traverse_levelorder().len(), 30_000);
+    assert_eq!(tree.remove(&0), Some(0));
+    assert_eq!(tree.min(), Some(&1));
+}
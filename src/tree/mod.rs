@@ -1 +1,5 @@
-pub mod binary_search_tree;
\ No newline at end of file
+pub mod binary_search_tree;
+
+pub mod avl_tree;
+
+pub mod red_black_tree;
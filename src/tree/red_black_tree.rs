@@ -0,0 +1,791 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// marks a missing child or parent, NIL leaves count as black
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Red,
+    Black,
+}
+
+struct RbTreeNode<K, V> {
+    key: K,
+    value: V,
+    color: Color,
+    parent: usize,
+    left: usize,
+    right: usize,
+}
+
+/// An ordered map stored in a red-black tree
+///
+/// Every node is red or black, red nodes only have black children and every path from a node down to
+/// a leaf passes the same number of black nodes, so no path is more than twice as long as another and
+/// all operations take logarithmic time. The nodes link to their children and parent by index into
+/// a vector of slots, which lets the iterators walk the tree in both directions without a stack.
+pub struct RbTreeMap<K, V> {
+    slots: Vec<Option<RbTreeNode<K, V>>>,
+    /// slots of removed nodes that new nodes can take
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+}
+
+impl<K, V> RbTreeMap<K, V> {
+    fn node(&self, index: usize) -> &RbTreeNode<K, V> {
+        self.slots[index].as_ref().expect("index to refer to a live node")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut RbTreeNode<K, V> {
+        self.slots[index].as_mut().expect("index to refer to a live node")
+    }
+
+    fn color(&self, index: usize) -> Color {
+        if index == NIL {
+            Color::Black
+        } else {
+            self.node(index).color
+        }
+    }
+
+    fn parent(&self, index: usize) -> usize {
+        self.node(index).parent
+    }
+
+    fn left(&self, index: usize) -> usize {
+        self.node(index).left
+    }
+
+    fn right(&self, index: usize) -> usize {
+        self.node(index).right
+    }
+
+    fn set_parent(&mut self, index: usize, parent: usize) {
+        if index != NIL {
+            self.node_mut(index).parent = parent;
+        }
+    }
+
+    /// returns the leftmost node below index
+    fn minimum(&self, mut index: usize) -> usize {
+        while self.left(index) != NIL {
+            index = self.left(index);
+        }
+        index
+    }
+
+    /// returns the rightmost node below index
+    fn maximum(&self, mut index: usize) -> usize {
+        while self.right(index) != NIL {
+            index = self.right(index);
+        }
+        index
+    }
+
+    /// returns the node after index in key order
+    fn successor(&self, mut index: usize) -> usize {
+        if self.right(index) != NIL {
+            return self.minimum(self.right(index));
+        }
+        let mut parent = self.parent(index);
+        while parent != NIL && index == self.right(parent) {
+            index = parent;
+            parent = self.parent(parent);
+        }
+        parent
+    }
+
+    /// returns the node before index in key order
+    fn predecessor(&self, mut index: usize) -> usize {
+        if self.left(index) != NIL {
+            return self.maximum(self.left(index));
+        }
+        let mut parent = self.parent(index);
+        while parent != NIL && index == self.left(parent) {
+            index = parent;
+            parent = self.parent(parent);
+        }
+        parent
+    }
+
+    /// makes child take the place of node below the parent of node
+    fn replace_child(&mut self, node: usize, child: usize) {
+        let parent = self.parent(node);
+        if parent == NIL {
+            self.root = child;
+        } else if node == self.left(parent) {
+            self.node_mut(parent).left = child;
+        } else {
+            self.node_mut(parent).right = child;
+        }
+        self.set_parent(child, parent);
+    }
+
+    /// lifts the right child of index above it
+    fn rotate_left(&mut self, index: usize) {
+        let right = self.right(index);
+        let middle = self.left(right);
+        self.node_mut(index).right = middle;
+        self.set_parent(middle, index);
+        self.replace_child(index, right);
+        self.node_mut(right).left = index;
+        self.node_mut(index).parent = right;
+    }
+
+    /// lifts the left child of index above it
+    fn rotate_right(&mut self, index: usize) {
+        let left = self.left(index);
+        let middle = self.right(left);
+        self.node_mut(index).left = middle;
+        self.set_parent(middle, index);
+        self.replace_child(index, left);
+        self.node_mut(left).right = index;
+        self.node_mut(index).parent = left;
+    }
+
+    /// repairs a red node whose parent may be red as well
+    fn fix_insert(&mut self, mut node: usize) {
+        while self.color(self.parent(node)) == Color::Red {
+            let mut parent = self.parent(node);
+            // a red parent is never the root, so the grandparent exists
+            let grandparent = self.parent(parent);
+            let parent_is_left = parent == self.left(grandparent);
+            let uncle = if parent_is_left { self.right(grandparent) } else { self.left(grandparent) };
+            if self.color(uncle) == Color::Red {
+                // recolouring moves the problem two levels up
+                self.node_mut(parent).color = Color::Black;
+                self.node_mut(uncle).color = Color::Black;
+                self.node_mut(grandparent).color = Color::Red;
+                node = grandparent;
+                continue;
+            }
+            if parent_is_left {
+                if node == self.right(parent) {
+                    self.rotate_left(parent);
+                    node = parent;
+                    parent = self.parent(node);
+                }
+                self.rotate_right(grandparent);
+            } else {
+                if node == self.left(parent) {
+                    self.rotate_right(parent);
+                    node = parent;
+                    parent = self.parent(node);
+                }
+                self.rotate_left(grandparent);
+            }
+            self.node_mut(parent).color = Color::Black;
+            self.node_mut(grandparent).color = Color::Red;
+        }
+        let root = self.root;
+        self.node_mut(root).color = Color::Black;
+    }
+
+    /// repairs the tree after a black node was removed above node, whose paths now miss one black node
+    ///
+    /// node may be NIL, so its parent is passed along.
+    fn fix_remove(&mut self, mut node: usize, mut parent: usize) {
+        while node != self.root && self.color(node) == Color::Black {
+            let node_is_left = node == self.left(parent);
+            let mut sibling = if node_is_left { self.right(parent) } else { self.left(parent) };
+            if self.color(sibling) == Color::Red {
+                self.node_mut(sibling).color = Color::Black;
+                self.node_mut(parent).color = Color::Red;
+                if node_is_left {
+                    self.rotate_left(parent);
+                    sibling = self.right(parent);
+                } else {
+                    self.rotate_right(parent);
+                    sibling = self.left(parent);
+                }
+            }
+            let (near, far) = if node_is_left {
+                (self.left(sibling), self.right(sibling))
+            } else {
+                (self.right(sibling), self.left(sibling))
+            };
+            if self.color(near) == Color::Black && self.color(far) == Color::Black {
+                // the sibling gives up a black node too, which moves the missing black node up
+                self.node_mut(sibling).color = Color::Red;
+                node = parent;
+                parent = self.parent(node);
+                continue;
+            }
+            if self.color(far) == Color::Black {
+                self.node_mut(near).color = Color::Black;
+                self.node_mut(sibling).color = Color::Red;
+                if node_is_left {
+                    self.rotate_right(sibling);
+                    sibling = self.right(parent);
+                } else {
+                    self.rotate_left(sibling);
+                    sibling = self.left(parent);
+                }
+            }
+            let far = if node_is_left { self.right(sibling) } else { self.left(sibling) };
+            self.node_mut(sibling).color = self.color(parent);
+            self.node_mut(parent).color = Color::Black;
+            self.node_mut(far).color = Color::Black;
+            if node_is_left {
+                self.rotate_left(parent);
+            } else {
+                self.rotate_right(parent);
+            }
+            node = self.root;
+        }
+        if node != NIL {
+            self.node_mut(node).color = Color::Black;
+        }
+    }
+
+    /// unlinks the node from the tree and returns its key and value
+    fn remove_node(&mut self, node: usize) -> (K, V) {
+        let mut removed_color = self.color(node);
+        let child;
+        let child_parent;
+        if self.left(node) == NIL {
+            child = self.right(node);
+            child_parent = self.parent(node);
+            self.replace_child(node, child);
+        } else if self.right(node) == NIL {
+            child = self.left(node);
+            child_parent = self.parent(node);
+            self.replace_child(node, child);
+        } else {
+            // the in-order successor has no left child, it takes the place and colour of the node
+            let successor = self.minimum(self.right(node));
+            removed_color = self.color(successor);
+            child = self.right(successor);
+            if self.parent(successor) == node {
+                child_parent = successor;
+            } else {
+                child_parent = self.parent(successor);
+                self.replace_child(successor, child);
+                let right = self.right(node);
+                self.node_mut(successor).right = right;
+                self.set_parent(right, successor);
+            }
+            self.replace_child(node, successor);
+            let left = self.left(node);
+            self.node_mut(successor).left = left;
+            self.set_parent(left, successor);
+            self.node_mut(successor).color = self.color(node);
+        }
+        if removed_color == Color::Black {
+            self.fix_remove(child, child_parent);
+        }
+        self.len -= 1;
+        self.free.push(node);
+        let node = self.slots[node].take().expect("index to refer to a live node");
+        (node.key, node.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+        self.root = NIL;
+        self.len = 0;
+    }
+
+    /// returns the entry with the smallest key
+    pub fn first(&self) -> Option<(&K, &V)> {
+        (self.root != NIL).then(|| self.entry(self.minimum(self.root)))
+    }
+
+    /// returns the entry with the largest key
+    pub fn last(&self) -> Option<(&K, &V)> {
+        (self.root != NIL).then(|| self.entry(self.maximum(self.root)))
+    }
+
+    fn entry(&self, index: usize) -> (&K, &V) {
+        let node = self.node(index);
+        (&node.key, &node.value)
+    }
+
+    /// returns an iterator over the entries in key order, it can be walked from both ends
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let (front, back) = if self.root == NIL {
+            (NIL, NIL)
+        } else {
+            (self.minimum(self.root), self.maximum(self.root))
+        };
+        Iter {
+            map: self,
+            front,
+            back,
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Ord, V> RbTreeMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+        }
+    }
+
+    /// returns the index of the node with the key, or NIL
+    fn find<Q: Ord + ?Sized>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+    {
+        let mut index = self.root;
+        while index != NIL {
+            let node = self.node(index);
+            match key.cmp(node.key.borrow()) {
+                Ordering::Less => index = node.left,
+                Ordering::Greater => index = node.right,
+                Ordering::Equal => break,
+            }
+        }
+        index
+    }
+
+    /// inserts a key-value pair, returning the value previously stored for the key
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = NIL;
+        let mut index = self.root;
+        let mut ordering = Ordering::Equal;
+        while index != NIL {
+            parent = index;
+            let node = self.node_mut(index);
+            ordering = key.cmp(&node.key);
+            match ordering {
+                Ordering::Less => index = node.left,
+                Ordering::Greater => index = node.right,
+                Ordering::Equal => return Some(std::mem::replace(&mut node.value, value)),
+            }
+        }
+        let node = RbTreeNode { key, value, color: Color::Red, parent, left: NIL, right: NIL };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Some(node));
+                self.slots.len() - 1
+            }
+        };
+        match ordering {
+            _ if parent == NIL => self.root = index,
+            Ordering::Less => self.node_mut(parent).left = index,
+            _ => self.node_mut(parent).right = index,
+        }
+        self.len += 1;
+        self.fix_insert(index);
+        #[cfg(all(test, debug_assertions))]
+        self.verify();
+        None
+    }
+
+    /// removes a key from the map, returning its key and value
+    pub fn remove_entry<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.find(key);
+        if index == NIL {
+            return None;
+        }
+        let entry = self.remove_node(index);
+        #[cfg(all(test, debug_assertions))]
+        self.verify();
+        Some(entry)
+    }
+
+    /// removes a key from the map, returning its value
+    pub fn remove<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.find(key);
+        (index != NIL).then(|| &self.node(index).value)
+    }
+
+    pub fn get_mut<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.find(key);
+        (index != NIL).then(|| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.find(key) != NIL
+    }
+
+    /// removes the entry with the smallest key
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.root == NIL {
+            return None;
+        }
+        let entry = self.remove_node(self.minimum(self.root));
+        #[cfg(all(test, debug_assertions))]
+        self.verify();
+        Some(entry)
+    }
+
+    /// removes the entry with the largest key
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.root == NIL {
+            return None;
+        }
+        let entry = self.remove_node(self.maximum(self.root));
+        #[cfg(all(test, debug_assertions))]
+        self.verify();
+        Some(entry)
+    }
+
+    /// checks the order of the keys, the links between nodes and the colour rules, returning the black height
+    ///
+    /// Tests built with debug assertions run it after every change to the tree.
+    #[cfg(test)]
+    fn verify(&self) -> usize {
+        fn check<K: Ord, V>(map: &RbTreeMap<K, V>, index: usize, parent: usize, count: &mut usize) -> usize {
+            if index == NIL {
+                return 1;
+            }
+            *count += 1;
+            let node = map.node(index);
+            assert_eq!(node.parent, parent, "a node does not link back to its parent");
+            if node.color == Color::Red {
+                assert_eq!(map.color(node.left), Color::Black, "a red node has a red left child");
+                assert_eq!(map.color(node.right), Color::Black, "a red node has a red right child");
+            }
+            if node.left != NIL {
+                assert!(map.node(node.left).key < node.key, "a left child is not smaller than its parent");
+            }
+            if node.right != NIL {
+                assert!(map.node(node.right).key > node.key, "a right child is not larger than its parent");
+            }
+            let left = check(map, node.left, index, count);
+            let right = check(map, node.right, index, count);
+            assert_eq!(left, right, "the paths below a node pass different numbers of black nodes");
+            left + (node.color == Color::Black) as usize
+        }
+        assert_eq!(self.color(self.root), Color::Black, "the root is red");
+        let mut count = 0;
+        let black_height = check(self, self.root, NIL, &mut count);
+        assert_eq!(count, self.len, "the length does not match the number of nodes");
+        // the ordering checks above only compare neighbours, the in-order walk compares everything
+        assert!(self.keys().zip(self.keys().skip(1)).all(|(a, b)| a < b), "the keys are out of order");
+        black_height
+    }
+}
+
+impl<K: Ord, V> Default for RbTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for RbTreeMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a RbTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [RbTreeMap] in key order
+pub struct Iter<'a, K, V> {
+    map: &'a RbTreeMap<K, V>,
+    front: usize,
+    back: usize,
+    /// the number of entries between front and back, which tells when the two ends met
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.front;
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.front = self.map.successor(index);
+        }
+        Some(self.map.entry(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.back;
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.back = self.map.predecessor(index);
+        }
+        Some(self.map.entry(index))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// An ordered set stored in a red-black tree, see [RbTreeMap]
+pub struct RbTreeSet<T> {
+    map: RbTreeMap<T, ()>,
+}
+
+impl<T: Ord> RbTreeSet<T> {
+    pub fn new() -> Self {
+        Self { map: RbTreeMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// inserts the value, returns false if it is already in the set
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.map.contains_key(&value) {
+            return false;
+        }
+        self.map.insert(value, ());
+        true
+    }
+
+    /// removes the value from the set and returns it
+    pub fn remove<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+    {
+        self.map.remove_entry(value).map(|(value, _)| value)
+    }
+
+    /// returns the value in the set that is equal to value
+    pub fn get<Q: Ord + ?Sized>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+    {
+        let index = self.map.find(value);
+        (index != NIL).then(|| &self.map.node(index).key)
+    }
+
+    pub fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.map.first().map(|(value, _)| value)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.map.last().map(|(value, _)| value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.map.pop_first().map(|(value, _)| value)
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.map.pop_last().map(|(value, _)| value)
+    }
+
+    /// returns an iterator over the values in ascending order, it can be walked from both ends
+    pub fn iter(&self) -> SetIter<'_, T> {
+        SetIter { iter: self.map.iter() }
+    }
+}
+
+impl<T: Ord> Default for RbTreeSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RbTreeSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a RbTreeSet<T> {
+    type Item = &'a T;
+    type IntoIter = SetIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the values of a [RbTreeSet] in ascending order
+pub struct SetIter<'a, T> {
+    iter: Iter<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for SetIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(value, _)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for SetIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(value, _)| value)
+    }
+}
+
+impl<T> ExactSizeIterator for SetIter<'_, T> {}
+
+#[test]
+pub fn rb_tree_map_test() {
+    let mut map = RbTreeMap::new();
+    assert_eq!(map.first(), None);
+    assert_eq!(map.iter().next_back(), None);
+    for key in 0..1000 {
+        assert_eq!(map.insert(key, key * 2), None);
+    }
+    assert_eq!(map.insert(7, 0), Some(14));
+    assert_eq!(map.len(), 1000);
+    // b black nodes on every path need at least 2^b - 1 nodes, so 1000 keys allow 9 plus the NIL leaf
+    assert!(map.verify() <= 10);
+    assert_eq!(map.first(), Some((&0, &0)));
+    assert_eq!(map.last(), Some((&999, &1998)));
+    assert_eq!(map.get(&7), Some(&0));
+    *map.get_mut(&7).unwrap() = 14;
+    assert!(map.iter().map(|(key, value)| (*key, *value)).eq((0..1000).map(|key| (key, key * 2))));
+    assert!(map.keys().rev().copied().eq((0..1000).rev()));
+
+    // the two ends of an iterator meet in the middle
+    let mut iter = map.iter();
+    assert_eq!(iter.next(), Some((&0, &0)));
+    assert_eq!(iter.next_back(), Some((&999, &1998)));
+    assert_eq!(iter.len(), 998);
+    assert_eq!(iter.by_ref().rev().take(997).count(), 997);
+    assert_eq!(iter.next(), Some((&1, &2)));
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(map.remove(&500), Some(1000));
+    assert_eq!(map.remove(&500), None);
+    assert!(!map.contains_key(&500));
+    assert_eq!(map.pop_first(), Some((0, 0)));
+    assert_eq!(map.pop_last(), Some((999, 1998)));
+    for key in (0..1000).step_by(3) {
+        map.remove(&key);
+    }
+    // the multiples of 3 include 0 and 999, which are gone already
+    assert_eq!(map.len(), 1000 - 3 - 332);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.insert(1, 1), None);
+}
+
+#[test]
+pub fn rb_tree_set_test() {
+    let mut set: RbTreeSet<String> = ["pear", "apple", "fig"].into_iter().map(String::from).collect();
+    assert!(!set.insert("fig".to_string()));
+    assert!(set.contains("apple"));
+    assert_eq!(set.get("pear").map(String::as_str), Some("pear"));
+    assert_eq!(set.first().map(String::as_str), Some("apple"));
+    assert_eq!(set.last().map(String::as_str), Some("pear"));
+    assert_eq!(set.iter().rev().map(String::as_str).collect::<Vec<_>>(), vec!["pear", "fig", "apple"]);
+    assert_eq!(set.remove("fig"), Some("fig".to_string()));
+    assert_eq!(set.remove("fig"), None);
+    assert_eq!(set.pop_last(), Some("pear".to_string()));
+    assert_eq!(set.pop_first(), Some("apple".to_string()));
+    assert_eq!(set.pop_first(), None);
+    assert!(set.is_empty());
+}
+
+#[test]
+pub fn rb_tree_random_test() {
+    use std::collections::BTreeMap;
+
+    let mut map = RbTreeMap::new();
+    let mut expected = BTreeMap::new();
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for i in 0..20_000u32 {
+        // xorshift64, deterministic so failures can be reproduced
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let key = (state % 400) as u32;
+        // every insert and remove verifies the tree on its own
+        match (state >> 32) % 5 {
+            0 | 1 => assert_eq!(map.remove(&key), expected.remove(&key), "remove {} in operation {}", key, i),
+            2 => assert_eq!(map.pop_first(), expected.pop_first(), "pop_first in operation {}", i),
+            _ => assert_eq!(map.insert(key, i), expected.insert(key, i), "insert {} in operation {}", key, i),
+        }
+        assert_eq!(map.len(), expected.len());
+    }
+    assert!(map.iter().eq(expected.iter()));
+    assert!(map.iter().rev().eq(expected.iter().rev()));
+    assert_eq!(map.first(), expected.first_key_value());
+    assert_eq!(map.last(), expected.last_key_value());
+}